@@ -0,0 +1,589 @@
+//! Ready-made definitions for the AArch64 system registers.
+//!
+//! Every register has its own module, which contains the register type, that implements
+//! [`RegisterRead`] and [`RegisterWrite`] using the `mrs` and `msr` instructions, and the
+//! API that is generated by [`define_cpu_register`](crate::define_cpu_register).
+//! Registers which only hold a single value, like `VBAR_EL1`, only have plain `read` and
+//! `write` functions.
+//!
+//! Fields that hold a plain number, like `TCR_EL1.T0SZ`, are not defined, and
+//! can be accessed using the raw `get` and `set` functions.
+//!
+//! # Other targets
+//!
+//! System registers can only be accessed on AArch64. On other targets, every register
+//! is a mock register from the `cpu::mock` module if the `std` feature is enabled,
+//! so code that uses them can be tested on the host.
+//!
+//! Alternatively, a [`Backend`] can be installed using [`set_backend`], which then receives
+//! every access to the system registers, for example to forward them to an emulator.
+//! The backend only exists on other targets, so on AArch64 every access goes straight
+//! to the hardware. If neither a backend is installed nor the `std` feature is enabled,
+//! accessing a register on another target panics.
+//!
+//! This module is only available with the `aarch64` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rumio::aarch64::{current_el, daif, sctlr_el1};
+//!
+//! assert_eq!(current_el::EL::get(), Some(current_el::ExceptionLevel::El1));
+//!
+//! daif::I::set(true);
+//! sctlr_el1::M::set(true);
+//! ```
+
+use crate::cpu::{RegisterRead, RegisterWrite};
+#[cfg(not(target_arch = "aarch64"))]
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A replacement for the hardware system registers on other targets.
+///
+/// The registers are identified by their architectural name, like `SCTLR_EL1`.
+#[cfg(not(target_arch = "aarch64"))]
+pub trait Backend: Sync {
+    /// Read the value of the given register.
+    fn read(&self, register: &'static str) -> u64;
+
+    /// Write the given value into the register.
+    fn write(&self, register: &'static str, val: u64);
+}
+
+/// The error that is returned by [`set_backend`] if a backend was already installed.
+#[cfg(not(target_arch = "aarch64"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SetBackendError;
+
+#[cfg(not(target_arch = "aarch64"))]
+impl fmt::Display for SetBackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a system register backend was already installed")
+    }
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+struct NoBackend;
+
+#[cfg(not(target_arch = "aarch64"))]
+impl Backend for NoBackend {
+    fn read(&self, _: &'static str) -> u64 {
+        unreachable!()
+    }
+
+    fn write(&self, _: &'static str, _: u64) {
+        unreachable!()
+    }
+}
+
+#[cfg(not(target_arch = "aarch64"))]
+const UNINITIALIZED: usize = 0;
+#[cfg(not(target_arch = "aarch64"))]
+const INITIALIZING: usize = 1;
+#[cfg(not(target_arch = "aarch64"))]
+const INITIALIZED: usize = 2;
+
+#[cfg(not(target_arch = "aarch64"))]
+static STATE: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
+#[cfg(not(target_arch = "aarch64"))]
+static mut BACKEND: &dyn Backend = &NoBackend;
+
+/// Install the global backend that will receive all system register accesses.
+///
+/// This function can only be called once, every call after the
+/// first one will return an error.
+///
+/// On targets without atomic compare-and-swap, like `thumbv6m-none-eabi`,
+/// the state is checked and set using a separate load and store, so calls
+/// of this function must not race with each other, e.g. from an interrupt handler.
+///
+/// # Example
+///
+/// ```
+/// use rumio::aarch64::{daif, set_backend, Backend};
+///
+/// struct Emulator;
+///
+/// impl Backend for Emulator {
+///     fn read(&self, register: &'static str) -> u64 {
+///         match register {
+///             "DAIF" => 0b1111 << 6,
+///             _ => 0,
+///         }
+///     }
+///
+///     fn write(&self, register: &'static str, val: u64) {
+///         println!("{} = {:#x}", register, val);
+///     }
+/// }
+///
+/// set_backend(&Emulator).unwrap();
+/// assert!(set_backend(&Emulator).is_err());
+/// assert!(daif::I::get());
+/// ```
+#[cfg(not(target_arch = "aarch64"))]
+pub fn set_backend(backend: &'static dyn Backend) -> Result<(), SetBackendError> {
+    #[cfg(target_has_atomic = "ptr")]
+    let claimed = STATE
+        .compare_exchange(
+            UNINITIALIZED,
+            INITIALIZING,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        )
+        .is_ok();
+
+    #[cfg(not(target_has_atomic = "ptr"))]
+    let claimed = {
+        let claimed = STATE.load(Ordering::Acquire) == UNINITIALIZED;
+        if claimed {
+            STATE.store(INITIALIZING, Ordering::Relaxed);
+        }
+        claimed
+    };
+
+    if !claimed {
+        return Err(SetBackendError);
+    }
+
+    // SAFETY: the state guarantees that we are the only one writing the backend,
+    // and nobody will read it until the state is `INITIALIZED`.
+    unsafe { BACKEND = backend };
+    STATE.store(INITIALIZED, Ordering::Release);
+    Ok(())
+}
+
+/// Return the currently installed backend, if there is one.
+#[cfg(not(target_arch = "aarch64"))]
+pub fn backend() -> Option<&'static dyn Backend> {
+    if STATE.load(Ordering::Acquire) == INITIALIZED {
+        // SAFETY: the backend is never written again after the state is `INITIALIZED`.
+        Some(unsafe { BACKEND })
+    } else {
+        None
+    }
+}
+
+/// Define the register type for a system register, and implement the CPU register traits.
+macro_rules! sysreg {
+    ($name:ident, $reg:literal) => {
+        sysreg!($name, $reg, r);
+
+        impl RegisterWrite<u64> for $name {
+            #[inline]
+            fn write(val: u64) {
+                #[cfg(target_arch = "aarch64")]
+                unsafe {
+                    ::core::arch::asm!(
+                        ::core::concat!("msr ", $reg, ", {}"),
+                        in(reg) val,
+                        options(nostack, preserves_flags)
+                    );
+                }
+
+                #[cfg(not(target_arch = "aarch64"))]
+                match backend() {
+                    Some(backend) => backend.write($reg, val),
+                    None => sysreg!(@mock $reg, mock_write(val)),
+                }
+            }
+
+            #[inline]
+            fn set(mask: u64) {
+                crate::impl_cpu_set!(Self, mask);
+            }
+
+            #[inline]
+            fn clear(mask: u64) {
+                crate::impl_cpu_clear!(Self, mask);
+            }
+        }
+    };
+
+    ($name:ident, $reg:literal, r) => {
+        #[doc = ::core::concat!("The `", $reg, "` system register.")]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name;
+
+        #[cfg(all(not(target_arch = "aarch64"), feature = "std"))]
+        crate::define_mock_register!(@state $name: u64 = 0);
+
+        impl RegisterRead<u64> for $name {
+            #[inline]
+            fn read() -> u64 {
+                #[cfg(target_arch = "aarch64")]
+                unsafe {
+                    let val: u64;
+                    ::core::arch::asm!(
+                        ::core::concat!("mrs {}, ", $reg),
+                        out(reg) val,
+                        options(nomem, nostack, preserves_flags)
+                    );
+                    val
+                }
+
+                #[cfg(not(target_arch = "aarch64"))]
+                match backend() {
+                    Some(backend) => backend.read($reg),
+                    None => sysreg!(@mock $reg, mock_read()),
+                }
+            }
+        }
+    };
+
+    (@mock $reg:literal, $op:ident($($arg:expr)?)) => {{
+        #[cfg(feature = "std")]
+        {
+            <Self as crate::cpu::mock::Mock>::$op($($arg)?)
+        }
+
+        #[cfg(not(feature = "std"))]
+        {
+            $(let _ = $arg;)?
+            panic!(
+                "the `{}` register can only be accessed on AArch64, using a backend, or with the `std` feature",
+                $reg
+            )
+        }
+    }};
+}
+
+/// Generate plain `read` and `write` functions for a system register without fields.
+macro_rules! plain {
+    ($name:ident) => {
+        plain!($name, r);
+
+        /// Write the raw value into this register.
+        #[inline]
+        pub fn write(val: u64) {
+            crate::cpu::__write::<$name, u64>(val, ::core::stringify!($name), None);
+        }
+    };
+
+    ($name:ident, r) => {
+        /// Read the raw value of this register.
+        #[inline]
+        pub fn read() -> u64 {
+            crate::cpu::__read::<$name, u64>(::core::stringify!($name), None)
+        }
+    };
+}
+
+pub mod sctlr_el1 {
+    //! The system control register for EL1 and EL0, which controls the MMU and caches.
+
+    use super::*;
+
+    sysreg!(SctlrEl1, "SCTLR_EL1");
+
+    crate::define_cpu_register! { SctlrEl1 as u64 =>
+        /// Enable the MMU for EL1 and EL0.
+        rw M: 0,
+        /// Enable alignment checks.
+        rw A: 1,
+        /// Enable the data cache.
+        rw C: 2,
+        /// Enable stack alignment checks for EL1.
+        rw SA: 3,
+        /// Enable stack alignment checks for EL0.
+        rw SA0: 4,
+        /// Enable the instruction cache.
+        rw I: 12,
+        /// Allow `dc zva` in EL0.
+        rw DZE: 14,
+        /// Allow EL0 to access `CTR_EL0`.
+        rw UCT: 15,
+        /// Don't trap `wfi` in EL0.
+        rw NTWI: 16,
+        /// Don't trap `wfe` in EL0.
+        rw NTWE: 18,
+        /// Make writable memory never executable.
+        rw WXN: 19,
+        /// Don't set `PSTATE.PAN` on exceptions to EL1.
+        rw SPAN: 23,
+        /// Use big-endian data accesses in EL0.
+        rw E0E: 24,
+        /// Use big-endian data accesses in EL1, and for translation table walks.
+        rw EE: 25,
+        /// Allow cache maintenance instructions in EL0.
+        rw UCI: 26,
+    }
+}
+
+pub mod tcr_el1 {
+    //! The translation control register for EL1 and EL0.
+
+    use super::*;
+
+    sysreg!(TcrEl1, "TCR_EL1");
+
+    crate::define_cpu_register! { TcrEl1 as u64 =>
+        /// Disable translation table walks using `TTBR0_EL1`.
+        rw EPD0: 7,
+        /// Inner cacheability of table walks using `TTBR0_EL1`.
+        rw IRGN0: 8..9 =
+            /// The inner cacheability of table walks using `TTBR0_EL1`.
+            enum InnerCacheability0 [
+                /// Non-cacheable.
+                NonCacheable = 0b00,
+                /// Write-back, read-allocate and write-allocate.
+                WriteBackAllocate = 0b01,
+                /// Write-through, read-allocate and no write-allocate.
+                WriteThrough = 0b10,
+                /// Write-back, read-allocate and no write-allocate.
+                WriteBackNoAllocate = 0b11,
+            ],
+        /// Outer cacheability of table walks using `TTBR0_EL1`.
+        rw ORGN0: 10..11 =
+            /// The outer cacheability of table walks using `TTBR0_EL1`.
+            enum OuterCacheability0 [
+                /// Non-cacheable.
+                NonCacheable = 0b00,
+                /// Write-back, read-allocate and write-allocate.
+                WriteBackAllocate = 0b01,
+                /// Write-through, read-allocate and no write-allocate.
+                WriteThrough = 0b10,
+                /// Write-back, read-allocate and no write-allocate.
+                WriteBackNoAllocate = 0b11,
+            ],
+        /// Shareability of table walks using `TTBR0_EL1`.
+        rw SH0: 12..13 =
+            /// The shareability of table walks using `TTBR0_EL1`.
+            enum Shareability0 [
+                /// Non-shareable.
+                NonShareable = 0b00,
+                /// Outer shareable.
+                OuterShareable = 0b10,
+                /// Inner shareable.
+                InnerShareable = 0b11,
+            ],
+        /// Granule size of `TTBR0_EL1`.
+        rw TG0: 14..15 =
+            /// The granule size of `TTBR0_EL1`.
+            enum Granule0 [
+                /// 4KiB granules.
+                Kb4 = 0b00,
+                /// 64KiB granules.
+                Kb64 = 0b01,
+                /// 16KiB granules.
+                Kb16 = 0b10,
+            ],
+        /// Use the ASID of `TTBR1_EL1` instead of `TTBR0_EL1`.
+        rw A1: 22,
+        /// Disable translation table walks using `TTBR1_EL1`.
+        rw EPD1: 23,
+        /// Inner cacheability of table walks using `TTBR1_EL1`.
+        rw IRGN1: 24..25 =
+            /// The inner cacheability of table walks using `TTBR1_EL1`.
+            enum InnerCacheability1 [
+                /// Non-cacheable.
+                NonCacheable = 0b00,
+                /// Write-back, read-allocate and write-allocate.
+                WriteBackAllocate = 0b01,
+                /// Write-through, read-allocate and no write-allocate.
+                WriteThrough = 0b10,
+                /// Write-back, read-allocate and no write-allocate.
+                WriteBackNoAllocate = 0b11,
+            ],
+        /// Outer cacheability of table walks using `TTBR1_EL1`.
+        rw ORGN1: 26..27 =
+            /// The outer cacheability of table walks using `TTBR1_EL1`.
+            enum OuterCacheability1 [
+                /// Non-cacheable.
+                NonCacheable = 0b00,
+                /// Write-back, read-allocate and write-allocate.
+                WriteBackAllocate = 0b01,
+                /// Write-through, read-allocate and no write-allocate.
+                WriteThrough = 0b10,
+                /// Write-back, read-allocate and no write-allocate.
+                WriteBackNoAllocate = 0b11,
+            ],
+        /// Shareability of table walks using `TTBR1_EL1`.
+        rw SH1: 28..29 =
+            /// The shareability of table walks using `TTBR1_EL1`.
+            enum Shareability1 [
+                /// Non-shareable.
+                NonShareable = 0b00,
+                /// Outer shareable.
+                OuterShareable = 0b10,
+                /// Inner shareable.
+                InnerShareable = 0b11,
+            ],
+        /// Granule size of `TTBR1_EL1`.
+        rw TG1: 30..31 =
+            /// The granule size of `TTBR1_EL1`.
+            enum Granule1 [
+                /// 16KiB granules.
+                Kb16 = 0b01,
+                /// 4KiB granules.
+                Kb4 = 0b10,
+                /// 64KiB granules.
+                Kb64 = 0b11,
+            ],
+        /// Intermediate physical address size.
+        rw IPS: 32..34 =
+            /// The size of intermediate physical addresses.
+            enum PhysicalAddressSize [
+                /// 32 bits, 4GiB.
+                Bits32 = 0b000,
+                /// 36 bits, 64GiB.
+                Bits36 = 0b001,
+                /// 40 bits, 1TiB.
+                Bits40 = 0b010,
+                /// 42 bits, 4TiB.
+                Bits42 = 0b011,
+                /// 44 bits, 16TiB.
+                Bits44 = 0b100,
+                /// 48 bits, 256TiB.
+                Bits48 = 0b101,
+                /// 52 bits, 4PiB.
+                Bits52 = 0b110,
+            ],
+        /// Use 16-bit ASIDs.
+        rw AS: 36,
+        /// Ignore the top byte of addresses in `TTBR0_EL1`.
+        rw TBI0: 37,
+        /// Ignore the top byte of addresses in `TTBR1_EL1`.
+        rw TBI1: 38,
+        /// Update the access flag in hardware.
+        rw HA: 39,
+        /// Update the dirty state in hardware.
+        rw HD: 40,
+    }
+}
+
+pub mod mair_el1 {
+    //! The memory attribute indirection register for EL1, which holds eight 8-bit attributes.
+
+    use super::*;
+
+    sysreg!(MairEl1, "MAIR_EL1");
+    plain!(MairEl1);
+}
+
+pub mod current_el {
+    //! The current exception level.
+
+    use super::*;
+
+    sysreg!(CurrentEl, "CurrentEL", r);
+
+    crate::define_cpu_register! { CurrentEl as u64 =>
+        /// The current exception level.
+        r EL: 2..3 =
+            /// An exception level.
+            enum ExceptionLevel [
+                /// EL0, which runs applications.
+                El0 = 0b00,
+                /// EL1, which runs the kernel.
+                El1 = 0b01,
+                /// EL2, which runs the hypervisor.
+                El2 = 0b10,
+                /// EL3, which runs the secure monitor.
+                El3 = 0b11,
+            ],
+    }
+}
+
+pub mod daif {
+    //! The interrupt mask bits.
+
+    use super::*;
+
+    sysreg!(Daif, "DAIF");
+
+    crate::define_cpu_register! { Daif as u64 =>
+        /// Mask FIQ interrupts.
+        rw F: 6,
+        /// Mask IRQ interrupts.
+        rw I: 7,
+        /// Mask SError interrupts.
+        rw A: 8,
+        /// Mask debug exceptions.
+        rw D: 9,
+    }
+}
+
+pub mod ttbr0_el1 {
+    //! The translation table base register for the lower half of the address space.
+
+    use super::*;
+
+    sysreg!(Ttbr0El1, "TTBR0_EL1");
+    plain!(Ttbr0El1);
+}
+
+pub mod ttbr1_el1 {
+    //! The translation table base register for the upper half of the address space.
+
+    use super::*;
+
+    sysreg!(Ttbr1El1, "TTBR1_EL1");
+    plain!(Ttbr1El1);
+}
+
+pub mod vbar_el1 {
+    //! The vector base address register for EL1.
+
+    use super::*;
+
+    sysreg!(VbarEl1, "VBAR_EL1");
+    plain!(VbarEl1);
+}
+
+pub mod esr_el1 {
+    //! The exception syndrome register for EL1.
+
+    use super::*;
+
+    sysreg!(EsrEl1, "ESR_EL1");
+    plain!(EsrEl1);
+}
+
+pub mod far_el1 {
+    //! The fault address register for EL1.
+
+    use super::*;
+
+    sysreg!(FarEl1, "FAR_EL1");
+    plain!(FarEl1);
+}
+
+pub mod elr_el1 {
+    //! The exception link register for EL1.
+
+    use super::*;
+
+    sysreg!(ElrEl1, "ELR_EL1");
+    plain!(ElrEl1);
+}
+
+pub mod mpidr_el1 {
+    //! The multiprocessor affinity register.
+
+    use super::*;
+
+    sysreg!(MpidrEl1, "MPIDR_EL1", r);
+    plain!(MpidrEl1, r);
+}
+
+pub mod cntfrq_el0 {
+    //! The frequency of the system counter.
+
+    use super::*;
+
+    sysreg!(CntfrqEl0, "CNTFRQ_EL0");
+    plain!(CntfrqEl0);
+}
+
+pub mod cntvct_el0 {
+    //! The virtual count of the system counter.
+
+    use super::*;
+
+    sysreg!(CntvctEl0, "CNTVCT_EL0", r);
+    plain!(CntvctEl0, r);
+}
@@ -0,0 +1,232 @@
+//! Memory barriers around register accesses.
+//!
+//! Volatile accesses are never reordered with each other by the compiler, but they
+//! provide no ordering guarantees for the hardware or for normal memory accesses.
+//! For example, writing a DMA descriptor to memory and then writing a doorbell register
+//! requires a barrier in between on ARM and RISC-V.
+//!
+//! A [`Barrier`] can be attached to a whole MMIO register, or used for a single access:
+//!
+//! - A register that is declared with a barrier in brackets after the number type issues the
+//!   barrier before every write and after every read of the register, like the `readl` and
+//!   `writel` functions of Linux. Instead, the barrier can also be issued `before` or `after`
+//!   every access, or two different barriers can be issued before and after every access:
+//!   `[before Dmb]`, `[after Dsb]` or `[before Dmb, after Dsb]`.
+//! - The `get_with` and `set_with` methods of registers, the `set_with` method of fields, and
+//!   the `read_with` and `write_with` methods of [`Lit`](crate::mmio::Lit) do the same for
+//!   a single access, using the [`Ordered`] type that is given as a type parameter.
+//!   Every barrier is an [`Ordered`] type, that issues the barrier before writes and after reads,
+//!   and [`Before`], [`After`] and [`Both`] issue it around every access.
+//!
+//! The `save`, `restore` and `init` methods of MMIO structs access the registers
+//! using their declared barriers, too.
+//!
+//! The barriers are types instead of functions, so they can be swapped out in tests.
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::barrier::Dmb;
+//!
+//! rumio::define_mmio_register! {
+//!     Doorbell: u32 [Dmb] {
+//!         w RING: 0,
+//!     }
+//! }
+//!
+//! rumio::define_mmio_register! {
+//!     Status: u32 {
+//!         r BUSY: 0,
+//!     }
+//! }
+//!
+//! let mut mem = [0u32; 2];
+//! let doorbell = Doorbell::new(unsafe { rumio::mmio::VolAddr::new(mem.as_mut_ptr() as usize) });
+//! let status = Status::new(unsafe { rumio::mmio::VolAddr::new(mem.as_mut_ptr() as usize + 4) });
+//!
+//! // always issues a `Dmb` before the write
+//! doorbell.RING().set(true);
+//! // issues a `Dmb` only for this single read
+//! assert_eq!(status.get_with::<Dmb>(), 0);
+//! ```
+//!
+//! A barrier can be issued after every write instead, e.g. to wait until the write completed:
+//!
+//! ```
+//! use rumio::barrier::{After, Dsb};
+//!
+//! rumio::define_mmio_register! {
+//!     Power: u32 [after Dsb] {
+//!         rw ON: 0,
+//!     }
+//! }
+//!
+//! let mut mem = 0u32;
+//! let power = Power::new(unsafe { rumio::mmio::VolAddr::new(&mut mem as *mut u32 as usize) });
+//!
+//! // issues a `Dsb` after the read and after the write
+//! power.ON().set(true);
+//! // issues only a `Dsb` after the write for this single access
+//! power.ON().set_with::<After<Dsb>>(false);
+//! ```
+
+use core::{
+    marker::PhantomData,
+    sync::atomic::{self, Ordering},
+};
+
+/// A memory barrier.
+pub trait Barrier {
+    /// Issue the barrier.
+    fn barrier();
+}
+
+/// The barriers that are issued around the accesses of a register.
+///
+/// This trait is implemented by [`define_mmio_register`](crate::define_mmio_register),
+/// and by every [`Barrier`], which is issued before every write and after every read.
+pub trait Ordered {
+    /// The barrier that is issued before every read.
+    type BeforeRead: Barrier;
+    /// The barrier that is issued after every read.
+    type AfterRead: Barrier;
+    /// The barrier that is issued before every write.
+    type BeforeWrite: Barrier;
+    /// The barrier that is issued after every write.
+    type AfterWrite: Barrier;
+}
+
+impl<B: Barrier> Ordered for B {
+    type BeforeRead = NoBarrier;
+    type AfterRead = B;
+    type BeforeWrite = B;
+    type AfterWrite = NoBarrier;
+}
+
+/// Issue the barrier `B` before every read and write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Before<B>(PhantomData<B>);
+
+impl<B: Barrier> Ordered for Before<B> {
+    type BeforeRead = B;
+    type AfterRead = NoBarrier;
+    type BeforeWrite = B;
+    type AfterWrite = NoBarrier;
+}
+
+/// Issue the barrier `B` after every read and write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct After<B>(PhantomData<B>);
+
+impl<B: Barrier> Ordered for After<B> {
+    type BeforeRead = NoBarrier;
+    type AfterRead = B;
+    type BeforeWrite = NoBarrier;
+    type AfterWrite = B;
+}
+
+/// Issue the barrier `B` before, and the barrier `A` after every read and write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Both<B, A>(PhantomData<(B, A)>);
+
+impl<B: Barrier, A: Barrier> Ordered for Both<B, A> {
+    type BeforeRead = B;
+    type AfterRead = A;
+    type BeforeWrite = B;
+    type AfterWrite = A;
+}
+
+/// A barrier that does nothing.
+///
+/// This is the barrier of every register that doesn't declare one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoBarrier {}
+
+impl Barrier for NoBarrier {
+    #[inline(always)]
+    fn barrier() {}
+}
+
+/// A compiler fence, that prevents the compiler from reordering memory accesses
+/// across the barrier, but emits no instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompilerFence {}
+
+impl Barrier for CompilerFence {
+    #[inline(always)]
+    fn barrier() {
+        atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// A sequentially consistent [`fence`](core::sync::atomic::fence), which orders
+/// all normal memory accesses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fence {}
+
+impl Barrier for Fence {
+    #[inline(always)]
+    fn barrier() {
+        atomic::fence(Ordering::SeqCst);
+    }
+}
+
+/// A full data memory barrier, that also orders accesses to device memory.
+///
+/// - AArch64: `dmb sy`
+/// - RISC-V: `fence iorw, iorw`
+/// - Everything else: the same as [`Fence`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dmb {}
+
+impl Barrier for Dmb {
+    #[inline(always)]
+    fn barrier() {
+        #[cfg(target_arch = "aarch64")]
+        unsafe {
+            core::arch::asm!("dmb sy", options(nostack, preserves_flags));
+        }
+
+        #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+        unsafe {
+            core::arch::asm!("fence iorw, iorw", options(nostack, preserves_flags));
+        }
+
+        #[cfg(not(any(
+            target_arch = "aarch64",
+            target_arch = "riscv32",
+            target_arch = "riscv64"
+        )))]
+        Fence::barrier();
+    }
+}
+
+/// A data synchronization barrier, which waits until all previous memory accesses completed.
+///
+/// - AArch64: `dsb sy`
+/// - RISC-V: `fence iorw, iorw`
+/// - Everything else: the same as [`Fence`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dsb {}
+
+impl Barrier for Dsb {
+    #[inline(always)]
+    fn barrier() {
+        #[cfg(target_arch = "aarch64")]
+        unsafe {
+            core::arch::asm!("dsb sy", options(nostack, preserves_flags));
+        }
+
+        #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+        unsafe {
+            core::arch::asm!("fence iorw, iorw", options(nostack, preserves_flags));
+        }
+
+        #[cfg(not(any(
+            target_arch = "aarch64",
+            target_arch = "riscv32",
+            target_arch = "riscv64"
+        )))]
+        Fence::barrier();
+    }
+}
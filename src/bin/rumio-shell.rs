@@ -0,0 +1,28 @@
+//! A register shell for the devices of the `example_generated` module.
+//!
+//! Board crates are expected to build their own binary with their own devices,
+//! using [`rumio::shell::main`], which is all this binary does.
+//!
+//! ```text
+//! rumio-shell [MEMORY FILE] [BASE ADDRESS]
+//! ```
+
+use rumio::example_generated::mmio::Device;
+
+const USAGE: &str = "usage: rumio-shell [MEMORY FILE] [BASE ADDRESS]";
+
+fn main() {
+    let base = match std::env::args().nth(2) {
+        Some(base) => match rumio::shell::parse_int(&base) {
+            Ok(base) => base,
+            Err(_) => {
+                eprintln!("invalid base address `{}`", base);
+                eprintln!("{}", USAGE);
+                std::process::exit(2);
+            }
+        },
+        None => 0,
+    };
+
+    rumio::shell::main(&[("DEV", base, &Device::INFO)]);
+}
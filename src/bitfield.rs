@@ -0,0 +1,283 @@
+/// Define a value type for bitfields that live in ordinary memory, like page table entries,
+/// DMA descriptors or packet headers.
+///
+/// The fields use the same syntax as [`define_cpu_register`](crate::define_cpu_register),
+/// and the enums and flags are generated the same way. A range of bits without an enum or
+/// flags holds a plain number.
+///
+/// The generated struct is `#[repr(transparent)]` over the number type, so it can be
+/// used directly inside descriptor tables or other structures that are shared with the hardware.
+/// For every field, a `const` getter and a `const` setter, that returns a modified copy,
+/// is generated, depending on the permission of the field. Their names are given in brackets
+/// after the name of the field: the getter and the setter for `rw` fields, only the getter
+/// for `r` fields, and only the setter for `w` fields. Every field is also available as an
+/// associated [`Field`](crate::Field) constant.
+///
+/// The getter of a number returns the bits of the field, shifted down to the first bit,
+/// and the setter ignores all bits of the number that don't fit into the field.
+///
+/// The struct can be combined with [`Value`](crate::Value)s using the `apply` method,
+/// and converted from and into a `Value`, so it can be written into a register.
+///
+/// # Example
+///
+/// ```
+/// rumio::define_bitfield! {
+///     /// A descriptor of a DMA transfer.
+///     pub struct Descriptor: u32 {
+///         rw VALID [valid, with_valid]: 0,
+///         r DONE [done]: 1,
+///
+///         rw WIDTH [width, with_width]: 2..3 = enum Width [
+///             Byte = 0b00,
+///             Half = 0b01,
+///             Word = 0b10,
+///         ],
+///
+///         rw IRQ [irq, with_irq]: 4..5 = flags Irq [
+///             DONE = 0b01,
+///             ERROR = 0b10,
+///         ],
+///
+///         rw LEN [len, with_len]: 8..15,
+///     }
+/// }
+///
+/// const DESC: Descriptor = Descriptor::new(0)
+///     .with_valid(true)
+///     .with_width(Width::Word)
+///     .with_irq(Irq::DONE)
+///     .with_len(64);
+///
+/// assert_eq!(DESC.bits(), 0x40_00 | 0b01_10_0_1);
+/// assert!(!DESC.done());
+/// assert_eq!(DESC.width(), Some(Width::Word));
+/// assert_eq!(DESC.len(), 64);
+///
+/// let desc = DESC.apply(rumio::Value::from(Width::Half) | Irq::ERROR);
+/// assert_eq!(
+///     format!("{:?}", desc),
+///     "Descriptor { VALID: true, DONE: false, WIDTH: Half, IRQ: ERROR, LEN: 0x40 }"
+/// );
+/// assert_eq!(Descriptor::DONE.read(0b10), 0b10);
+/// ```
+#[macro_export]
+macro_rules! define_bitfield {
+    ($(#[$attr:meta])*
+     $vis:vis struct $name:ident: $num_ty:ty { $(
+         $(#[$field_attr:meta])*
+         $perm:ident $field:ident [$($accessor:ident),+]: $from:literal $( .. $to:literal $( =
+             $(#[$kind_attr:meta])*
+             $kind_type:ident $kind_name:ident [
+                 $(
+                     $(#[$kind_variant_attr:meta])*
+                     $kind_variant:ident = $kind_variant_val:expr
+                 ),*$(,)?
+             ]
+         )? )?
+     ),*$(,)? }) => {
+        $(#[$attr])*
+        #[repr(transparent)]
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+        $vis struct $name($num_ty);
+
+        $($($(
+            $crate::__generate_field_kinds__!($num_ty, $perm, $from .. $to,
+                $(#[$kind_attr])*
+                $kind_type $kind_name [
+                    $(
+                        $(#[$kind_variant_attr])*
+                        $kind_variant = $kind_variant_val
+                    ),*
+                ]
+            );
+        )?)?)*
+
+        #[allow(dead_code)]
+        impl $name {
+            /// Create a new value from the raw bits.
+            pub const fn new(bits: $num_ty) -> Self {
+                Self(bits)
+            }
+
+            /// Return the raw bits of this value.
+            pub const fn bits(self) -> $num_ty {
+                self.0
+            }
+
+            /// Apply the given `Value`, and return the modified copy.
+            pub const fn apply(self, val: $crate::Value<$num_ty>) -> Self {
+                Self($crate::Value::<$num_ty>::apply(val, self.0))
+            }
+
+            $(
+                $(#[$field_attr])*
+                #[doc = ::core::concat!("The `Field` that covers `", ::core::stringify!($field), "`.")]
+                pub const $field: $crate::Field<$num_ty, $crate::__perm_for_name__!($perm)> =
+                    $crate::Field::<$num_ty, _>::new($crate::__field_mask__!($num_ty, $from $(.. $to)?));
+            )*
+
+            $(
+                $crate::define_bitfield!(@field $num_ty, $perm [$($accessor),+] $field: $from $(.. $to $(=
+                    $kind_type $kind_name [
+                        $($kind_variant = $kind_variant_val),*
+                    ]
+                )?)?);
+            )*
+        }
+
+        impl ::core::convert::From<$num_ty> for $name {
+            fn from(bits: $num_ty) -> Self {
+                Self(bits)
+            }
+        }
+
+        impl ::core::convert::From<$name> for $num_ty {
+            fn from(val: $name) -> Self {
+                val.0
+            }
+        }
+
+        impl ::core::convert::From<$crate::Value<$num_ty>> for $name {
+            fn from(val: $crate::Value<$num_ty>) -> Self {
+                Self($crate::Value::<$num_ty>::apply(val, 0))
+            }
+        }
+
+        impl ::core::convert::From<$name> for $crate::Value<$num_ty> {
+            fn from(val: $name) -> Self {
+                $crate::Value::<$num_ty>::new(!0, val.0)
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                ::core::fmt::Debug::fmt(&$crate::decode::Decoded::<$name>::new(self.0), f)
+            }
+        }
+
+        impl $crate::meta::Describe for $name {
+            const INFO: $crate::meta::RegisterInfo = {
+                const FIELDS: &[$crate::meta::FieldInfo] = &[$(
+                    $crate::__field_info__!($perm $field: $from $(.. $to $(=
+                        $kind_type $kind_name [
+                            $($kind_variant = $kind_variant_val),*
+                        ]
+                    )?)?),
+                )*];
+
+                $crate::meta::RegisterInfo {
+                    name: ::core::stringify!($name),
+                    width: ::core::mem::size_of::<$num_ty>() * 8,
+                    perm: $crate::meta::Perm::of_fields(FIELDS),
+                    fields: FIELDS,
+                }
+            };
+        }
+
+        impl $crate::decode::Decode for $name {
+            type Int = $num_ty;
+
+            const NAME: &'static ::core::primitive::str = ::core::stringify!($name);
+
+            #[allow(unused)]
+            fn decode_fields(val: $num_ty, f: &mut ::core::fmt::DebugStruct<'_, '_>) {
+                $(
+                    $crate::__decode_field__!(f, val, $perm $field: $from $(.. $to $(=
+                        $kind_type $kind_name [
+                            $($kind_variant = $kind_variant_val),*
+                        ]
+                    )?)?);
+                )*
+            }
+        }
+    };
+
+    // =====================================
+    // Getters and setters
+    // =====================================
+
+    (@field $num_ty:ty, rw [$get:ident, $set:ident] $($rest:tt)*) => {
+        $crate::define_bitfield!(@field $num_ty, r [$get] $($rest)*);
+        $crate::define_bitfield!(@field $num_ty, w [$set] $($rest)*);
+    };
+
+    (@field $num_ty:ty, r [$get:ident] $field:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        #[doc = ::core::concat!("Return the flags of `", ::core::stringify!($field), "`, ignoring unknown bits.")]
+        pub const fn $get(self) -> $kind_name {
+            const MASK: $num_ty = $crate::__field_mask__!($num_ty, 0 .. ($to - $from));
+            $kind_name::from_bits_truncate((self.0 >> $from) & MASK)
+        }
+    };
+
+    (@field $num_ty:ty, w [$set:ident] $field:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        #[doc = ::core::concat!("Return a copy with `", ::core::stringify!($field), "` set to the given flags.")]
+        pub const fn $set(self, flags: $kind_name) -> Self {
+            const MASK: $num_ty = $crate::__field_mask__!($num_ty, $from .. $to);
+            Self((self.0 & !MASK) | ((flags.bits() << $from) & MASK))
+        }
+    };
+
+    (@field $num_ty:ty, r [$get:ident] $field:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        #[doc = ::core::concat!("Return the variant of `", ::core::stringify!($field), "`, or `None` if the bits don't match any variant.")]
+        pub const fn $get(self) -> ::core::option::Option<$kind_name> {
+            const MASK: $num_ty = $crate::__field_mask__!($num_ty, 0 .. ($to - $from));
+            match (self.0 >> $from) & MASK {
+                $($kind_variant_val => ::core::option::Option::Some($kind_name::$kind_variant),)*
+                _ => ::core::option::Option::None,
+            }
+        }
+    };
+
+    (@field $num_ty:ty, w [$set:ident] $field:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        #[doc = ::core::concat!("Return a copy with `", ::core::stringify!($field), "` set to the given variant.")]
+        pub const fn $set(self, val: $kind_name) -> Self {
+            const MASK: $num_ty = $crate::__field_mask__!($num_ty, $from .. $to);
+            let bits: $num_ty = match val {
+                $($kind_name::$kind_variant => $kind_variant_val,)*
+            };
+            Self((self.0 & !MASK) | ((bits << $from) & MASK))
+        }
+    };
+
+    (@field $num_ty:ty, r [$get:ident] $field:ident: $from:literal .. $to:literal) => {
+        #[doc = ::core::concat!("Return the number in `", ::core::stringify!($field), "`.")]
+        pub const fn $get(self) -> $num_ty {
+            const MASK: $num_ty = $crate::__field_mask__!($num_ty, 0 .. ($to - $from));
+            (self.0 >> $from) & MASK
+        }
+    };
+
+    (@field $num_ty:ty, w [$set:ident] $field:ident: $from:literal .. $to:literal) => {
+        #[doc = ::core::concat!("Return a copy with `", ::core::stringify!($field), "` set to the given number.")]
+        pub const fn $set(self, val: $num_ty) -> Self {
+            const MASK: $num_ty = $crate::__field_mask__!($num_ty, $from .. $to);
+            Self((self.0 & !MASK) | ((val << $from) & MASK))
+        }
+    };
+
+    (@field $num_ty:ty, r [$get:ident] $field:ident: $bit:literal) => {
+        #[doc = ::core::concat!("Check if `", ::core::stringify!($field), "` is set.")]
+        pub const fn $get(self) -> ::core::primitive::bool {
+            self.0 & (1 << $bit) != 0
+        }
+    };
+
+    (@field $num_ty:ty, w [$set:ident] $field:ident: $bit:literal) => {
+        #[doc = ::core::concat!("Return a copy with `", ::core::stringify!($field), "` set to the given value.")]
+        pub const fn $set(self, x: ::core::primitive::bool) -> Self {
+            match x {
+                true => Self(self.0 | (1 << $bit)),
+                false => Self(self.0 & !(1 << $bit)),
+            }
+        }
+    };
+}
@@ -1,6 +1,8 @@
 //! Traits for accessing CPU registers.
 
 mod macros;
+#[cfg(feature = "std")]
+pub mod mock;
 
 /// Trait for reading from a CPU register.
 ///
@@ -34,3 +36,273 @@ pub trait RegisterWrite<I: crate::Int> {
     /// your architecture doesn't have a bit clear instruction.
     fn clear(mask: I);
 }
+
+/// Trait for an indexed family of identically laid-out CPU registers,
+/// like the RISC-V `pmpcfg0..15` CSRs, or MSRs.
+///
+/// The registers are accessed using [`IndexedRead`] and [`IndexedWrite`].
+pub trait Indexed {
+    /// The number of registers in the family.
+    const COUNT: usize;
+}
+
+/// Trait for reading from a register of an indexed family of CPU registers.
+///
+/// This is the counterpart of [`RegisterRead`] for an [`Indexed`] family of registers.
+/// The index is passed to every access, so a single definition covers the whole family.
+///
+/// Use `Register[idx]` in [`define_cpu_register`](crate::define_cpu_register) to
+/// define the fields of an indexed register. All generated functions then take
+/// the index as the first argument, and panic if it is not below [`COUNT`](Indexed::COUNT).
+pub trait IndexedRead<I: crate::Int>: Indexed {
+    /// Read the raw value from the CPU register with the given index.
+    ///
+    /// It's recommended to implement this method as `#[inline]`
+    fn read(idx: usize) -> I;
+}
+
+/// Trait for writing into a register of an indexed family of CPU registers.
+///
+/// See [`IndexedRead`] and [`RegisterWrite`] for more information.
+pub trait IndexedWrite<I: crate::Int>: Indexed {
+    /// Write the given value into the CPU register with the given index.
+    fn write(idx: usize, val: I);
+
+    /// Set all bits that high in the mask, to `1`
+    /// inside the CPU register with the given index.
+    fn set(idx: usize, mask: I);
+
+    /// Set all bits that high in the mask, to `0`
+    /// inside the CPU register with the given index.
+    fn clear(idx: usize, mask: I);
+}
+
+/// Trait for accessing the saved copy of the CPU register `R` inside a context,
+/// like the state of a virtual CPU or a hart that is currently not running.
+///
+/// For every register whose definition starts with `context`,
+/// [`define_cpu_register`](crate::define_cpu_register) also generates functions with
+/// an `_in` suffix, like `get_in` and `set_in`, that take any context which implements
+/// this trait for the register, instead of accessing the live register.
+/// This way, the same definitions can be used for both.
+///
+/// The accesses are not reported to the `trace` feature, because they only
+/// touch memory.
+///
+/// # Example
+///
+/// ```
+/// # use rumio::cpu::{Context, RegisterRead};
+/// pub struct Status;
+///
+/// impl RegisterRead<u64> for Status {
+///     fn read() -> u64 {
+///         // ...
+///         # unimplemented!()
+///     }
+/// }
+///
+/// rumio::define_cpu_register! { context Status as u64 =>
+///     r INTERRUPTS: 0,
+/// }
+///
+/// pub struct Vcpu {
+///     status: u64,
+/// }
+///
+/// impl Context<Status, u64> for Vcpu {
+///     fn read(&self) -> u64 {
+///         self.status
+///     }
+///
+///     fn write(&mut self, val: u64) {
+///         self.status = val;
+///     }
+/// }
+///
+/// # fn main() {
+/// let vcpu = Vcpu { status: 0b1 };
+/// assert!(INTERRUPTS::get_in(&vcpu));
+/// # }
+/// ```
+pub trait Context<R, I: crate::Int> {
+    /// Read the saved value of the register.
+    fn read(&self) -> I;
+
+    /// Write the given value into the saved copy of the register.
+    fn write(&mut self, val: I);
+}
+
+/// Read the given CPU register and report the access,
+/// if the `trace` feature is enabled.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __read<R, I>(register: &'static str, field: Option<&'static str>) -> I
+where
+    R: RegisterRead<I>,
+    I: crate::Int,
+{
+    let val = R::read();
+    #[cfg(feature = "trace")]
+    __trace::<R, I>(register, field, val, crate::trace::Direction::Read);
+    #[cfg(not(feature = "trace"))]
+    let _ = (register, field);
+    val
+}
+
+/// Write the given CPU register and report the access,
+/// if the `trace` feature is enabled.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __write<R, I>(val: I, register: &'static str, field: Option<&'static str>)
+where
+    R: RegisterWrite<I>,
+    I: crate::Int,
+{
+    #[cfg(feature = "trace")]
+    __trace::<R, I>(register, field, val, crate::trace::Direction::Write);
+    #[cfg(not(feature = "trace"))]
+    let _ = (register, field);
+    R::write(val);
+}
+
+/// Set the bits of the given mask inside the CPU register and report the access,
+/// if the `trace` feature is enabled.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __set<R, I>(mask: I, register: &'static str, field: Option<&'static str>)
+where
+    R: RegisterWrite<I>,
+    I: crate::Int,
+{
+    #[cfg(feature = "trace")]
+    __trace::<R, I>(register, field, mask, crate::trace::Direction::Set);
+    #[cfg(not(feature = "trace"))]
+    let _ = (register, field);
+    R::set(mask);
+}
+
+/// Clear the bits of the given mask inside the CPU register and report the access,
+/// if the `trace` feature is enabled.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __clear<R, I>(mask: I, register: &'static str, field: Option<&'static str>)
+where
+    R: RegisterWrite<I>,
+    I: crate::Int,
+{
+    #[cfg(feature = "trace")]
+    __trace::<R, I>(register, field, mask, crate::trace::Direction::Clear);
+    #[cfg(not(feature = "trace"))]
+    let _ = (register, field);
+    R::clear(mask);
+}
+
+/// The accessors for indexed CPU registers, that are used by the generated code.
+#[doc(hidden)]
+pub mod __indexed {
+    use super::{IndexedRead, IndexedWrite};
+
+    /// Panic if the index is outside of the register family.
+    #[inline(always)]
+    fn check(idx: usize, count: usize, register: &'static str) {
+        assert!(
+            idx < count,
+            "the index is {} but the `{}` family only has {} registers",
+            idx,
+            register,
+            count
+        );
+    }
+
+    /// Read the given indexed CPU register and report the access,
+    /// if the `trace` feature is enabled.
+    #[inline(always)]
+    pub fn __read<R, I>(idx: usize, register: &'static str, field: Option<&'static str>) -> I
+    where
+        R: IndexedRead<I>,
+        I: crate::Int,
+    {
+        check(idx, R::COUNT, register);
+        let val = R::read(idx);
+        #[cfg(feature = "trace")]
+        super::__trace::<R, I>(register, field, val, crate::trace::Direction::Read);
+        #[cfg(not(feature = "trace"))]
+        let _ = (register, field);
+        val
+    }
+
+    /// Write the given indexed CPU register and report the access,
+    /// if the `trace` feature is enabled.
+    #[inline(always)]
+    pub fn __write<R, I>(idx: usize, val: I, register: &'static str, field: Option<&'static str>)
+    where
+        R: IndexedWrite<I>,
+        I: crate::Int,
+    {
+        check(idx, R::COUNT, register);
+        #[cfg(feature = "trace")]
+        super::__trace::<R, I>(register, field, val, crate::trace::Direction::Write);
+        #[cfg(not(feature = "trace"))]
+        let _ = (register, field);
+        R::write(idx, val);
+    }
+
+    /// Set the bits of the given mask inside the indexed CPU register and report the access,
+    /// if the `trace` feature is enabled.
+    #[inline(always)]
+    pub fn __set<R, I>(idx: usize, mask: I, register: &'static str, field: Option<&'static str>)
+    where
+        R: IndexedWrite<I>,
+        I: crate::Int,
+    {
+        check(idx, R::COUNT, register);
+        #[cfg(feature = "trace")]
+        super::__trace::<R, I>(register, field, mask, crate::trace::Direction::Set);
+        #[cfg(not(feature = "trace"))]
+        let _ = (register, field);
+        R::set(idx, mask);
+    }
+
+    /// Clear the bits of the given mask inside the indexed CPU register and report the access,
+    /// if the `trace` feature is enabled.
+    #[inline(always)]
+    pub fn __clear<R, I>(idx: usize, mask: I, register: &'static str, field: Option<&'static str>)
+    where
+        R: IndexedWrite<I>,
+        I: crate::Int,
+    {
+        check(idx, R::COUNT, register);
+        #[cfg(feature = "trace")]
+        super::__trace::<R, I>(register, field, mask, crate::trace::Direction::Clear);
+        #[cfg(not(feature = "trace"))]
+        let _ = (register, field);
+        R::clear(idx, mask);
+    }
+}
+
+#[cfg(feature = "trace")]
+#[inline(always)]
+fn __trace<R, I: crate::Int>(
+    register: &'static str,
+    field: Option<&'static str>,
+    val: I,
+    direction: crate::trace::Direction,
+) {
+    crate::trace::record(&crate::trace::Access {
+        register,
+        field,
+        location: crate::trace::Location::Cpu(core::any::type_name::<R>()),
+        width: (core::mem::size_of::<I>() * 8) as u8,
+        value: val.to_u64(),
+        direction,
+    });
+}
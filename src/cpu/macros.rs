@@ -126,16 +126,140 @@
 /// ```
 ///
 ///
+/// # Indexed registers
+///
+/// A family of identically laid-out registers, like the RISC-V `pmpcfg0..15` CSRs, can be
+/// defined once by putting the name of an index parameter in brackets after the register.
+/// The register must implement [`Indexed`][ix], [`IndexedRead`][ir] and [`IndexedWrite`][iw]
+/// instead, and every generated function takes the index as the first argument. Every access
+/// panics if the index is not below the `COUNT` of the register family.
+///
+/// ```no_run
+/// # use rumio::cpu::{Indexed, IndexedRead, IndexedWrite};
+/// pub struct Counter;
+///
+/// impl Indexed for Counter {
+///     const COUNT: usize = 4;
+/// }
+///
+/// impl IndexedRead<u64> for Counter {
+///     fn read(idx: usize) -> u64 {
+///         // ...
+///         # unimplemented!()
+///     }
+/// }
+///
+/// impl IndexedWrite<u64> for Counter {
+///     fn write(idx: usize, val: u64) {
+///         // ...
+///     }
+///
+///     fn set(idx: usize, mask: u64) {
+///         rumio::impl_cpu_set!(Self[idx], mask);
+///     }
+///
+///     fn clear(idx: usize, mask: u64) {
+///         rumio::impl_cpu_clear!(Self[idx], mask);
+///     }
+/// }
+///
+/// rumio::define_cpu_register! { Counter[idx] as u64 =>
+///     rw ENABLED: 0,
+///     r OVERFLOW: 1,
+/// }
+///
+/// # fn main() {
+/// for idx in 0..4 {
+///     ENABLED::set(idx, true);
+/// }
+/// assert!(!OVERFLOW::get(2));
+/// # }
+/// ```
+///
+///
+/// # Saved registers
+///
+/// If the definition starts with `context`, every field and the register itself also get
+/// functions with an `_in` suffix, like `get_in`, `set_in` and `modify_in`, next to the
+/// functions that access the live register. They take a context that implements
+/// [`Context`][ctx] for the register, like the saved state of a virtual CPU,
+/// and access its copy of the register instead.
+///
+/// ```no_run
+/// # use rumio::cpu::{Context, RegisterRead, RegisterWrite};
+/// # pub struct Status;
+/// # impl RegisterRead<u64> for Status { fn read() -> u64 { unimplemented!() } }
+/// # impl RegisterWrite<u64> for Status {
+/// #     fn write(val: u64) {}
+/// #     fn set(mask: u64) {}
+/// #     fn clear(mask: u64) {}
+/// # }
+/// rumio::define_cpu_register! { context Status as u64 =>
+///     rw INTERRUPTS: 0,
+/// }
+///
+/// pub struct Vcpu {
+///     status: u64,
+/// }
+///
+/// impl Context<Status, u64> for Vcpu {
+///     fn read(&self) -> u64 { self.status }
+///     fn write(&mut self, val: u64) { self.status = val }
+/// }
+///
+/// # fn main() {
+/// let mut vcpu = Vcpu { status: 0 };
+/// // enable the interrupts of the guest, but not on the host
+/// INTERRUPTS::set_in(&mut vcpu, true);
+/// assert!(INTERRUPTS::get_in(&vcpu));
+/// # }
+/// ```
+///
+/// These functions can't be generated for indexed registers:
+///
+/// ```compile_fail
+/// # use rumio::cpu::{Indexed, IndexedRead};
+/// # pub struct Counter;
+/// # impl Indexed for Counter { const COUNT: usize = 4; }
+/// # impl IndexedRead<u64> for Counter {
+/// #     fn read(idx: usize) -> u64 { unimplemented!() }
+/// # }
+/// rumio::define_cpu_register! { context Counter[idx] as u64 =>
+///     r ENABLED: 0,
+/// }
+/// ```
+///
+///
 /// To explore the whole generated api, take a look at the
 /// `example_generated` module on docs.rs
 ///
 ///
 /// [rr]: crate::cpu::RegisterRead
 /// [rw]: crate::cpu::RegisterWrite
+/// [ix]: crate::cpu::Indexed
+/// [ir]: crate::cpu::IndexedRead
+/// [iw]: crate::cpu::IndexedWrite
+/// [ctx]: crate::cpu::Context
 /// [bf]: https://docs.rs/bitflags
 #[macro_export]
 macro_rules! define_cpu_register {
-    ($register:ident as $num_ty:ty => $(
+    (context $register:ident [$idx:ident] as $num_ty:ty => $($fields:tt)*) => {
+        ::core::compile_error!("the `_in` functions can't be generated for indexed registers");
+    };
+
+    (context $register:ident as $num_ty:ty => $($fields:tt)*) => {
+        $crate::define_cpu_register!(@define [context] [] $register as $num_ty => $($fields)*);
+    };
+
+    ($register:ident [$idx:ident] as $num_ty:ty => $($fields:tt)*) => {
+        $crate::define_cpu_register!(@define [] [$idx] $register as $num_ty => $($fields)*);
+    };
+
+    ($register:ident as $num_ty:ty => $($fields:tt)*) => {
+        $crate::define_cpu_register!(@define [] [] $register as $num_ty => $($fields)*);
+    };
+
+    (@define $ctx:tt $idx:tt $register:ident as $num_ty:ty => $(
      $(#[$field_attr:meta])*
      $perm:ident $name:ident: $from:literal $( .. $to:literal =
          $(#[$kind_attr:meta])*
@@ -168,7 +292,7 @@ macro_rules! define_cpu_register {
             $(#[$field_attr])*
             #[allow(non_snake_case, dead_code)]
             pub mod $name {
-                $crate::define_cpu_register!(@internal, $num_ty, $register, $perm $name: $from $(.. $to =
+                $crate::define_cpu_register!(@internal, $num_ty, $register, $ctx, $idx, $perm $name: $from $(.. $to =
                     $kind_type $kind_name [
                         $($kind_variant = $kind_variant_val),*
                     ]
@@ -176,18 +300,77 @@ macro_rules! define_cpu_register {
             }
         )*
 
+        $crate::define_cpu_register!(@register, $num_ty, $register, $ctx, $idx, $($perm)*);
+
+        impl $crate::meta::Describe for $register {
+            const INFO: $crate::meta::RegisterInfo = {
+                const FIELDS: &[$crate::meta::FieldInfo] = &[$(
+                    $crate::__field_info__!($perm $name: $from $(.. $to =
+                        $kind_type $kind_name [
+                            $($kind_variant = $kind_variant_val),*
+                        ]
+                    )?),
+                )*];
+
+                $crate::meta::RegisterInfo {
+                    name: ::core::stringify!($register),
+                    width: ::core::mem::size_of::<$num_ty>() * 8,
+                    perm: $crate::meta::Perm::of_fields(FIELDS),
+                    fields: FIELDS,
+                }
+            };
+        }
+
+        impl $crate::decode::Decode for $register {
+            type Int = $num_ty;
+
+            const NAME: &'static ::core::primitive::str = ::core::stringify!($register);
+
+            #[allow(unused)]
+            fn decode_fields(val: $num_ty, f: &mut ::core::fmt::DebugStruct<'_, '_>) {
+                $(
+                    $crate::__decode_field__!(f, val, $perm $name: $from $(.. $to =
+                        $kind_type $kind_name [
+                            $($kind_variant = $kind_variant_val),*
+                        ]
+                    )?);
+                )*
+            }
+        }
+    };
+
+    // =====================================
+    // Functions for the whole register
+    // =====================================
+
+    (@register, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], $($perm:ident)*) => {
         $crate::__generate_if_perm__! { @read
             /// Get the raw value out of this CPU register.
-            pub fn get() -> $num_ty {
-                <$register as $crate::cpu::RegisterRead<$num_ty>>::read()
+            pub fn get($($idx: ::core::primitive::usize)?) -> $num_ty {
+                $crate::define_cpu_register!(@access __read, [$($idx)?], $register, $num_ty, ::core::stringify!($register), ::core::option::Option::None)
+            }
+            => $($perm) *
+        }
+
+        $crate::__generate_if_perm__! { @read
+            /// Read this register once, and return the value which can be printed field by field.
+            #[allow(private_interfaces)]
+            pub fn snapshot($($idx: ::core::primitive::usize)?) -> $crate::decode::Decoded<$register> {
+                $crate::decode::Decoded::new(get($($idx)?))
             }
             => $($perm) *
         }
 
+        /// Wrap the given raw value, so it can be printed field by field.
+        #[allow(private_interfaces)]
+        pub fn decode(val: $num_ty) -> $crate::decode::Decoded<$register> {
+            $crate::decode::Decoded::new(val)
+        }
+
         $crate::__generate_if_perm__! { @read
             /// Read the given field from this register.
-            pub fn read<P: $crate::perm::Readable>(field: $crate::Field<$num_ty, P>) -> $num_ty {
-                let val = <$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+            pub fn read<P: $crate::perm::Readable>($($idx: ::core::primitive::usize,)? field: $crate::Field<$num_ty, P>) -> $num_ty {
+                let val = $crate::define_cpu_register!(@access __read, [$($idx)?], $register, $num_ty, ::core::stringify!($register), ::core::option::Option::None);
                 $crate::Field::<$num_ty, P>::read(field, val)
             }
             => $($perm) *
@@ -197,148 +380,434 @@ macro_rules! define_cpu_register {
             /// Check if one of the given fields is set.
             ///
             /// Returns `true` if the value specified by the field is not null.
-            pub fn is_set<P: $crate::perm::Readable>(field: $crate::Field<$num_ty, P>) -> ::core::primitive::bool {
-                let val = <$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+            pub fn is_set<P: $crate::perm::Readable>($($idx: ::core::primitive::usize,)? field: $crate::Field<$num_ty, P>) -> ::core::primitive::bool {
+                let val = $crate::define_cpu_register!(@access __read, [$($idx)?], $register, $num_ty, ::core::stringify!($register), ::core::option::Option::None);
                 $crate::Field::<$num_ty, P>::read(field, val) != 0
             }
             => $($perm) *
         }
 
+        $crate::__generate_if_perm__! { @read
+            /// Wait until the given closure returns `true` for the value of this register.
+            ///
+            /// Returns the value that satisfied the condition.
+            pub fn wait_until<D: $crate::poll::Deadline>(
+                $($idx: ::core::primitive::usize,)?
+                deadline: D,
+                mut f: impl FnMut($num_ty) -> ::core::primitive::bool,
+            ) -> ::core::result::Result<$num_ty, $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || {
+                    let val = $crate::define_cpu_register!(@access __read, [$($idx)?], $register, $num_ty, ::core::stringify!($register), ::core::option::Option::None);
+                    if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+                })
+            }
+            => $($perm) *
+        }
+
+        $crate::__generate_if_perm__! { @read
+            /// Wait until one of the given fields is set.
+            pub fn wait_for_set<P: $crate::perm::Readable, D: $crate::poll::Deadline>(
+                $($idx: ::core::primitive::usize,)?
+                field: $crate::Field<$num_ty, P>,
+                deadline: D,
+            ) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || is_set($($idx,)? field).then_some(()))
+            }
+            => $($perm) *
+        }
+
+        $crate::__generate_if_perm__! { @read
+            /// Wait until all of the given fields are cleared.
+            pub fn wait_for_clear<P: $crate::perm::Readable, D: $crate::poll::Deadline>(
+                $($idx: ::core::primitive::usize,)?
+                field: $crate::Field<$num_ty, P>,
+                deadline: D,
+            ) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || (!is_set($($idx,)? field)).then_some(()))
+            }
+            => $($perm) *
+        }
+
         $crate::__generate_if_perm__! { @write
             /// Write the raw value into this CPU register.
-            pub fn set(val: $num_ty) {
-                <$register as $crate::cpu::RegisterWrite<$num_ty>>::write(val);
+            pub fn set($($idx: ::core::primitive::usize,)? val: $num_ty) {
+                $crate::define_cpu_register!(@access __write, [$($idx)?], $register, $num_ty, val, ::core::stringify!($register), ::core::option::Option::None);
             }
             => $($perm) *
         }
 
         $crate::__generate_if_perm__! { @write
             /// Write the given values into this register and set all other bits to 0.
-            pub fn write(val: $crate::Value<$num_ty>) {
+            pub fn write($($idx: ::core::primitive::usize,)? val: $crate::Value<$num_ty>) {
                 let val = $crate::Value::<$num_ty>::modify(val, 0);
-                <$register as $crate::cpu::RegisterWrite<$num_ty>>::write(val);
+                $crate::define_cpu_register!(@access __write, [$($idx)?], $register, $num_ty, val, ::core::stringify!($register), ::core::option::Option::None);
             }
             => $($perm) *
         }
 
         $crate::__generate_if_perm__! { @read_write
             /// Modify this register to match the given value, but keep all other bits untouched.
-            pub fn modify(val: $crate::Value<$num_ty>) {
-                let reg = <$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+            pub fn modify($($idx: ::core::primitive::usize,)? val: $crate::Value<$num_ty>) {
+                let reg = $crate::define_cpu_register!(@access __read, [$($idx)?], $register, $num_ty, ::core::stringify!($register), ::core::option::Option::None);
                 let reg = $crate::Value::<$num_ty>::modify(val, reg);
-                <$register as $crate::cpu::RegisterWrite<$num_ty>>::write(reg);
+                $crate::define_cpu_register!(@access __write, [$($idx)?], $register, $num_ty, reg, ::core::stringify!($register), ::core::option::Option::None);
+            }
+            => $($perm) *
+        }
+
+        $crate::__generate_if_perm__! { @read_write
+            /// Like [`modify`], but returns an error without touching the register,
+            /// if the value covers any bit that is not part of a writable field, like the
+            /// `try_modify` method of MMIO registers. CPU registers have no locked fields.
+            pub fn try_modify($($idx: ::core::primitive::usize,)? val: $crate::Value<$num_ty>) -> ::core::result::Result<(), $crate::Error> {
+                let writable = <$register as $crate::meta::Describe>::INFO
+                    .fields
+                    .iter()
+                    .filter(|field| field.perm.is_writable())
+                    .fold(0, |mask, field| mask | field.mask());
+                if $crate::Int::to_u64($crate::Value::<$num_ty>::mask(val)) & !writable != 0 {
+                    return ::core::result::Result::Err($crate::Error::OutOfRange);
+                }
+                modify($($idx,)? val);
+                ::core::result::Result::Ok(())
             }
             => $($perm) *
         }
+
+        $crate::__generate_if_perm__! { @read
+            $crate::define_cpu_register!(@context $ctx {
+                /// Get the raw value out of the saved register of the given context.
+                pub fn get_in<C: $crate::cpu::Context<$register, $num_ty> + ?Sized>(ctx: &C) -> $num_ty {
+                    <C as $crate::cpu::Context<$register, $num_ty>>::read(ctx)
+                }
+
+                /// Return the value of the saved register of the given context,
+                /// which can be printed field by field.
+                #[allow(private_interfaces)]
+                pub fn snapshot_in<C: $crate::cpu::Context<$register, $num_ty> + ?Sized>(ctx: &C) -> $crate::decode::Decoded<$register> {
+                    $crate::decode::Decoded::new(get_in(ctx))
+                }
+
+                /// Read the given field from the saved register of the given context.
+                pub fn read_in<C: $crate::cpu::Context<$register, $num_ty> + ?Sized, P: $crate::perm::Readable>(ctx: &C, field: $crate::Field<$num_ty, P>) -> $num_ty {
+                    $crate::Field::<$num_ty, P>::read(field, get_in(ctx))
+                }
+
+                /// Check if one of the given fields is set inside the saved register of the given context.
+                pub fn is_set_in<C: $crate::cpu::Context<$register, $num_ty> + ?Sized, P: $crate::perm::Readable>(ctx: &C, field: $crate::Field<$num_ty, P>) -> ::core::primitive::bool {
+                    $crate::Field::<$num_ty, P>::read(field, get_in(ctx)) != 0
+                }
+            });
+            => $($perm) *
+        }
+
+        $crate::__generate_if_perm__! { @write
+            $crate::define_cpu_register!(@context $ctx {
+                /// Write the raw value into the saved register of the given context.
+                pub fn set_in<C: $crate::cpu::Context<$register, $num_ty> + ?Sized>(ctx: &mut C, val: $num_ty) {
+                    <C as $crate::cpu::Context<$register, $num_ty>>::write(ctx, val);
+                }
+
+                /// Write the given values into the saved register of the given context
+                /// and set all other bits to 0.
+                pub fn write_in<C: $crate::cpu::Context<$register, $num_ty> + ?Sized>(ctx: &mut C, val: $crate::Value<$num_ty>) {
+                    <C as $crate::cpu::Context<$register, $num_ty>>::write(ctx, $crate::Value::<$num_ty>::modify(val, 0));
+                }
+            });
+            => $($perm) *
+        }
+
+        $crate::__generate_if_perm__! { @read_write
+            $crate::define_cpu_register!(@context $ctx {
+                /// Modify the saved register of the given context to match the given value,
+                /// but keep all other bits untouched.
+                pub fn modify_in<C: $crate::cpu::Context<$register, $num_ty> + ?Sized>(ctx: &mut C, val: $crate::Value<$num_ty>) {
+                    let reg = <C as $crate::cpu::Context<$register, $num_ty>>::read(ctx);
+                    <C as $crate::cpu::Context<$register, $num_ty>>::write(ctx, $crate::Value::<$num_ty>::modify(val, reg));
+                }
+            });
+            => $($perm) *
+        }
     };
 
     // =====================================
     // Read and write bitflags
     // =====================================
 
-    (@internal, $num_ty:ty, $register:ident, rw $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+    (@internal, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], rw $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        $crate::define_cpu_register!(@internal, $num_ty, $register, r $name: $from .. $to = flags $kind_name [
+        $crate::define_cpu_register!(@internal, $num_ty, $register, $ctx, [$($idx)?], r $name: $from .. $to = flags $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
 
-        $crate::define_cpu_register!(@internal, $num_ty, $register, w $name: $from .. $to = flags $kind_name [
+        $crate::define_cpu_register!(@internal, $num_ty, $register, $ctx, [$($idx)?], w $name: $from .. $to = flags $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
     };
 
-    (@internal, $num_ty:ty, $register:ident, r $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+    (@internal, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], r $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
         /// Read the raw bits from the register and return a struct representing
         /// all flags of this bit range.
-        pub fn get() -> super::$kind_name {
-            let val = <super::$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+        pub fn get($($idx: ::core::primitive::usize)?) -> super::$kind_name {
+            let val = $crate::define_cpu_register!(@access __read, [$($idx)?], super::$register, $num_ty, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
             super::$kind_name::from_bits_truncate($crate::get_bits(val, ($from, $to)))
         }
+
+        /// Like [`get`], but returns an error if any bit is set, that isn't a known flag.
+        pub fn try_get($($idx: ::core::primitive::usize)?) -> ::core::result::Result<super::$kind_name, $crate::Error> {
+            let val = $crate::define_cpu_register!(@access __read, [$($idx)?], super::$register, $num_ty, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
+            super::$kind_name::from_bits($crate::get_bits(val, ($from, $to))).ok_or($crate::Error::InvalidPattern)
+        }
+
+        /// Wait until all of the given flags are set.
+        pub fn wait_for_set<D: $crate::poll::Deadline>($($idx: ::core::primitive::usize,)? flags: super::$kind_name, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || get($($idx)?).contains(flags).then_some(()))
+        }
+
+        /// Wait until none of the given flags are set.
+        pub fn wait_for_clear<D: $crate::poll::Deadline>($($idx: ::core::primitive::usize,)? flags: super::$kind_name, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || (!get($($idx)?).intersects(flags)).then_some(()))
+        }
+
+        /// Wait until the given closure returns `true` for the flags of this bit range.
+        ///
+        /// Returns the flags that satisfied the condition.
+        pub fn wait_until<D: $crate::poll::Deadline>(
+            $($idx: ::core::primitive::usize,)?
+            deadline: D,
+            mut f: impl FnMut(super::$kind_name) -> ::core::primitive::bool,
+        ) -> ::core::result::Result<super::$kind_name, $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || {
+                let flags = get($($idx)?);
+                if f(flags) { ::core::option::Option::Some(flags) } else { ::core::option::Option::None }
+            })
+        }
+
+        $crate::define_cpu_register!(@context $ctx {
+            /// Read the raw bits from the saved register of the given context and return
+            /// a struct representing all flags of this bit range.
+            pub fn get_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &C) -> super::$kind_name {
+                let val = <C as $crate::cpu::Context<super::$register, $num_ty>>::read(ctx);
+                super::$kind_name::from_bits_truncate($crate::get_bits(val, ($from, $to)))
+            }
+
+            /// Like [`get_in`], but returns an error if any bit is set, that isn't a known flag.
+            pub fn try_get_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &C) -> ::core::result::Result<super::$kind_name, $crate::Error> {
+                let val = <C as $crate::cpu::Context<super::$register, $num_ty>>::read(ctx);
+                super::$kind_name::from_bits($crate::get_bits(val, ($from, $to))).ok_or($crate::Error::InvalidPattern)
+            }
+        });
     };
 
-    (@internal, $num_ty:ty, $register:ident, w $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+    (@internal, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], w $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
         /// Set this bit range to the given bitflags.
-        pub fn set(flags: super::$kind_name) {
+        pub fn set($($idx: ::core::primitive::usize,)? flags: super::$kind_name) {
             let bits = super::$kind_name::bits(&flags);
-            let val = <super::$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+            let val = $crate::define_cpu_register!(@access __read, [$($idx)?], super::$register, $num_ty, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
             let val = $crate::set_bits(val, ($from, $to), bits);
-            <super::$register as $crate::cpu::RegisterWrite<$num_ty>>::write(val);
+            $crate::define_cpu_register!(@access __write, [$($idx)?], super::$register, $num_ty, val, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
+        }
+
+        /// Set this bit range to the given bitflags, or return an error if they don't fit into the bit range.
+        pub fn try_set($($idx: ::core::primitive::usize,)? flags: super::$kind_name) -> ::core::result::Result<(), $crate::Error> {
+            $crate::__check_range(super::$kind_name::bits(&flags), ($from, $to))?;
+            set($($idx,)? flags);
+            ::core::result::Result::Ok(())
         }
+
+        $crate::define_cpu_register!(@context $ctx {
+            /// Set this bit range inside the saved register of the given context to the given bitflags.
+            pub fn set_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &mut C, flags: super::$kind_name) {
+                let bits = super::$kind_name::bits(&flags);
+                let val = <C as $crate::cpu::Context<super::$register, $num_ty>>::read(ctx);
+                <C as $crate::cpu::Context<super::$register, $num_ty>>::write(ctx, $crate::set_bits(val, ($from, $to), bits));
+            }
+
+            /// Like [`set_in`], but returns an error if the flags don't fit into the bit range.
+            pub fn try_set_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &mut C, flags: super::$kind_name) -> ::core::result::Result<(), $crate::Error> {
+                $crate::__check_range(super::$kind_name::bits(&flags), ($from, $to))?;
+                set_in(ctx, flags);
+                ::core::result::Result::Ok(())
+            }
+        });
     };
 
     // =====================================
     // Read and write a enum range of bits
     // =====================================
 
-    (@internal, $num_ty:ty, $register:ident, rw $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+    (@internal, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], rw $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        $crate::define_cpu_register!(@internal, $num_ty, $register, r $name: $from .. $to = enum $kind_name [
+        $crate::define_cpu_register!(@internal, $num_ty, $register, $ctx, [$($idx)?], r $name: $from .. $to = enum $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
 
-        $crate::define_cpu_register!(@internal, $num_ty, $register, w $name: $from .. $to = enum $kind_name [
+        $crate::define_cpu_register!(@internal, $num_ty, $register, $ctx, [$($idx)?], w $name: $from .. $to = enum $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
     };
 
-    (@internal, $num_ty:ty, $register:ident, r $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+    (@internal, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], r $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
         /// Read the raw bits from the register, and then try to map them to an enum.
-        pub fn get() -> ::core::option::Option<super::$kind_name> {
-            let val = <super::$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+        pub fn get($($idx: ::core::primitive::usize)?) -> ::core::option::Option<super::$kind_name> {
+            let val = $crate::define_cpu_register!(@access __read, [$($idx)?], super::$register, $num_ty, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
             match $crate::get_bits(val, ($from, $to)) {
                 $($kind_variant_val => ::core::option::Option::Some(super::$kind_name::$kind_variant),)*
                 _ => ::core::option::Option::None,
             }
         }
+
+        /// Like [`get`], but returns an error if the bits don't match any variant.
+        pub fn try_get($($idx: ::core::primitive::usize)?) -> ::core::result::Result<super::$kind_name, $crate::Error> {
+            get($($idx)?).ok_or($crate::Error::InvalidPattern)
+        }
+
+        /// Wait until this bit range holds the given variant.
+        pub fn wait_for<D: $crate::poll::Deadline>($($idx: ::core::primitive::usize,)? variant: super::$kind_name, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || (get($($idx)?) == ::core::option::Option::Some(variant)).then_some(()))
+        }
+
+        /// Wait until the given closure returns `true` for the value of this bit range.
+        ///
+        /// Returns the value that satisfied the condition.
+        pub fn wait_until<D: $crate::poll::Deadline>(
+            $($idx: ::core::primitive::usize,)?
+            deadline: D,
+            mut f: impl FnMut(::core::option::Option<super::$kind_name>) -> ::core::primitive::bool,
+        ) -> ::core::result::Result<::core::option::Option<super::$kind_name>, $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || {
+                let val = get($($idx)?);
+                if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+            })
+        }
+
+        $crate::define_cpu_register!(@context $ctx {
+            /// Read the raw bits from the saved register of the given context,
+            /// and then try to map them to an enum.
+            pub fn get_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &C) -> ::core::option::Option<super::$kind_name> {
+                let val = <C as $crate::cpu::Context<super::$register, $num_ty>>::read(ctx);
+                match $crate::get_bits(val, ($from, $to)) {
+                    $($kind_variant_val => ::core::option::Option::Some(super::$kind_name::$kind_variant),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            /// Like [`get_in`], but returns an error if the bits don't match any variant.
+            pub fn try_get_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &C) -> ::core::result::Result<super::$kind_name, $crate::Error> {
+                get_in(ctx).ok_or($crate::Error::InvalidPattern)
+            }
+        });
     };
 
-    (@internal, $num_ty:ty, $register:ident, w $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+    (@internal, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], w $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
         /// Set this bits to the given value.
-        pub fn set(val: super::$kind_name) {
+        pub fn set($($idx: ::core::primitive::usize,)? val: super::$kind_name) {
             let bits = match val {
                 $(super::$kind_name::$kind_variant => $kind_variant_val,)*
             };
-            let val = <super::$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+            let val = $crate::define_cpu_register!(@access __read, [$($idx)?], super::$register, $num_ty, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
             let val = $crate::set_bits(val, ($from, $to), bits);
-            <super::$register as $crate::cpu::RegisterWrite<$num_ty>>::write(val);
+            $crate::define_cpu_register!(@access __write, [$($idx)?], super::$register, $num_ty, val, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
+        }
+
+        /// Set this bits to the given value, or return an error if the value doesn't fit into the bit range.
+        pub fn try_set($($idx: ::core::primitive::usize,)? val: super::$kind_name) -> ::core::result::Result<(), $crate::Error> {
+            let bits = match val {
+                $(super::$kind_name::$kind_variant => $kind_variant_val,)*
+            };
+            $crate::__check_range::<$num_ty>(bits, ($from, $to))?;
+            set($($idx,)? val);
+            ::core::result::Result::Ok(())
         }
+
+        $crate::define_cpu_register!(@context $ctx {
+            /// Set this bits inside the saved register of the given context to the given value.
+            pub fn set_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &mut C, val: super::$kind_name) {
+                let bits = match val {
+                    $(super::$kind_name::$kind_variant => $kind_variant_val,)*
+                };
+                let reg = <C as $crate::cpu::Context<super::$register, $num_ty>>::read(ctx);
+                <C as $crate::cpu::Context<super::$register, $num_ty>>::write(ctx, $crate::set_bits(reg, ($from, $to), bits));
+            }
+
+            /// Like [`set_in`], but returns an error if the value doesn't fit into the bit range.
+            pub fn try_set_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &mut C, val: super::$kind_name) -> ::core::result::Result<(), $crate::Error> {
+                let bits = match val {
+                    $(super::$kind_name::$kind_variant => $kind_variant_val,)*
+                };
+                $crate::__check_range::<$num_ty>(bits, ($from, $to))?;
+                set_in(ctx, val);
+                ::core::result::Result::Ok(())
+            }
+        });
     };
 
     // =====================================
     // Read and write a single bit
     // =====================================
 
-    (@internal, $num_ty:ty, $register:ident, $perm:ident $name:ident: $bit:literal) => {
+    (@internal, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], $perm:ident $name:ident: $bit:literal) => {
         /// A `Field` that covers this single bit.
         pub const FIELD: $crate::Field<$num_ty, $crate::__perm_for_name__!($perm)> = $crate::Field::<$num_ty, _>::new(1 << $bit);
 
-        $crate::define_cpu_register!(@internal_bit, $num_ty, $register, $perm $name: $bit);
+        $crate::define_cpu_register!(@internal_bit, $num_ty, $register, $ctx, [$($idx)?], $perm $name: $bit);
     };
 
-    (@internal_bit, $num_ty:ty, $register:ident, rw $name:ident: $bit:literal) => {
-        $crate::define_cpu_register!(@internal_bit, $num_ty, $register, r $name: $bit);
-        $crate::define_cpu_register!(@internal_bit, $num_ty, $register, w $name: $bit);
+    (@internal_bit, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], rw $name:ident: $bit:literal) => {
+        $crate::define_cpu_register!(@internal_bit, $num_ty, $register, $ctx, [$($idx)?], r $name: $bit);
+        $crate::define_cpu_register!(@internal_bit, $num_ty, $register, $ctx, [$($idx)?], w $name: $bit);
     };
 
-    (@internal_bit, $num_ty:ty, $register:ident, r $name:ident: $bit:literal) => {
+    (@internal_bit, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], r $name:ident: $bit:literal) => {
         /// Check if this bit is set inside the CPU register.
-        pub fn get() -> ::core::primitive::bool {
-            let val = <super::$register as $crate::cpu::RegisterRead<$num_ty>>::read();
+        pub fn get($($idx: ::core::primitive::usize)?) -> ::core::primitive::bool {
+            let val = $crate::define_cpu_register!(@access __read, [$($idx)?], super::$register, $num_ty, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name)));
             val & (1 << $bit) != 0
         }
+
+        /// Wait until this bit is set.
+        pub fn wait_for_set<D: $crate::poll::Deadline>($($idx: ::core::primitive::usize,)? deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || get($($idx)?).then_some(()))
+        }
+
+        /// Wait until this bit is cleared.
+        pub fn wait_for_clear<D: $crate::poll::Deadline>($($idx: ::core::primitive::usize,)? deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || (!get($($idx)?)).then_some(()))
+        }
+
+        /// Wait until the given closure returns `true` for the value of this bit.
+        ///
+        /// Returns the value that satisfied the condition.
+        pub fn wait_until<D: $crate::poll::Deadline>(
+            $($idx: ::core::primitive::usize,)?
+            deadline: D,
+            mut f: impl FnMut(::core::primitive::bool) -> ::core::primitive::bool,
+        ) -> ::core::result::Result<::core::primitive::bool, $crate::poll::Timeout> {
+            $crate::poll::wait_until(deadline, || {
+                let val = get($($idx)?);
+                if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+            })
+        }
+
+        $crate::define_cpu_register!(@context $ctx {
+            /// Check if this bit is set inside the saved register of the given context.
+            pub fn get_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &C) -> ::core::primitive::bool {
+                <C as $crate::cpu::Context<super::$register, $num_ty>>::read(ctx) & (1 << $bit) != 0
+            }
+        });
     };
 
-    (@internal_bit, $num_ty:ty, $register:ident, w $name:ident: $bit:literal) => {
+    (@internal_bit, $num_ty:ty, $register:ident, $ctx:tt, [$($idx:ident)?], w $name:ident: $bit:literal) => {
         /// A `Value` that will set this bit to high when modifying a register.
         pub const SET: $crate::Value<$num_ty> = $crate::Value::<$num_ty>::new(1 << $bit, 1 << $bit);
 
@@ -346,14 +815,65 @@ macro_rules! define_cpu_register {
         pub const CLEAR: $crate::Value<$num_ty> = $crate::Value::<$num_ty>::new(1 << $bit, 0);
 
         /// Set the value of this bit inside the CPU register.
-        pub fn set(x: ::core::primitive::bool) {
+        pub fn set($($idx: ::core::primitive::usize,)? x: ::core::primitive::bool) {
             const MASK: $num_ty = 1 << $bit;
             match x {
-                true => <super::$register as $crate::cpu::RegisterWrite<$num_ty>>::set(MASK),
-                false => <super::$register as $crate::cpu::RegisterWrite<$num_ty>>::clear(MASK),
+                true => $crate::define_cpu_register!(@access __set, [$($idx)?], super::$register, $num_ty, MASK, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name))),
+                false => $crate::define_cpu_register!(@access __clear, [$($idx)?], super::$register, $num_ty, MASK, ::core::stringify!($register), ::core::option::Option::Some(::core::stringify!($name))),
             }
         }
+
+        /// Set the value of this bit inside the CPU register.
+        ///
+        /// This never fails, and only exists for consistency with the other fields.
+        pub fn try_set($($idx: ::core::primitive::usize,)? x: ::core::primitive::bool) -> ::core::result::Result<(), $crate::Error> {
+            set($($idx,)? x);
+            ::core::result::Result::Ok(())
+        }
+
+        $crate::define_cpu_register!(@context $ctx {
+            /// Set the value of this bit inside the saved register of the given context.
+            pub fn set_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &mut C, x: ::core::primitive::bool) {
+                const MASK: $num_ty = 1 << $bit;
+                let val = <C as $crate::cpu::Context<super::$register, $num_ty>>::read(ctx);
+                let val = if x { val | MASK } else { val & !MASK };
+                <C as $crate::cpu::Context<super::$register, $num_ty>>::write(ctx, val);
+            }
+
+            /// Set the value of this bit inside the saved register of the given context.
+            ///
+            /// This never fails, and only exists for consistency with the other fields.
+            pub fn try_set_in<C: $crate::cpu::Context<super::$register, $num_ty> + ?Sized>(ctx: &mut C, x: ::core::primitive::bool) -> ::core::result::Result<(), $crate::Error> {
+                set_in(ctx, x);
+                ::core::result::Result::Ok(())
+            }
+        });
     };
+
+    // =====================================
+    // Access the register
+    // =====================================
+
+    (@access $op:ident, [], $reg_ty:ty, $num_ty:ty, $($arg:expr),*) => {
+        $crate::cpu::$op::<$reg_ty, $num_ty>($($arg),*)
+    };
+
+    (@access $op:ident, [$idx:ident], $reg_ty:ty, $num_ty:ty, $($arg:expr),*) => {
+        $crate::cpu::__indexed::$op::<$reg_ty, $num_ty>($idx, $($arg),*)
+    };
+
+    // =====================================
+    // Access a saved register
+    // =====================================
+
+    (@context [context] { $($item:item)* }) => {
+        $(
+            #[allow(private_bounds)]
+            $item
+        )*
+    };
+
+    (@context [] { $($item:item)* }) => {};
 }
 
 /// Provide a simple implementation for the [`RegisterWrite::set()`](super::RegisterWrite::clear) method.
@@ -365,6 +885,9 @@ macro_rules! define_cpu_register {
 ///
 /// The same can be done for [`clear`](super::RegisterWrite::clear) using the [`impl_cpu_clear`] macro.
 ///
+/// For indexed registers, that implement [`IndexedWrite`](super::IndexedWrite), the index
+/// must be passed in brackets: `impl_cpu_set!(Self[idx], mask)`.
+///
 /// # Example
 ///
 /// ```
@@ -399,6 +922,13 @@ macro_rules! impl_cpu_set {
             <$this as $crate::cpu::RegisterRead<_>>::read() | $mask,
         )
     };
+
+    ($this:ident [$idx:ident], $mask:ident) => {
+        <$this as $crate::cpu::IndexedWrite<_>>::write(
+            $idx,
+            <$this as $crate::cpu::IndexedRead<_>>::read($idx) | $mask,
+        )
+    };
 }
 
 /// Provide a simple implementation for the [`RegisterWrite::clear()`](super::RegisterWrite::clear) method.
@@ -409,6 +939,9 @@ macro_rules! impl_cpu_set {
 ///
 /// The same can be done for [`set`](super::RegisterWrite::set) using the [`impl_cpu_set`] macro.
 ///
+/// For indexed registers, that implement [`IndexedWrite`](super::IndexedWrite), the index
+/// must be passed in brackets: `impl_cpu_clear!(Self[idx], mask)`.
+///
 /// # Example
 ///
 /// ```
@@ -443,4 +976,11 @@ macro_rules! impl_cpu_clear {
             <$this as $crate::cpu::RegisterRead<_>>::read() & !$mask,
         )
     };
+
+    ($this:ident [$idx:ident], $mask:ident) => {
+        <$this as $crate::cpu::IndexedWrite<_>>::write(
+            $idx,
+            <$this as $crate::cpu::IndexedRead<_>>::read($idx) & !$mask,
+        )
+    };
 }
@@ -0,0 +1,451 @@
+//! Mock CPU registers for tests on the host.
+//!
+//! [`define_mock_register`](crate::define_mock_register) defines a register type, that
+//! implements [`RegisterRead`](super::RegisterRead) and [`RegisterWrite`](super::RegisterWrite)
+//! by accessing a value in memory, so it can be used with
+//! [`define_cpu_register`](crate::define_cpu_register) without any boilerplate.
+//!
+//! Every mock register implements the [`Mock`] trait, which can be used to inspect and
+//! control the register:
+//!
+//! - The current value can be read and changed without being recorded, and
+//!   [`reset`](Mock::reset) restores the initial value.
+//! - Every access is recorded, and can be inspected using [`log`](Mock::log).
+//! - [`push_reads`](Mock::push_reads) queues values that are returned by the next reads,
+//!   for example to simulate a busy bit that is cleared after a few polls.
+//! - [`on_read`](Mock::on_read) and [`on_write`](Mock::on_write) install hooks, that
+//!   simulate bits which are owned by the hardware, like read-only or self-clearing bits.
+//!
+//! An indexed family of registers is defined by putting the number of registers in brackets
+//! after the name. It implements [`IndexedMock`] instead, where every function takes the index.
+//!
+//! The state of a mock register is thread local, so tests that run in parallel
+//! don't influence each other.
+//!
+//! This module is only available with the `std` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::cpu::mock::{Access, Mock};
+//!
+//! rumio::define_mock_register! {
+//!     /// The control register.
+//!     pub struct Ctrl: u32 = 0b100;
+//! }
+//!
+//! rumio::define_cpu_register! { Ctrl as u32 =>
+//!     rw START: 0,
+//!     r BUSY: 1,
+//!     r READY: 2,
+//! }
+//!
+//! # fn main() {
+//! // the hardware owns `BUSY` and `READY`, and clears `START` immediately
+//! Ctrl::on_write(|old, new| (new & !0b111) | (old & 0b110));
+//! // the register is busy for the next two reads
+//! Ctrl::push_reads([0b010, 0b010]);
+//!
+//! START::set(true);
+//! while BUSY::get() {}
+//!
+//! assert!(READY::get());
+//! assert_eq!(Ctrl::value(), 0b100);
+//! assert_eq!(
+//!     Ctrl::log(),
+//!     [
+//!         Access::Set(0b001),
+//!         Access::Read(0b010),
+//!         Access::Read(0b010),
+//!         Access::Read(0b100),
+//!         Access::Read(0b100),
+//!     ]
+//! );
+//! # }
+//! ```
+
+use crate::{cpu::Indexed, Int};
+use std::{boxed::Box, collections::VecDeque, vec::Vec};
+
+#[doc(hidden)]
+pub use std::thread_local as __thread_local;
+
+/// A single recorded access to a mock register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Access<I> {
+    /// The register was read, and returned the value.
+    Read(I),
+    /// The value was written into the register.
+    Write(I),
+    /// The bits of the mask were set.
+    Set(I),
+    /// The bits of the mask were cleared.
+    Clear(I),
+}
+
+/// The state of a mock register.
+///
+/// This type is only used by the generated code,
+/// the register is controlled using the [`Mock`] or [`IndexedMock`] trait.
+pub struct State<I> {
+    initial: I,
+    value: I,
+    log: Vec<Access<I>>,
+    reads: VecDeque<I>,
+    on_read: Option<Box<dyn FnMut(I) -> I>>,
+    on_write: Option<Box<dyn FnMut(I, I) -> I>>,
+}
+
+impl<I: Int> State<I> {
+    /// Create a new state with the given initial value.
+    pub fn new(initial: I) -> Self {
+        Self {
+            initial,
+            value: initial,
+            log: Vec::new(),
+            reads: VecDeque::new(),
+            on_read: None,
+            on_write: None,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.value = self.initial;
+        self.log.clear();
+        self.reads.clear();
+        self.on_read = None;
+        self.on_write = None;
+    }
+
+    fn read(&mut self) -> I {
+        if let Some(hook) = self.on_read.as_mut() {
+            self.value = hook(self.value);
+        }
+        let val = self.reads.pop_front().unwrap_or(self.value);
+        self.log.push(Access::Read(val));
+        val
+    }
+
+    fn record(&mut self, access: Access<I>, val: I) {
+        self.log.push(access);
+        self.value = match self.on_write.as_mut() {
+            Some(hook) => hook(self.value, val),
+            None => val,
+        };
+    }
+
+    fn write(&mut self, val: I) {
+        self.record(Access::Write(val), val);
+    }
+
+    fn set(&mut self, mask: I) {
+        self.record(Access::Set(mask), self.value | mask);
+    }
+
+    fn clear(&mut self, mask: I) {
+        self.record(Access::Clear(mask), self.value & !mask);
+    }
+}
+
+/// Inspect and control a mock register.
+///
+/// This trait is implemented by [`define_mock_register`](crate::define_mock_register).
+///
+/// The hooks must not access the same mock register, because the state is
+/// borrowed while they run.
+pub trait Mock: 'static {
+    /// The number type of the register.
+    type Int: Int;
+
+    /// Run the closure with the state of this register for the current thread.
+    fn with_state<R>(f: impl FnOnce(&mut State<Self::Int>) -> R) -> R;
+
+    /// Return the current value, without recording an access.
+    fn value() -> Self::Int {
+        Self::with_state(|state| state.value)
+    }
+
+    /// Change the current value, without recording an access or running the write hook.
+    fn set_value(val: Self::Int) {
+        Self::with_state(|state| state.value = val)
+    }
+
+    /// Restore the initial value, and remove all recorded accesses, queued reads and hooks.
+    fn reset() {
+        Self::with_state(State::reset)
+    }
+
+    /// Return all accesses since the last reset, or the last call to [`take_log`](Mock::take_log).
+    fn log() -> Vec<Access<Self::Int>> {
+        Self::with_state(|state| state.log.clone())
+    }
+
+    /// Return all recorded accesses, and remove them from the log.
+    fn take_log() -> Vec<Access<Self::Int>> {
+        Self::with_state(|state| core::mem::take(&mut state.log))
+    }
+
+    /// Queue values, that are returned by the next reads, instead of the current value.
+    ///
+    /// The queued values don't change the current value of the register.
+    fn push_reads(vals: impl IntoIterator<Item = Self::Int>) {
+        Self::with_state(|state| state.reads.extend(vals))
+    }
+
+    /// Install a hook, that is called before every read with the current value, and returns
+    /// the new current value, for example to simulate a counter.
+    fn on_read(hook: impl FnMut(Self::Int) -> Self::Int + 'static) {
+        Self::with_state(|state| state.on_read = Some(Box::new(hook)))
+    }
+
+    /// Install a hook, that is called on every write, set and clear with the old value and the
+    /// value that is written, and returns the value that is actually stored in the register.
+    fn on_write(hook: impl FnMut(Self::Int, Self::Int) -> Self::Int + 'static) {
+        Self::with_state(|state| state.on_write = Some(Box::new(hook)))
+    }
+
+    /// Read the register, and record the access.
+    ///
+    /// This is used by the [`RegisterRead`](super::RegisterRead) implementation.
+    fn mock_read() -> Self::Int {
+        Self::with_state(State::read)
+    }
+
+    /// Write the register, and record the access.
+    ///
+    /// This is used by the [`RegisterWrite`](super::RegisterWrite) implementation.
+    fn mock_write(val: Self::Int) {
+        Self::with_state(|state| state.write(val))
+    }
+
+    /// Set the bits of the mask, and record the access.
+    ///
+    /// This is used by the [`RegisterWrite`](super::RegisterWrite) implementation.
+    fn mock_set(mask: Self::Int) {
+        Self::with_state(|state| state.set(mask))
+    }
+
+    /// Clear the bits of the mask, and record the access.
+    ///
+    /// This is used by the [`RegisterWrite`](super::RegisterWrite) implementation.
+    fn mock_clear(mask: Self::Int) {
+        Self::with_state(|state| state.clear(mask))
+    }
+}
+
+/// Inspect and control an indexed family of mock registers.
+///
+/// This is the counterpart of [`Mock`] for indexed registers, and is implemented by
+/// [`define_mock_register`](crate::define_mock_register) if the number of registers
+/// is given in brackets. Every register of the family has its own state, so all
+/// functions take the index of the register, and panic if it is out of range.
+pub trait IndexedMock: Indexed + 'static {
+    /// The number type of the registers.
+    type Int: Int;
+
+    /// Run the closure with the state of the register with the given index for the current thread.
+    fn with_state<R>(idx: usize, f: impl FnOnce(&mut State<Self::Int>) -> R) -> R;
+
+    /// Return the current value, without recording an access.
+    fn value(idx: usize) -> Self::Int {
+        Self::with_state(idx, |state| state.value)
+    }
+
+    /// Change the current value, without recording an access or running the write hook.
+    fn set_value(idx: usize, val: Self::Int) {
+        Self::with_state(idx, |state| state.value = val)
+    }
+
+    /// Reset every register of the family, like [`Mock::reset`].
+    fn reset() {
+        for idx in 0..Self::COUNT {
+            Self::with_state(idx, State::reset)
+        }
+    }
+
+    /// Return all accesses since the last reset, or the last call to
+    /// [`take_log`](IndexedMock::take_log).
+    fn log(idx: usize) -> Vec<Access<Self::Int>> {
+        Self::with_state(idx, |state| state.log.clone())
+    }
+
+    /// Return all recorded accesses, and remove them from the log.
+    fn take_log(idx: usize) -> Vec<Access<Self::Int>> {
+        Self::with_state(idx, |state| core::mem::take(&mut state.log))
+    }
+
+    /// Queue values, that are returned by the next reads, instead of the current value.
+    fn push_reads(idx: usize, vals: impl IntoIterator<Item = Self::Int>) {
+        Self::with_state(idx, |state| state.reads.extend(vals))
+    }
+
+    /// Install a read hook, like [`Mock::on_read`].
+    fn on_read(idx: usize, hook: impl FnMut(Self::Int) -> Self::Int + 'static) {
+        Self::with_state(idx, |state| state.on_read = Some(Box::new(hook)))
+    }
+
+    /// Install a write hook, like [`Mock::on_write`].
+    fn on_write(idx: usize, hook: impl FnMut(Self::Int, Self::Int) -> Self::Int + 'static) {
+        Self::with_state(idx, |state| state.on_write = Some(Box::new(hook)))
+    }
+
+    /// Read the register, and record the access.
+    ///
+    /// This is used by the [`IndexedRead`](super::IndexedRead) implementation.
+    fn mock_read(idx: usize) -> Self::Int {
+        Self::with_state(idx, State::read)
+    }
+
+    /// Write the register, and record the access.
+    ///
+    /// This is used by the [`IndexedWrite`](super::IndexedWrite) implementation.
+    fn mock_write(idx: usize, val: Self::Int) {
+        Self::with_state(idx, |state| state.write(val))
+    }
+
+    /// Set the bits of the mask, and record the access.
+    ///
+    /// This is used by the [`IndexedWrite`](super::IndexedWrite) implementation.
+    fn mock_set(idx: usize, mask: Self::Int) {
+        Self::with_state(idx, |state| state.set(mask))
+    }
+
+    /// Clear the bits of the mask, and record the access.
+    ///
+    /// This is used by the [`IndexedWrite`](super::IndexedWrite) implementation.
+    fn mock_clear(idx: usize, mask: Self::Int) {
+        Self::with_state(idx, |state| state.clear(mask))
+    }
+}
+
+/// Define a mock CPU register, with the given number type and initial value.
+///
+/// The register implements [`RegisterRead`](crate::cpu::RegisterRead),
+/// [`RegisterWrite`](crate::cpu::RegisterWrite) and [`Mock`](crate::cpu::mock::Mock).
+/// See the [`mock`](crate::cpu::mock) module for more information.
+///
+/// If the number of registers is given in brackets, an indexed family of registers is
+/// defined instead, which implements [`Indexed`](crate::cpu::Indexed),
+/// [`IndexedRead`](crate::cpu::IndexedRead), [`IndexedWrite`](crate::cpu::IndexedWrite)
+/// and [`IndexedMock`](crate::cpu::mock::IndexedMock).
+/// Every register of the family starts with the initial value.
+///
+/// # Example
+///
+/// ```
+/// use rumio::cpu::mock::IndexedMock;
+///
+/// rumio::define_mock_register! {
+///     pub struct Status: u64 = 0;
+/// }
+///
+/// rumio::define_mock_register! {
+///     pub struct Counter[4]: u32 = 0;
+/// }
+///
+/// rumio::define_cpu_register! { Counter[idx] as u32 =>
+///     rw EN: 0,
+/// }
+///
+/// # fn main() {
+/// EN::set(2, true);
+/// assert_eq!(Counter::value(2), 1);
+/// assert_eq!(Counter::value(1), 0);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! define_mock_register {
+    ($(#[$attr:meta])* $vis:vis struct $name:ident: $num_ty:ty = $init:expr;) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug)]
+        $vis struct $name;
+
+        $crate::define_mock_register!(@state $name: $num_ty = $init);
+
+        impl $crate::cpu::RegisterRead<$num_ty> for $name {
+            #[inline]
+            fn read() -> $num_ty {
+                <$name as $crate::cpu::mock::Mock>::mock_read()
+            }
+        }
+
+        impl $crate::cpu::RegisterWrite<$num_ty> for $name {
+            #[inline]
+            fn write(val: $num_ty) {
+                <$name as $crate::cpu::mock::Mock>::mock_write(val)
+            }
+
+            #[inline]
+            fn set(mask: $num_ty) {
+                <$name as $crate::cpu::mock::Mock>::mock_set(mask)
+            }
+
+            #[inline]
+            fn clear(mask: $num_ty) {
+                <$name as $crate::cpu::mock::Mock>::mock_clear(mask)
+            }
+        }
+    };
+
+    ($(#[$attr:meta])* $vis:vis struct $name:ident [$count:expr]: $num_ty:ty = $init:expr;) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug)]
+        $vis struct $name;
+
+        impl $crate::cpu::Indexed for $name {
+            const COUNT: usize = $count;
+        }
+
+        impl $crate::cpu::mock::IndexedMock for $name {
+            type Int = $num_ty;
+
+            fn with_state<R>(idx: usize, f: impl ::core::ops::FnOnce(&mut $crate::cpu::mock::State<$num_ty>) -> R) -> R {
+                $crate::cpu::mock::__thread_local! {
+                    static STATE: ::core::cell::RefCell<[$crate::cpu::mock::State<$num_ty>; $count]> =
+                        ::core::cell::RefCell::new(::core::array::from_fn(|_| $crate::cpu::mock::State::new($init)));
+                }
+
+                STATE.with(|state| f(&mut state.borrow_mut()[idx]))
+            }
+        }
+
+        impl $crate::cpu::IndexedRead<$num_ty> for $name {
+            #[inline]
+            fn read(idx: usize) -> $num_ty {
+                <$name as $crate::cpu::mock::IndexedMock>::mock_read(idx)
+            }
+        }
+
+        impl $crate::cpu::IndexedWrite<$num_ty> for $name {
+            #[inline]
+            fn write(idx: usize, val: $num_ty) {
+                <$name as $crate::cpu::mock::IndexedMock>::mock_write(idx, val)
+            }
+
+            #[inline]
+            fn set(idx: usize, mask: $num_ty) {
+                <$name as $crate::cpu::mock::IndexedMock>::mock_set(idx, mask)
+            }
+
+            #[inline]
+            fn clear(idx: usize, mask: $num_ty) {
+                <$name as $crate::cpu::mock::IndexedMock>::mock_clear(idx, mask)
+            }
+        }
+    };
+
+    (@state $name:ident: $num_ty:ty = $init:expr) => {
+        impl $crate::cpu::mock::Mock for $name {
+            type Int = $num_ty;
+
+            fn with_state<R>(f: impl ::core::ops::FnOnce(&mut $crate::cpu::mock::State<$num_ty>) -> R) -> R {
+                $crate::cpu::mock::__thread_local! {
+                    static STATE: ::core::cell::RefCell<$crate::cpu::mock::State<$num_ty>> =
+                        ::core::cell::RefCell::new($crate::cpu::mock::State::new($init));
+                }
+
+                STATE.with(|state| f(&mut state.borrow_mut()))
+            }
+        }
+    };
+}
@@ -0,0 +1,136 @@
+//! Human-readable decoding of raw register values.
+//!
+//! Every register that is defined using [`define_mmio_register`](crate::define_mmio_register)
+//! or [`define_cpu_register`](crate::define_cpu_register) implements [`Decode`], and
+//! can be turned into a [`Decoded`] value, whose [`Debug`](fmt::Debug) and
+//! [`Display`](fmt::Display) implementations print every readable field by name.
+//!
+//! - Single bits are printed as booleans.
+//! - Enums are printed using the name of the variant, or the raw bits in hex
+//!   if the bits don't match any variant.
+//! - Flags are printed using the names of all set flags.
+//!
+//! # Example
+//!
+//! ```
+//! rumio::define_mmio_register! {
+//!     Ctrl: u32 {
+//!         rw EN: 0,
+//!         rw MODE: 1..2 = enum Mode [
+//!             Slow = 0b00,
+//!             Fast = 0b01,
+//!         ],
+//!     }
+//! }
+//!
+//! let val = Ctrl::decode(0b011);
+//! assert_eq!(format!("{:?}", val), "Ctrl { EN: true, MODE: Fast }");
+//! ```
+
+use crate::Int;
+use core::{fmt, marker::PhantomData};
+
+/// A register that knows how to decode its raw value.
+///
+/// This trait is implemented by the register definition macros.
+pub trait Decode {
+    /// The underlying value of the register.
+    type Int: Int;
+
+    /// The name of the register.
+    const NAME: &'static str;
+
+    /// Add every readable field of the given raw value to the debug struct.
+    fn decode_fields(val: Self::Int, f: &mut fmt::DebugStruct<'_, '_>);
+}
+
+/// A raw value of the register `R`, that is printed field by field.
+pub struct Decoded<R: Decode> {
+    raw: R::Int,
+    _reg: PhantomData<fn() -> R>,
+}
+
+impl<R: Decode> Decoded<R> {
+    /// Wrap the given raw value of the register `R`.
+    pub fn new(raw: R::Int) -> Self {
+        Self {
+            raw,
+            _reg: PhantomData,
+        }
+    }
+
+    /// Return the raw value.
+    pub fn raw(&self) -> R::Int {
+        self.raw
+    }
+}
+
+impl<R: Decode> Clone for Decoded<R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<R: Decode> Copy for Decoded<R> {}
+
+impl<R: Decode> PartialEq for Decoded<R>
+where
+    R::Int: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+impl<R: Decode> Eq for Decoded<R> where R::Int: Eq {}
+
+impl<R: Decode> fmt::Debug for Decoded<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct(R::NAME);
+        R::decode_fields(self.raw, &mut f);
+        f.finish()
+    }
+}
+
+impl<R: Decode> fmt::Display for Decoded<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl<R: Decode> fmt::LowerHex for Decoded<R>
+where
+    R::Int: fmt::LowerHex,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.raw, f)
+    }
+}
+
+impl<R: Decode> fmt::UpperHex for Decoded<R>
+where
+    R::Int: fmt::UpperHex,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.raw, f)
+    }
+}
+
+impl<R: Decode> fmt::Binary for Decoded<R>
+where
+    R::Int: fmt::Binary,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Binary::fmt(&self.raw, f)
+    }
+}
+
+/// Helper that prints the inner integer as hex when using `Debug`.
+///
+/// Used for bit patterns that don't match any variant of an enum.
+#[doc(hidden)]
+pub struct Hex<I>(pub I);
+
+impl<I: fmt::LowerHex> fmt::Debug for Hex<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
@@ -0,0 +1,84 @@
+//! The crate-level error type.
+
+use crate::{mmio::Locked, poll::Timeout, Int};
+use core::fmt;
+
+/// All errors that can be returned by the fallible `try_*` methods of registers.
+///
+/// The more specific error types, like [`Timeout`] and [`Locked`], can be converted
+/// into this type, so they can be propagated using `?`.
+///
+/// # Example
+///
+/// ```
+/// rumio::define_mmio_register! {
+///     Ctrl: u32 {
+///         rw MODE: 0..1 = enum Mode [
+///             Slow = 0b00,
+///             Fast = 0b01,
+///         ],
+///     }
+/// }
+///
+/// fn speed_up(ctrl: Ctrl) -> Result<(), rumio::Error> {
+///     if ctrl.MODE().try_get()? == Mode::Slow {
+///         ctrl.MODE().try_set(Mode::Fast)?;
+///     }
+///     Ok(())
+/// }
+///
+/// let mut mem = 0b11u32;
+/// let ctrl = Ctrl::new(unsafe { rumio::mmio::VolAddr::new(&mut mem as *mut u32 as usize) });
+/// assert_eq!(speed_up(ctrl), Err(rumio::Error::InvalidPattern));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// The bits of a field don't match any variant or flag.
+    InvalidPattern,
+    /// The value doesn't fit into the bits of the field, or covers bits that can't be written.
+    OutOfRange,
+    /// The field is locked by another field.
+    Locked,
+    /// The deadline expired before a condition was met.
+    Timeout,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidPattern => f.write_str("the bits don't match any variant of the field"),
+            Error::OutOfRange => f.write_str("the value doesn't fit into the field"),
+            Error::Locked => fmt::Display::fmt(&Locked, f),
+            Error::Timeout => fmt::Display::fmt(&Timeout, f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl From<Locked> for Error {
+    fn from(_: Locked) -> Self {
+        Error::Locked
+    }
+}
+
+impl From<Timeout> for Error {
+    fn from(_: Timeout) -> Self {
+        Error::Timeout
+    }
+}
+
+/// Check that the given bits fit into the inclusive range of bits.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+pub fn __check_range<I: Int>(bits: I, (start, end): (usize, usize)) -> Result<I, Error> {
+    let width = end - start + 1;
+    if width < 64 && bits.to_u64() >> width != 0 {
+        Err(Error::OutOfRange)
+    } else {
+        Ok(bits)
+    }
+}
@@ -1,5 +1,6 @@
 //! This module shows examples of the generated code by the macros.
 #![allow(missing_docs)]
 
+pub mod bitfield;
 pub mod cpu;
 pub mod mmio;
@@ -0,0 +1,28 @@
+//! Example code for the [`define_bitfield`] code.
+
+use crate::define_bitfield;
+
+define_bitfield! {
+    pub struct Bitfield: u64 {
+        rw MODE [mode, with_mode]: 0..1 = enum Mode [
+            A = 0b00,
+            B = 0b01,
+            C = 0b10,
+            D = 0b11,
+        ],
+
+        r FOO [foo]: 2,
+
+        rw BAR [bar, with_bar]: 3,
+        rw BAZ [baz, with_baz]: 4,
+
+        rw FLAGS [flags, with_flags]: 5..8 = flags Flags [
+            A = 0b0001,
+            B = 0b0010,
+            C = 0b0100,
+            D = 0b1000,
+        ],
+
+        rw COUNT [count, with_count]: 9..15,
+    }
+}
@@ -27,7 +27,7 @@ define_mmio_register! {
 
 define_mmio_struct! {
     pub struct Device {
-        0x00 => one: Reg,
-        0x08 => two: Reg,
+        (0x00 => one: Reg),
+        (0x08 => two: Reg),
     }
 }
@@ -0,0 +1,141 @@
+//! Futures for waiting until a register reaches a specific state.
+//!
+//! This is the `async` counterpart of the [`poll`](crate::poll) module and is only available
+//! if the `async` feature is enabled. Every readable MMIO register and field gets `*_async` versions
+//! of its `wait_*` methods, which return a [`Wait`] future instead of spinning.
+//!
+//! A pending [`Wait`] future has to be polled again once the register might have changed.
+//! When this happens is decided by a [`WakerSource`], which receives the [`Waker`] of the task.
+//! This could be an interrupt handler that wakes the task, or simply [`Yield`] which
+//! asks the executor to poll the future again as soon as possible.
+//!
+//! None of the futures allocate and they can be used in `no_std` environments.
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::future::Yield;
+//! use std::{
+//!     future::Future,
+//!     pin::Pin,
+//!     sync::Arc,
+//!     task::{Context, Poll, Wake, Waker},
+//! };
+//!
+//! rumio::define_mmio_register! {
+//!     Status: u32 {
+//!         r TX_EMPTY: 0,
+//!     }
+//! }
+//!
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart {
+//!         (0x00 => status: Status),
+//!     }
+//! }
+//!
+//! async fn wait_for_tx(uart: Uart) {
+//!     uart.status().TX_EMPTY().wait_for_set_async(Yield).await;
+//! }
+//!
+//! // a waker that does nothing, because the future is polled in a loop anyway
+//! struct NoopWaker;
+//!
+//! impl Wake for NoopWaker {
+//!     fn wake(self: Arc<Self>) {}
+//! }
+//!
+//! let mut mem = 0u32;
+//! let ptr = &mut mem as *mut u32;
+//! let uart = unsafe { Uart::new(ptr as usize) };
+//!
+//! let waker = Waker::from(Arc::new(NoopWaker));
+//! let mut cx = Context::from_waker(&waker);
+//! let mut fut = Box::pin(wait_for_tx(uart));
+//! assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+//!
+//! // the hardware sets `TX_EMPTY`
+//! unsafe { ptr.write_volatile(0b1) };
+//! assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(()));
+//! ```
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+/// Decides when a pending [`Wait`] future is polled again.
+pub trait WakerSource {
+    /// Register the waker of a pending future.
+    ///
+    /// The waker must be woken as soon as the condition the future is waiting for
+    /// may have changed, for example when the interrupt of the peripheral fires.
+    fn register(&self, waker: &Waker);
+}
+
+impl<S: WakerSource + ?Sized> WakerSource for &S {
+    fn register(&self, waker: &Waker) {
+        S::register(*self, waker)
+    }
+}
+
+/// A [`WakerSource`] that immediately wakes the task again.
+///
+/// This turns the future into a busy loop that yields to the executor
+/// between every check of the register.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Yield;
+
+impl WakerSource for Yield {
+    #[inline]
+    fn register(&self, waker: &Waker) {
+        waker.wake_by_ref();
+    }
+}
+
+/// A future that resolves once the inner closure returns `Some`.
+///
+/// Created by [`wait_until`] and all generated `*_async` methods.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[derive(Debug)]
+pub struct Wait<S, F> {
+    source: S,
+    poll: F,
+}
+
+impl<S, T, F> Future for Wait<S, F>
+where
+    S: WakerSource + Unpin,
+    F: FnMut() -> Option<T> + Unpin,
+{
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let this = self.get_mut();
+
+        if let Some(val) = (this.poll)() {
+            return Poll::Ready(val);
+        }
+
+        this.source.register(cx.waker());
+
+        // check again after registering the waker, so we don't miss
+        // a wake up that happened in between.
+        match (this.poll)() {
+            Some(val) => Poll::Ready(val),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Create a future that resolves once `poll` returns `Some`.
+///
+/// This is the building block for all generated `*_async` methods.
+pub fn wait_until<S, T, F>(source: S, poll: F) -> Wait<S, F>
+where
+    S: WakerSource,
+    F: FnMut() -> Option<T>,
+{
+    Wait { source, poll }
+}
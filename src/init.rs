@@ -0,0 +1,524 @@
+//! Table-driven initialization of MMIO blocks.
+//!
+//! Bring-up code is often just a long sequence of register writes. Instead of writing
+//! it by hand, the sequence can be declared as a `const` or `static` table of
+//! [`RegisterInit`] steps, and run against the struct it was written for by calling
+//! the `init` method of the struct.
+//!
+//! Registers are referred to by their accessor method, like `Uart::ctrl`, and every value has
+//! the type of the register, so a table only compiles if all of its registers exist and
+//! can be accessed as required by the steps. The steps are run strictly in the order of the
+//! table, through the accessors and with the barriers of the registers, and the first step
+//! that fails stops the initialization and is reported as an [`InitError`].
+//!
+//! Like `restore`, a table must write the registers of the `restore` list of the struct
+//! after all other registers, and in the order of the list. Before any step is run, the
+//! table is checked, and the first `write` or `modify` step that breaks this order is
+//! reported with [`InitErrorKind::Order`]. Waits and delays can be placed anywhere.
+//!
+//! The `Copy` structs of [`define_mmio_struct`](crate::define_mmio_struct) have an
+//! `init(&self, ..)` method, and owned structs, singletons and type state structs an
+//! `init(&mut self, ..)` method, whose tables use the `&mut self` accessors of the struct.
+//! The table of a type state struct can only use the registers of its state, e.g. a
+//! `RegisterInit<Uart<Enabled>>` table only runs on a `Uart` in the `Enabled` state.
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::{
+//!     init::{InitErrorKind, RegisterInit},
+//!     poll::Iterations,
+//!     Value,
+//! };
+//!
+//! rumio::define_mmio_register! {
+//!     Ctrl: u32 {
+//!         rw EN: 0,
+//!         r READY: 1,
+//!         rw DIV: 2..3 = enum Div [
+//!             Slow = 0b00,
+//!             Fast = 0b01,
+//!         ],
+//!     }
+//! }
+//!
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart {
+//!         (0x00 => ctrl: Ctrl),
+//!     }
+//! }
+//!
+//! static INIT: [RegisterInit<Uart>; 4] = [
+//!     RegisterInit::write(Uart::ctrl, Div::Fast.value()),
+//!     RegisterInit::delay(100),
+//!     RegisterInit::modify(Uart::ctrl, EN::SET),
+//!     // wait until `READY` is set
+//!     RegisterInit::wait(Uart::ctrl, Value::<u32>::new(0b10, 0b10)),
+//! ];
+//!
+//! let mut mem = 0u32;
+//! let uart = unsafe { Uart::new(&mut mem as *mut u32 as usize) };
+//!
+//! // nobody sets the `READY` bit in this example, so the last step fails
+//! let err = uart.init(&INIT, || Iterations::new(10), |_| {}).unwrap_err();
+//! assert_eq!(err.step, 3);
+//! assert_eq!(err.kind, InitErrorKind::Timeout);
+//! assert_eq!(mem, 0b101);
+//! ```
+//!
+//! Waiting for a register that can't be read fails to compile:
+//!
+//! ```compile_fail
+//! # use rumio::init::RegisterInit;
+//! rumio::define_mmio_register! {
+//!     Data: u32 {
+//!         w SEND: 0,
+//!     }
+//! }
+//!
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart {
+//!         (0x00 => data: Data),
+//!     }
+//! }
+//!
+//! static INIT: [RegisterInit<Uart>; 1] = [
+//!     RegisterInit::wait(Uart::data, SEND::SET),
+//! ];
+//! ```
+
+use crate::{
+    mmio::{Access, Block, Lit, VolAddr, WriteAccess},
+    poll::{self, Deadline},
+    Error, Int, Value,
+};
+use core::fmt;
+
+/// A register that can be used in the steps of an initialization table.
+///
+/// This trait is implemented for [`Lit`] and every register that is defined using
+/// [`define_mmio_register`](crate::define_mmio_register), including the views
+/// that are returned by owned structs.
+pub trait InitRegister {
+    /// The underlying value of the register.
+    type Int: Int;
+
+    /// Return the address of the register.
+    ///
+    /// Used by the generated code.
+    #[doc(hidden)]
+    fn __addr(&self) -> usize;
+
+    /// Return an error if the value covers a field that is locked in `reg`.
+    ///
+    /// Used by the generated code.
+    #[doc(hidden)]
+    fn __check_locks(&self, reg: Self::Int, val: Value<Self::Int>) -> Result<(), Error>;
+}
+
+/// An [`InitRegister`] that can be read.
+pub trait InitRead: InitRegister {
+    /// Read the register using its barriers.
+    ///
+    /// Used by the generated code.
+    #[doc(hidden)]
+    fn __get(&self) -> Self::Int;
+}
+
+/// An [`InitRegister`] that can be written.
+pub trait InitWrite: InitRegister {
+    /// Write the register using its barriers.
+    ///
+    /// Used by the generated code.
+    #[doc(hidden)]
+    fn __set(&self, val: Self::Int);
+}
+
+impl<I: Int, A: Access> InitRegister for Lit<I, A> {
+    type Int = I;
+
+    fn __addr(&self) -> usize {
+        self.addr().__addr()
+    }
+
+    fn __check_locks(&self, _: I, _: Value<I>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl<I: Int, A: Access> InitRead for Lit<I, A> {
+    fn __get(&self) -> I {
+        self.read()
+    }
+}
+
+impl<I: Int, A: WriteAccess> InitWrite for Lit<I, A> {
+    fn __set(&self, val: I) {
+        self.write(val)
+    }
+}
+
+/// An accessor method of the struct `B`, like `Uart::ctrl`, that returns an [`InitRegister`].
+///
+/// `M` is the signature of the method, without the returned register. It's inferred
+/// from the method, and allows to use methods that take `&self` and `&mut self`.
+/// Only methods and closures that don't capture anything can be used as accessors,
+/// function pointers are rejected when the step is created. `Bound` limits `'a` to
+/// the lifetime of `B`, and must not be specified.
+pub trait Accessor<'a, B, M, Bound = &'a B> {
+    /// The register that is returned by the accessor.
+    type Register: InitRegister;
+
+    /// Call the accessor, and ignore `index` if the accessor doesn't take one.
+    ///
+    /// Used by the generated code.
+    #[doc(hidden)]
+    fn __access(self, block: &'a mut B, index: usize) -> Self::Register;
+}
+
+impl<'a, B, F: FnOnce(&'a B) -> R, R: InitRegister> Accessor<'a, B, fn(&B), &'a B> for F {
+    type Register = R;
+
+    fn __access(self, block: &'a mut B, _: usize) -> R {
+        self(block)
+    }
+}
+
+impl<'a, B, F: FnOnce(&'a mut B) -> R, R: InitRegister> Accessor<'a, B, fn(&mut B), &'a B> for F {
+    type Register = R;
+
+    fn __access(self, block: &'a mut B, _: usize) -> R {
+        self(block)
+    }
+}
+
+impl<'a, B, F: FnOnce(&'a B, usize) -> R, R: InitRegister> Accessor<'a, B, fn(&B, usize), &'a B>
+    for F
+{
+    type Register = R;
+
+    fn __access(self, block: &'a mut B, index: usize) -> R {
+        self(block, index)
+    }
+}
+
+impl<'a, B, F: FnOnce(&'a mut B, usize) -> R, R: InitRegister>
+    Accessor<'a, B, fn(&mut B, usize), &'a B> for F
+{
+    type Register = R;
+
+    fn __access(self, block: &'a mut B, index: usize) -> R {
+        self(block, index)
+    }
+}
+
+/// The signature of an [`Accessor`] that returns a single register.
+#[doc(hidden)]
+pub trait Single {}
+
+impl<B> Single for fn(&B) {}
+impl<B> Single for fn(&mut B) {}
+
+/// The signature of an [`Accessor`] that returns a register inside a register array.
+#[doc(hidden)]
+pub trait AtIndex {}
+
+impl<B> AtIndex for fn(&B, usize) {}
+impl<B> AtIndex for fn(&mut B, usize) {}
+
+/// Runs a single step on the register at the index, and returns if the step is done.
+type Run<B> = fn(&mut B, usize, Value<u64>) -> Result<bool, InitErrorKind>;
+
+/// Returns the address of the register at the index.
+type Addr<B> = fn(&mut B, usize) -> usize;
+
+/// A single step of an initialization table for the struct `B`.
+#[derive(Clone, Copy, Debug)]
+pub struct RegisterInit<B> {
+    step: Step<B>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Step<B> {
+    Access {
+        index: usize,
+        val: Value<u64>,
+        /// If the step writes the register, and therefore must follow the `restore` order.
+        writes: bool,
+        run: Run<B>,
+        addr: Addr<B>,
+    },
+    Delay(u64),
+}
+
+impl<B> RegisterInit<B> {
+    /// Write the value into the register, and set all other bits to `0`.
+    pub const fn write<F, M: Single, I: Int>(register: F, val: Value<I>) -> Self
+    where
+        F: Copy + for<'a> Accessor<'a, B, M>,
+        for<'a> <F as Accessor<'a, B, M>>::Register: InitWrite<Int = I>,
+    {
+        Self::access(register, 0, val, true, write::<B, F, M, I>)
+    }
+
+    /// Like [`write`](Self::write), but for the register with the given index inside a register array.
+    pub const fn write_at<F, M: AtIndex, I: Int>(register: F, index: usize, val: Value<I>) -> Self
+    where
+        F: Copy + for<'a> Accessor<'a, B, M>,
+        for<'a> <F as Accessor<'a, B, M>>::Register: InitWrite<Int = I>,
+    {
+        Self::access(register, index, val, true, write::<B, F, M, I>)
+    }
+
+    /// Modify the register to match the value, but keep all other bits untouched.
+    ///
+    /// The step fails if the value covers a field that is currently locked.
+    pub const fn modify<F, M: Single, I: Int>(register: F, val: Value<I>) -> Self
+    where
+        F: Copy + for<'a> Accessor<'a, B, M>,
+        for<'a> <F as Accessor<'a, B, M>>::Register: InitRead + InitWrite<Int = I>,
+    {
+        Self::access(register, 0, val, true, modify::<B, F, M, I>)
+    }
+
+    /// Like [`modify`](Self::modify), but for the register with the given index inside a register array.
+    pub const fn modify_at<F, M: AtIndex, I: Int>(register: F, index: usize, val: Value<I>) -> Self
+    where
+        F: Copy + for<'a> Accessor<'a, B, M>,
+        for<'a> <F as Accessor<'a, B, M>>::Register: InitRead + InitWrite<Int = I>,
+    {
+        Self::access(register, index, val, true, modify::<B, F, M, I>)
+    }
+
+    /// Wait until the register matches the value.
+    pub const fn wait<F, M: Single, I: Int>(register: F, val: Value<I>) -> Self
+    where
+        F: Copy + for<'a> Accessor<'a, B, M>,
+        for<'a> <F as Accessor<'a, B, M>>::Register: InitRead<Int = I>,
+    {
+        Self::access(register, 0, val, false, wait::<B, F, M, I>)
+    }
+
+    /// Like [`wait`](Self::wait), but for the register with the given index inside a register array.
+    pub const fn wait_at<F, M: AtIndex, I: Int>(register: F, index: usize, val: Value<I>) -> Self
+    where
+        F: Copy + for<'a> Accessor<'a, B, M>,
+        for<'a> <F as Accessor<'a, B, M>>::Register: InitRead<Int = I>,
+    {
+        Self::access(register, index, val, false, wait::<B, F, M, I>)
+    }
+
+    /// Wait for the given number of ticks, using the delay function of the executor.
+    pub const fn delay(ticks: u64) -> Self {
+        Self {
+            step: Step::Delay(ticks),
+        }
+    }
+
+    const fn access<F, M, I: Int>(
+        register: F,
+        index: usize,
+        val: Value<I>,
+        writes: bool,
+        run: Run<B>,
+    ) -> Self
+    where
+        F: Copy + for<'a> Accessor<'a, B, M>,
+    {
+        // the accessor is recreated by `accessor` when the step is run
+        assert!(
+            core::mem::size_of::<F>() == 0,
+            "the register must be an accessor method, not a function pointer"
+        );
+        let _ = register;
+
+        let val = Value {
+            mask: crate::widen(val.mask),
+            bits: crate::widen(val.bits),
+        };
+        Self {
+            step: Step::Access {
+                index,
+                val,
+                writes,
+                run,
+                addr: addr::<B, F, M>,
+            },
+        }
+    }
+}
+
+/// Return the accessor `F`, that was passed to the constructor of a step.
+///
+/// The constructors only accept zero sized accessors, like methods and closures
+/// that don't capture anything. They are `Copy`, so creating a new one is the same
+/// as copying the one that was passed to the constructor.
+fn accessor<F: Copy>() -> F {
+    debug_assert_eq!(core::mem::size_of::<F>(), 0);
+    // SAFETY: see above.
+    unsafe { core::mem::zeroed() }
+}
+
+fn narrow<I: Int>(val: Value<u64>) -> Value<I> {
+    Value {
+        mask: I::from_u64(val.mask),
+        bits: I::from_u64(val.bits),
+    }
+}
+
+fn addr<B, F, M>(block: &mut B, index: usize) -> usize
+where
+    F: Copy + for<'a> Accessor<'a, B, M>,
+{
+    accessor::<F>().__access(block, index).__addr()
+}
+
+fn write<B, F, M, I: Int>(
+    block: &mut B,
+    index: usize,
+    val: Value<u64>,
+) -> Result<bool, InitErrorKind>
+where
+    F: Copy + for<'a> Accessor<'a, B, M>,
+    for<'a> <F as Accessor<'a, B, M>>::Register: InitWrite<Int = I>,
+{
+    let reg = accessor::<F>().__access(block, index);
+    reg.__set(narrow(val).modify(I::default()));
+    Ok(true)
+}
+
+fn modify<B, F, M, I: Int>(
+    block: &mut B,
+    index: usize,
+    val: Value<u64>,
+) -> Result<bool, InitErrorKind>
+where
+    F: Copy + for<'a> Accessor<'a, B, M>,
+    for<'a> <F as Accessor<'a, B, M>>::Register: InitRead + InitWrite<Int = I>,
+{
+    let reg = accessor::<F>().__access(block, index);
+    let val = narrow(val);
+    let current = reg.__get();
+    reg.__check_locks(current, val)
+        .map_err(|_| InitErrorKind::Locked)?;
+    reg.__set(val.modify(current));
+    Ok(true)
+}
+
+fn wait<B, F, M, I: Int>(
+    block: &mut B,
+    index: usize,
+    val: Value<u64>,
+) -> Result<bool, InitErrorKind>
+where
+    F: Copy + for<'a> Accessor<'a, B, M>,
+    for<'a> <F as Accessor<'a, B, M>>::Register: InitRead<Int = I>,
+{
+    let current = accessor::<F>().__access(block, index).__get().to_u64();
+    Ok(val.modify(current) == current)
+}
+
+/// The reason why a [`RegisterInit`] step failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InitErrorKind {
+    /// The step writes a register before another register, that must be written
+    /// earlier according to the `restore` list of the struct. No step was run.
+    Order,
+    /// The value of a modification covers a field that is locked by another field.
+    Locked,
+    /// The register didn't match the value before the deadline expired.
+    Timeout,
+}
+
+/// The error that is returned if a step of an initialization table failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct InitError {
+    /// The index of the failed step inside the table.
+    pub step: usize,
+    /// The reason why the step failed.
+    pub kind: InitErrorKind,
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            InitErrorKind::Order => "the restore order is violated",
+            InitErrorKind::Locked => "the field is locked",
+            InitErrorKind::Timeout => "timed out",
+        };
+        write!(f, "init step {} failed: {}", self.step, reason)
+    }
+}
+
+/// Return the position of the register at `offset` in the `restore` order of the block,
+/// where all registers that are not part of the `restore` list come first.
+fn restore_position<B: Block>(offset: usize) -> usize {
+    let order = B::INFO.restore_order;
+    order
+        .iter()
+        .position(|name| {
+            B::INFO
+                .register(name)
+                .is_some_and(|entry| (entry.offset..entry.offset_of(entry.count)).contains(&offset))
+        })
+        .map_or(0, |pos| pos + 1)
+}
+
+/// Run all steps of the table against the block at `base`.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+pub fn __run<B, D, F, G>(
+    base: VolAddr<u8>,
+    block: &mut B,
+    table: &[RegisterInit<B>],
+    mut deadline: F,
+    mut delay: G,
+) -> Result<(), InitError>
+where
+    B: Block,
+    D: Deadline,
+    F: FnMut() -> D,
+    G: FnMut(u64),
+{
+    let mut last = 0;
+    for (step, init) in table.iter().enumerate() {
+        if let Step::Access {
+            index,
+            writes: true,
+            addr,
+            ..
+        } = init.step
+        {
+            let position = restore_position::<B>(addr(block, index).wrapping_sub(base.__addr()));
+            if position < last {
+                let kind = InitErrorKind::Order;
+                return Err(InitError { step, kind });
+            }
+            last = position;
+        }
+    }
+
+    for (step, init) in table.iter().enumerate() {
+        let err = |kind| InitError { step, kind };
+
+        let (index, val, run) = match init.step {
+            Step::Access {
+                index, val, run, ..
+            } => (index, val, run),
+            Step::Delay(ticks) => {
+                delay(ticks);
+                continue;
+            }
+        };
+
+        if !run(block, index, val).map_err(err)? {
+            poll::wait_until(deadline(), || {
+                run(block, index, val).ok().filter(|done| *done)
+            })
+            .map_err(|_| err(InitErrorKind::Timeout))?;
+        }
+    }
+
+    Ok(())
+}
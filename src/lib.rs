@@ -123,8 +123,8 @@
 //!
 //! rumio::define_mmio_struct! {
 //!     pub struct Device {
-//!         0x00 => one: Reg,
-//!         0x08 => two: Reg,
+//!         (0x00 => one: Reg),
+//!         (0x08 => two: Reg),
 //!     }
 //! }
 //!
@@ -141,6 +141,42 @@
 //! one.modify(Mode::A | BAR::SET);
 //! ```
 //!
+//! ## Defining bitfields in memory
+//!
+//! Page table entries, descriptors and other bitfields, that live in ordinary memory,
+//! can be defined using the same field syntax. The generated type is a plain value
+//! with `const` getters and setters, whose names are given in brackets after each field.
+//!
+//! ```rust
+//! rumio::define_bitfield! {
+//!     pub struct Entry: u64 {
+//!         rw VALID [valid, with_valid]: 0,
+//!         rw PERM [perm, with_perm]: 1..3 = flags Perm [
+//!             READ = 0b001,
+//!             WRITE = 0b010,
+//!             EXEC = 0b100,
+//!         ],
+//!     }
+//! }
+//!
+//! const ENTRY: Entry = Entry::new(0).with_valid(true).with_perm(Perm::READ);
+//! assert!(ENTRY.valid());
+//! ```
+//!
+//! ## Features
+//!
+//! - `async`: Generate `*_async` versions of the `wait_*` methods of MMIO registers,
+//!   which return futures instead of spinning. See the `future` module.
+//! - `trace`: Report every register access to a user-installed sink. See the `trace` module.
+//! - `log` / `defmt`: Ready-made trace sinks for the `log` and `defmt` crates. Both enable `trace`.
+//! - `std`: Enables the `shell` module and the `rumio-shell` binary, an interactive shell
+//!   for reading and writing registers through `/dev/mem`, and the `cpu::mock` module for
+//!   testing code that uses CPU registers on the host. On Linux, this also enables the
+//!   `linux` module for mapping `/dev/mem` and UIO regions into the process.
+//! - `aarch64`: Ready-made definitions for the AArch64 system registers. See the `aarch64` module.
+//! - `riscv`: Ready-made definitions for the standard RISC-V CSRs and page table entries. See the `riscv` module.
+//! - `x86_64`: Ready-made definitions for the x86_64 control registers, MSRs and page table entries. See the `x86_64` module.
+//!
 //! ### License
 //!
 //! Licensed under either [Apache License][apache] or the [MIT][mit] license.
@@ -162,6 +198,9 @@
     private_intra_doc_links
 )]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 #[cfg(feature = "example_generated")]
 pub mod example_generated;
 
@@ -173,9 +212,31 @@ pub use defile;
 #[doc(hidden)]
 pub use bitflags;
 
+#[cfg(feature = "aarch64")]
+pub mod aarch64;
+pub mod barrier;
+mod bitfield;
 pub mod cpu;
+pub mod decode;
+mod error;
+pub mod init;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub mod linux;
+pub mod meta;
+#[cfg(feature = "async")]
+pub mod future;
 pub mod mmio;
 pub mod perm;
+pub mod poll;
+#[cfg(feature = "riscv")]
+pub mod riscv;
+pub mod save;
+#[cfg(feature = "std")]
+pub mod shell;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "x86_64")]
+pub mod x86_64;
 
 mod macros;
 
@@ -185,6 +246,10 @@ use core::{
 };
 use perm::Permission;
 
+#[doc(hidden)]
+pub use error::__check_range;
+pub use error::Error;
+
 /// Represents any type that can be used as
 /// the underlying value for a register or bitfield.
 pub trait Int:
@@ -198,6 +263,11 @@ pub trait Int:
     + Default
     + sealed::Sealed
 {
+    /// Convert this value into a `u64`.
+    fn to_u64(self) -> u64;
+
+    /// Convert the given `u64` into this type, truncating all bits that don't fit.
+    fn from_u64(val: u64) -> Self;
 }
 
 /// This macro includes generation of `Int` implementation
@@ -213,6 +283,21 @@ macro_rules! impl_int {
                     bits: (bits & mask),
                 }
             }
+
+            /// Combine this [`Value`] with the other one, like the `|` operator,
+            /// but usable in `const` items.
+            pub const fn or(self, other: Self) -> Self {
+                Self {
+                    mask: self.mask | other.mask,
+                    bits: self.bits | other.bits,
+                }
+            }
+
+            /// Modify all bits that are specified by this [`Value`] in the given value,
+            /// like [`modify`](Value::modify), but usable in `const` items.
+            pub const fn apply(self, val: $num) -> $num {
+                (val & !self.mask) | self.bits
+            }
         }
         impl<P> Field<$num, P> {
             /// Create a new [`Field`] that covers the given mask.
@@ -222,8 +307,24 @@ macro_rules! impl_int {
                     __perm: PhantomData,
                 }
             }
+
+            /// Return all bits of the given value that are covered by this field,
+            /// like [`read`](Field::read), but usable in `const` items.
+            pub const fn extract(self, val: $num) -> $num {
+                val & self.mask
+            }
+        }
+        impl Int for $num {
+            #[inline]
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            #[inline]
+            fn from_u64(val: u64) -> Self {
+                val as $num
+            }
         }
-        impl Int for $num {}
         )*
     };
 }
@@ -234,6 +335,33 @@ impl_int!(u8, u16, u32, u64, usize);
 /// the `modify` method.
 ///
 /// This is also used to modify mulitple bitfields in one write operation.
+///
+/// The `|` operator and [`modify`](Value::modify) can't be used in `const` items,
+/// so every number type has `const` versions of them called `or` and `apply`.
+/// Together with the `value` method of the generated enums and flags, whole
+/// register values can be computed at compile time.
+///
+/// # Example
+///
+/// ```
+/// use rumio::Value;
+///
+/// rumio::define_bitfield! {
+///     pub struct Ctrl: u32 {
+///         rw EN [en, with_en]: 0,
+///         rw MODE [mode, with_mode]: 1..2 = enum Mode [
+///             Slow = 0b00,
+///             Fast = 0b01,
+///         ],
+///     }
+/// }
+///
+/// const INIT: Value<u32> = Mode::Fast.value().or(Value::<u32>::new(0b1, 0b1));
+/// static INIT_VALUE: u32 = INIT.apply(0);
+///
+/// assert_eq!(INIT_VALUE, 0b011);
+/// assert_eq!(Ctrl::MODE.extract(INIT_VALUE), 0b010);
+/// ```
 #[derive(Clone, Copy, Debug)]
 pub struct Value<I> {
     mask: I,
@@ -247,6 +375,18 @@ impl<I: Int> Value<I> {
     pub fn modify(self, val: I) -> I {
         (val & !self.mask) | self.bits
     }
+
+    /// Return the mask of all bits that are modified by this [`Value`].
+    #[inline]
+    pub fn mask(self) -> I {
+        self.mask
+    }
+
+    /// Return the bits that are written by this [`Value`].
+    #[inline]
+    pub fn bits(self) -> I {
+        self.bits
+    }
 }
 
 impl<I: Int> BitOr<Value<I>> for Value<I> {
@@ -261,13 +401,22 @@ impl<I: Int> BitOr<Value<I>> for Value<I> {
 }
 
 /// Specifies a specific bit mask inside a register.
-#[derive(Clone, Copy, Debug)]
+#[derive(Debug)]
 #[repr(transparent)]
 pub struct Field<I, P> {
     mask: I,
     __perm: PhantomData<P>,
 }
 
+// manual implementations, because the permission
+// types are never `Clone` or `Copy`.
+impl<I: Copy, P> Clone for Field<I, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<I: Copy, P> Copy for Field<I, P> {}
+
 impl<I: Int, P: Permission> Field<I, P> {
     /// Return all bits that were covered by this field.
     ///
@@ -319,14 +468,12 @@ where
 /// assert_eq!(get_bits(x, (0, 1)), 0b11);
 /// assert_eq!(get_bits(x, (4, 6)), 0b001);
 /// ```
-pub fn get_bits<I: Int>(num: I, (start, end): (usize, usize)) -> I {
-    let bit_len = core::mem::size_of::<I>() * 8;
-
+pub const fn get_bits<I: Int>(num: I, (start, end): (usize, usize)) -> I {
     // add `1` because this is an inclusive range.
     let end = end + 1;
 
-    let bits = num << (bit_len - end) >> (bit_len - end);
-    bits >> start
+    let bits = widen(num) << (64 - end) >> (64 - end);
+    narrow(bits >> start)
 }
 
 /// Sets the range (inclusive) of bits, given by the `(start, end)` tuple, to the
@@ -351,17 +498,52 @@ pub fn get_bits<I: Int>(num: I, (start, end): (usize, usize)) -> I {
 ///
 /// let x = set_bits(x, (0, 4), 0b11001);
 /// assert_eq!(x, 0b11001);
+///
+/// // both functions can be used in constants
+/// const MODE: u8 = set_bits(0, (4, 7), 0b1010);
+/// assert_eq!(MODE, 0b1010_0000);
 /// ```
-pub fn set_bits<I: Int>(num: I, (start, end): (usize, usize), bits: I) -> I {
-    let bit_len = core::mem::size_of::<I>() * 8;
-
+pub const fn set_bits<I: Int>(num: I, (start, end): (usize, usize), bits: I) -> I {
     // add `1` because this is an inclusive range.
     let end = end + 1;
 
-    let mask = !I::default() << (bit_len - end) >> (bit_len - end);
-    let mask = !(mask >> start << start);
+    let mask = !0u64 << (64 - end) >> (64 - end);
+    let mask = mask >> start << start;
+
+    narrow((widen(num) & !mask) | ((widen(bits) << start) & mask))
+}
+
+/// Used by [`get_bits`] and [`set_bits`] to reinterpret an [`Int`] as a `u64`,
+/// because the operators of the `Int` trait can't be called in a `const fn`.
+union Raw<I: Copy> {
+    int: I,
+    raw: u64,
+}
+
+/// The number of bits an `I` has to be shifted by to be at the start of a `Raw`,
+/// which is non-zero on big endian targets.
+const fn offset<I>() -> usize {
+    if cfg!(target_endian = "big") {
+        (8 - core::mem::size_of::<I>()) * 8
+    } else {
+        0
+    }
+}
+
+/// Zero extends `num` into a `u64`.
+const fn widen<I: Int>(num: I) -> u64 {
+    let mut raw = Raw { raw: 0 };
+    raw.int = num;
+    // SAFETY: every `Int` is a primitive integer that is at most 64 bits wide,
+    // and all bytes of `raw` were initialized before `int` was written.
+    unsafe { raw.raw >> offset::<I>() }
+}
 
-    (num & mask) | ((bits << start) & !mask)
+/// Truncates `num` into an `I`, dropping all bits that don't fit.
+const fn narrow<I: Int>(num: u64) -> I {
+    // SAFETY: every `Int` is a primitive integer that is at most 64 bits wide,
+    // so every bit pattern of its bytes is valid.
+    unsafe { Raw::<I> { raw: num << offset::<I>() }.int }
 }
 
 mod sealed {
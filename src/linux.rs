@@ -0,0 +1,221 @@
+//! Mapping MMIO regions into a Linux userspace process.
+//!
+//! The structs generated by [`define_mmio_struct`](crate::define_mmio_struct) need the address
+//! of an already mapped region. This module creates such mappings from `/dev/mem`, a UIO device
+//! or any other file, and hands out devices which can't outlive the [`Mapping`].
+//! Only structs in the ownership mode of [`define_mmio_struct`](crate::define_mmio_struct)
+//! can be created inside a mapping, and creating one borrows the mapping mutably,
+//! so at most one device of a mapping is alive at any time.
+//!
+//! This module is only available with the `std` feature on Linux.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rumio::linux::Mapping;
+//!
+//! rumio::define_mmio_register! {
+//!     Ctrl: u32 {
+//!         rw EN: 0,
+//!     }
+//! }
+//!
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart<'a> {
+//!         (0x00 => mut ctrl: Ctrl),
+//!     }
+//! }
+//!
+//! let mut mapping = Mapping::physical(0x1000_0000, 0x1000).unwrap();
+//! let mut uart = mapping.device::<Uart<'_>>().unwrap();
+//! uart.ctrl().EN().set(true);
+//! ```
+//!
+//! A device can't be used after the mapping is dropped:
+//!
+//! ```compile_fail
+//! # use rumio::{linux::Mapping, mmio::Lit};
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart<'a> {
+//!         (0x00 => mut data: Lit<u32>),
+//!     }
+//! }
+//!
+//! let mut mapping = Mapping::physical(0x1000_0000, 0x1000).unwrap();
+//! let mut uart = mapping.device::<Uart<'_>>().unwrap();
+//! drop(mapping);
+//! uart.data().write(0);
+//! ```
+
+use crate::mmio::Owned;
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    os::unix::{fs::OpenOptionsExt, io::AsRawFd},
+    path::Path,
+    ptr::NonNull,
+};
+
+/// A region of memory that is mapped into this process.
+///
+/// The region is unmapped when this value is dropped.
+#[derive(Debug)]
+pub struct Mapping {
+    /// Start of the mapping, which is always page aligned.
+    ptr: NonNull<u8>,
+    /// Total length of the mapping, starting at `ptr`.
+    map_len: usize,
+    /// Offset of the requested region, relative to `ptr`.
+    delta: usize,
+    /// Length of the requested region.
+    len: usize,
+}
+
+// SAFETY: the mapping is just a region of memory, and all accesses
+// to it go through `VolAddr`.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    /// Map the physical address range starting at `addr`, using `/dev/mem`.
+    pub fn physical(addr: u64, len: usize) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_SYNC)
+            .open("/dev/mem")?;
+        Self::file(&file, addr, len)
+    }
+
+    /// Map the region with the given index of a UIO device, like `/dev/uio0`.
+    ///
+    /// The size of the region, and its offset inside the first mapped page,
+    /// are read from `/sys/class/uio`.
+    pub fn uio<P: AsRef<Path>>(dev: P, index: usize) -> io::Result<Self> {
+        let dev = dev.as_ref();
+        let name = dev
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid UIO device"))?;
+
+        let map = Path::new("/sys/class/uio")
+            .join(name)
+            .join(std::format!("maps/map{}", index));
+        let size = read_hex(&map.join("size"))?;
+        let offset = read_hex(&map.join("offset"))?;
+
+        let file = OpenOptions::new().read(true).write(true).open(dev)?;
+
+        // UIO selects the region using the page index as offset, and maps
+        // the whole page that contains the start of the region
+        let mut mapping = Self::file(&file, (index * page_size()) as u64, offset + size)?;
+        mapping.delta += offset;
+        mapping.len = size;
+        Ok(mapping)
+    }
+
+    /// Map `len` bytes of the given file, starting at `offset`.
+    ///
+    /// The offset doesn't have to be page aligned.
+    pub fn file(file: &File, offset: u64, len: usize) -> io::Result<Self> {
+        if len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "can not map an empty region",
+            ));
+        }
+
+        let delta = (offset % page_size() as u64) as usize;
+        let map_len = len + delta;
+
+        // SAFETY: we don't provide a hint address, so this will
+        // never replace an existing mapping.
+        let ptr = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                (offset - delta as u64) as libc::off_t,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            ptr: NonNull::new(ptr as *mut u8).expect("mmap returned null"),
+            map_len,
+            delta,
+            len,
+        })
+    }
+
+    /// Return the address of the start of the mapped region.
+    pub fn addr(&self) -> usize {
+        self.ptr.as_ptr() as usize + self.delta
+    }
+
+    /// Return the length of the mapped region in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always returns `false`, because empty mappings can't be created.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Create the device `D` at the start of this mapping.
+    ///
+    /// Returns `None` if the registers of `D` don't fit into the mapping.
+    pub fn device<'a, D: Owned<'a>>(&'a mut self) -> Option<D> {
+        self.device_at(0)
+    }
+
+    /// Create the device `D` at the given offset inside this mapping.
+    ///
+    /// Returns `None` if the registers of `D` don't fit into the mapping, or if
+    /// the address of the device is not aligned to the largest register of `D`.
+    pub fn device_at<'a, D: Owned<'a>>(&'a mut self, offset: usize) -> Option<D> {
+        if offset.checked_add(D::INFO.size())? > self.len {
+            return None;
+        }
+        // `is_multiple_of` needs Rust 1.87
+        #[allow(clippy::manual_is_multiple_of)]
+        if (self.addr() + offset) % D::INFO.align() != 0 {
+            return None;
+        }
+
+        // SAFETY: the address is valid for the whole size of the block, and `D`
+        // can't outlive the mutable borrow of the mapping.
+        Some(unsafe { D::new(self.addr() + offset) })
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: the pointer and length are the ones returned by `mmap`.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.map_len);
+        }
+    }
+}
+
+/// Read a hexadecimal number, like the attributes of a UIO map.
+fn read_hex(path: &Path) -> io::Result<usize> {
+    let val = fs::read_to_string(path)?;
+    usize::from_str_radix(val.trim().trim_start_matches("0x"), 16)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn page_size() -> usize {
+    // SAFETY: `sysconf` has no safety requirements.
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size <= 0 {
+        4096
+    } else {
+        size as usize
+    }
+}
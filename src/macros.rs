@@ -17,19 +17,23 @@ macro_rules! __generate_field_kinds__ {
 
         impl $kind_name {
             /// Return the field that covers the range of this enum.
-            pub fn field() -> $crate::Field<$num_ty, $crate::__perm_for_name__!($perm)> {
-                let mask = $crate::set_bits(0, ($from, $to), !0);
-                $crate::Field::<$num_ty, $crate::__perm_for_name__!($perm)>::new(mask)
+            pub const fn field() -> $crate::Field<$num_ty, $crate::__perm_for_name__!($perm)> {
+                $crate::Field::<$num_ty, $crate::__perm_for_name__!($perm)>::new($crate::__field_mask__!($num_ty, $from .. $to))
+            }
+
+            /// Return a `Value` that writes this variant into the range,
+            /// which can also be used in `const` items.
+            pub const fn value(self) -> $crate::Value<$num_ty> {
+                let bits: $num_ty = match self {
+                    $($kind_name::$variant => $variant_val,)*
+                };
+                $crate::Value::<$num_ty>::new($crate::__field_mask__!($num_ty, $from .. $to), bits << $from)
             }
         }
 
         impl ::core::convert::From<$kind_name> for $crate::Value<$num_ty> {
             fn from(x: $kind_name) -> $crate::Value<$num_ty> {
-                let mask = $crate::set_bits(0, ($from, $to), !0);
-                let bits = match x {
-                    $($kind_name::$variant => $variant_val,)*
-                };
-                $crate::Value::<$num_ty>::new(mask, bits << $from)
+                $kind_name::value(x)
             }
         }
 
@@ -57,25 +61,32 @@ macro_rules! __generate_field_kinds__ {
             $variant:ident = $variant_val:expr
         ),*]
     ) => {
-        $crate::bitflags::bitflags! {
-            $(#[$attr])*
-            pub struct $kind_name: $num_ty {
-                $(const $variant = $variant_val;)*
+        // `bitflags` can't parse `meta` fragments, so they are ungrouped using `defile`
+        $crate::defile::item! {
+            $crate::bitflags::bitflags! {
+                $(#[$attr])*
+                pub struct $kind_name: $num_ty {
+                    $($(#[@$variant_attr])* const $variant = $variant_val;)*
+                }
             }
         }
 
         impl $kind_name {
             /// Return the field that covers the range of this enum.
-            pub fn field() -> $crate::Field<$num_ty, $crate::__perm_for_name__!($perm)> {
-                let mask = $crate::set_bits(0, ($from, $to), !0);
-                $crate::Field::<$num_ty, $crate::__perm_for_name__!($perm)>::new(mask)
+            pub const fn field() -> $crate::Field<$num_ty, $crate::__perm_for_name__!($perm)> {
+                $crate::Field::<$num_ty, $crate::__perm_for_name__!($perm)>::new($crate::__field_mask__!($num_ty, $from .. $to))
+            }
+
+            /// Return a `Value` that writes these flags into the range,
+            /// which can also be used in `const` items.
+            pub const fn value(self) -> $crate::Value<$num_ty> {
+                $crate::Value::<$num_ty>::new($crate::__field_mask__!($num_ty, $from .. $to), self.bits() << $from)
             }
         }
 
         impl ::core::convert::From<$kind_name> for $crate::Value<$num_ty> {
             fn from(x: $kind_name) -> $crate::Value<$num_ty> {
-                let mask = $crate::set_bits(0, ($from, $to), !0);
-                $crate::Value::<$num_ty>::new(mask, $kind_name::bits(&x) << $from)
+                $kind_name::value(x)
             }
         }
 
@@ -97,6 +108,20 @@ macro_rules! __generate_field_kinds__ {
     };
 }
 
+/// Hidden macro that computes the mask of a single bit, or an inclusive
+/// range of bits, in a `const` context.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_mask__ {
+    ($num_ty:ty, $from:tt .. $to:tt) => {
+        (<$num_ty>::MAX >> (<$num_ty>::BITS as usize - 1 - ($to - $from))) << $from
+    };
+
+    ($num_ty:ty, $bit:tt) => {
+        1 << $bit
+    };
+}
+
 /// Hidden macro that allows to generate a function
 /// if at least one of the bitfields can be read/write.
 #[doc(hidden)]
@@ -166,3 +191,130 @@ macro_rules! __perm_for_name__ {
         $crate::perm::ReadWrite
     };
 }
+
+/// Hidden macro that only emits the given code if the `async` feature is enabled.
+#[cfg(feature = "async")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_async__ {
+    ($($code:tt)*) => { $($code)* };
+}
+
+/// Hidden macro that only emits the given code if the `async` feature is enabled.
+#[cfg(not(feature = "async"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_async__ {
+    ($($code:tt)*) => {};
+}
+
+/// Hidden macro that adds a single field of a raw register value
+/// to a `DebugStruct`, if the field is readable.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __decode_field__ {
+    ($f:ident, $val:ident, w $($field:tt)*) => {};
+
+    ($f:ident, $val:ident, $perm:ident $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        match $crate::get_bits($val, ($from, $to)) {
+            $($kind_variant_val => $f.field(::core::stringify!($name), &$kind_name::$kind_variant),)*
+            bits => $f.field(::core::stringify!($name), &$crate::decode::Hex(bits)),
+        };
+    };
+
+    ($f:ident, $val:ident, $perm:ident $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        $f.field(
+            ::core::stringify!($name),
+            &$kind_name::from_bits_truncate($crate::get_bits($val, ($from, $to))),
+        );
+    };
+
+    ($f:ident, $val:ident, $perm:ident $name:ident: $from:literal .. $to:literal) => {
+        $f.field(::core::stringify!($name), &$crate::decode::Hex($crate::get_bits($val, ($from, $to))));
+    };
+
+    ($f:ident, $val:ident, $perm:ident $name:ident: $bit:literal) => {
+        $f.field(::core::stringify!($name), &($val & (1 << $bit) != 0));
+    };
+}
+
+/// Hidden macro that creates the `FieldInfo` for a single field.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_info__ {
+    ($perm:ident $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        $crate::meta::FieldInfo {
+            name: ::core::stringify!($name),
+            perm: $crate::__meta_perm_for_name__!($perm),
+            from: $from,
+            to: $to,
+            kind: $crate::meta::FieldKind::Enum(&[$(
+                $crate::meta::Variant {
+                    name: ::core::stringify!($kind_variant),
+                    value: $kind_variant_val as u64,
+                },
+            )*]),
+        }
+    };
+
+    ($perm:ident $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+        $($kind_variant:ident = $kind_variant_val:expr),*
+    ]) => {
+        $crate::meta::FieldInfo {
+            name: ::core::stringify!($name),
+            perm: $crate::__meta_perm_for_name__!($perm),
+            from: $from,
+            to: $to,
+            kind: $crate::meta::FieldKind::Flags(&[$(
+                $crate::meta::Variant {
+                    name: ::core::stringify!($kind_variant),
+                    value: $kind_variant_val as u64,
+                },
+            )*]),
+        }
+    };
+
+    ($perm:ident $name:ident: $from:literal .. $to:literal) => {
+        $crate::meta::FieldInfo {
+            name: ::core::stringify!($name),
+            perm: $crate::__meta_perm_for_name__!($perm),
+            from: $from,
+            to: $to,
+            kind: $crate::meta::FieldKind::Number,
+        }
+    };
+
+    ($perm:ident $name:ident: $bit:literal) => {
+        $crate::meta::FieldInfo {
+            name: ::core::stringify!($name),
+            perm: $crate::__meta_perm_for_name__!($perm),
+            from: $bit,
+            to: $bit,
+            kind: $crate::meta::FieldKind::Bit,
+        }
+    };
+}
+
+/// Hidden macro that converts a identifier like `r`, `w` and `rw`
+/// to a variant of `meta::Perm`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __meta_perm_for_name__ {
+    (r) => {
+        $crate::meta::Perm::Read
+    };
+
+    (w) => {
+        $crate::meta::Perm::Write
+    };
+
+    (rw) => {
+        $crate::meta::Perm::ReadWrite
+    };
+}
@@ -0,0 +1,310 @@
+//! Runtime information about registers and MMIO blocks.
+//!
+//! The macros of this crate generate `const` tables that describe every register,
+//! field and MMIO block. They can be used to enumerate and decode registers generically,
+//! for example in a shell, a debugger or a test harness.
+//!
+//! - Every register implements [`Describe`], which provides a [`RegisterInfo`].
+//! - Every struct defined using [`define_mmio_struct`](crate::define_mmio_struct) has
+//!   an associated `INFO` constant, which is a [`BlockInfo`].
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::meta::{Describe, FieldKind};
+//!
+//! rumio::define_mmio_register! {
+//!     Ctrl: u32 {
+//!         rw EN: 0,
+//!         r MODE: 1..2 = enum Mode [
+//!             Slow = 0b00,
+//!             Fast = 0b01,
+//!         ],
+//!     }
+//! }
+//!
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart {
+//!         (0x00 => ctrl: Ctrl),
+//!     }
+//! }
+//!
+//! let info = <Ctrl as Describe>::INFO;
+//! assert_eq!(info.name, "Ctrl");
+//! assert_eq!(info.width, 32);
+//!
+//! let mode = info.field("MODE").unwrap();
+//! assert_eq!(mode.extract(0b0010), 0b01);
+//! assert!(matches!(mode.kind, FieldKind::Enum(_)));
+//! assert_eq!(mode.variant(0b01).unwrap().name, "Fast");
+//!
+//! let ctrl = Uart::INFO.register("ctrl").unwrap();
+//! assert_eq!(ctrl.offset, 0x00);
+//! assert_eq!(ctrl.info.name, "Ctrl");
+//! ```
+
+use crate::mmio::{Lit, VolAddr};
+use core::hash::{Hash, Hasher};
+
+/// A type that has a [`RegisterInfo`].
+///
+/// This trait is implemented by the register definition macros.
+pub trait Describe {
+    /// The description of this register.
+    const INFO: RegisterInfo;
+}
+
+/// The access permission of a field or register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Perm {
+    /// Can only be read.
+    Read,
+    /// Can only be written.
+    Write,
+    /// Can be read and written.
+    ReadWrite,
+}
+
+impl Perm {
+    /// Check if this permission allows reading.
+    pub const fn is_readable(self) -> bool {
+        matches!(self, Perm::Read | Perm::ReadWrite)
+    }
+
+    /// Check if this permission allows writing.
+    pub const fn is_writable(self) -> bool {
+        matches!(self, Perm::Write | Perm::ReadWrite)
+    }
+
+    /// Return the permission of a register that contains the given fields.
+    ///
+    /// A register is readable, if at least one field is readable,
+    /// and writable if at least one field is writable.
+    pub const fn of_fields(fields: &[FieldInfo]) -> Perm {
+        let mut read = false;
+        let mut write = false;
+
+        let mut idx = 0;
+        while idx < fields.len() {
+            read |= fields[idx].perm.is_readable();
+            write |= fields[idx].perm.is_writable();
+            idx += 1;
+        }
+
+        match (read, write) {
+            (true, false) => Perm::Read,
+            (false, true) => Perm::Write,
+            _ => Perm::ReadWrite,
+        }
+    }
+}
+
+/// A named value of an enum or flags field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Variant {
+    /// The name of the variant or flag.
+    pub name: &'static str,
+    /// The bits of the variant or flag, relative to the start of the field.
+    pub value: u64,
+}
+
+/// The kind of a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldKind {
+    /// A single bit.
+    Bit,
+    /// A range of bits that represent one of the variants.
+    Enum(&'static [Variant]),
+    /// A range of bits where every bit is a flag.
+    Flags(&'static [Variant]),
+    /// A range of bits that hold a plain number.
+    Number,
+}
+
+/// The description of a single field inside a register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FieldInfo {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The access permission of the field.
+    pub perm: Perm,
+    /// The first bit of this field.
+    pub from: usize,
+    /// The last bit (inclusive) of this field.
+    pub to: usize,
+    /// The kind of this field.
+    pub kind: FieldKind,
+}
+
+impl FieldInfo {
+    /// Return the mask that covers all bits of this field.
+    pub fn mask(&self) -> u64 {
+        crate::set_bits(0, (self.from, self.to), !0)
+    }
+
+    /// Extract the bits of this field from the given raw register value.
+    pub fn extract(&self, raw: u64) -> u64 {
+        crate::get_bits(raw, (self.from, self.to))
+    }
+
+    /// Replace the bits of this field inside the given raw register value.
+    pub fn insert(&self, raw: u64, bits: u64) -> u64 {
+        crate::set_bits(raw, (self.from, self.to), bits)
+    }
+
+    /// Return the variant of this enum field, that matches the given bits.
+    ///
+    /// Always returns `None` if this field is not an enum.
+    pub fn variant(&self, bits: u64) -> Option<&'static Variant> {
+        match self.kind {
+            FieldKind::Enum(variants) => variants.iter().find(|v| v.value == bits),
+            _ => None,
+        }
+    }
+}
+
+/// The description of a register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RegisterInfo {
+    /// The name of the register.
+    pub name: &'static str,
+    /// The width of the register in bits.
+    pub width: usize,
+    /// The access permission of the whole register.
+    pub perm: Perm,
+    /// All fields of this register, in declaration order.
+    pub fields: &'static [FieldInfo],
+}
+
+impl RegisterInfo {
+    /// Find the field with the given name.
+    pub fn field(&self, name: &str) -> Option<&'static FieldInfo> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Return the size of this register in bytes.
+    pub const fn size(&self) -> usize {
+        self.width.div_ceil(8)
+    }
+}
+
+/// A single register inside a [`BlockInfo`].
+#[derive(Clone, Copy, Debug)]
+pub struct RegisterEntry {
+    /// The name of the accessor method.
+    pub name: &'static str,
+    /// The offset in bytes from the start of the block.
+    pub offset: usize,
+    /// The number of registers, if this entry is an array. Otherwise `1`.
+    pub count: usize,
+    /// The description of the register.
+    pub info: &'static RegisterInfo,
+    /// Read the register using its barriers.
+    #[doc(hidden)]
+    pub __read: unsafe fn(VolAddr<u8>) -> u64,
+    /// Write the register using its barriers.
+    #[doc(hidden)]
+    pub __write: unsafe fn(VolAddr<u8>, u64),
+}
+
+impl RegisterEntry {
+    /// Return the offset of the register with the given index inside this entry.
+    pub const fn offset_of(&self, idx: usize) -> usize {
+        self.offset + idx * self.info.size()
+    }
+}
+
+// The access functions are fully determined by the register,
+// so they are ignored, because function pointers can't be compared reliably.
+impl PartialEq for RegisterEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.offset == other.offset
+            && self.count == other.count
+            && self.info == other.info
+    }
+}
+
+impl Eq for RegisterEntry {}
+
+impl Hash for RegisterEntry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        self.offset.hash(state);
+        self.count.hash(state);
+        self.info.hash(state);
+    }
+}
+
+/// The description of a MMIO block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlockInfo {
+    /// The name of the block.
+    pub name: &'static str,
+    /// All registers inside this block, in declaration order.
+    pub registers: &'static [RegisterEntry],
+    /// The names of the registers that must be restored last, in this order.
+    ///
+    /// All other registers are restored before them, in declaration order.
+    pub restore_order: &'static [&'static str],
+}
+
+impl BlockInfo {
+    /// Find the register with the given name.
+    pub fn register(&self, name: &str) -> Option<&'static RegisterEntry> {
+        self.registers.iter().find(|r| r.name == name)
+    }
+
+    /// Check if the block has a register with the given name, in a `const` context.
+    ///
+    /// Used by the generated code.
+    #[doc(hidden)]
+    pub const fn __has_register(&self, name: &str) -> bool {
+        let mut idx = 0;
+        while idx < self.registers.len() {
+            let reg = self.registers[idx].name.as_bytes();
+            let name = name.as_bytes();
+
+            let mut eq = reg.len() == name.len();
+            let mut byte = 0;
+            while eq && byte < reg.len() {
+                eq = reg[byte] == name[byte];
+                byte += 1;
+            }
+
+            if eq {
+                return true;
+            }
+            idx += 1;
+        }
+        false
+    }
+
+    /// Return the number of bytes that are covered by the registers of this block.
+    pub fn size(&self) -> usize {
+        self.registers
+            .iter()
+            .map(|r| r.offset_of(r.count))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Return the size of the largest register of this block in bytes,
+    /// which is the alignment that the address of the block needs.
+    pub fn align(&self) -> usize {
+        self.registers
+            .iter()
+            .map(|r| r.info.size())
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+impl<T> Describe for Lit<T> {
+    const INFO: RegisterInfo = RegisterInfo {
+        name: "Lit",
+        width: core::mem::size_of::<T>() * 8,
+        perm: Perm::ReadWrite,
+        fields: &[],
+    };
+}
@@ -2,10 +2,203 @@
 
 mod macros;
 
+use crate::{
+    barrier::{Barrier, NoBarrier, Ordered},
+    meta::{BlockInfo, Describe},
+    sealed::Sealed,
+    Int,
+};
 use core::{fmt, marker::PhantomData, num::NonZeroUsize};
 
-/// A structure that represents any type, and can be used
-/// to have any type inside a MMIO struct.
+/// A struct that represents a whole MMIO block.
+///
+/// This trait is implemented for every struct that is
+/// defined using [`define_mmio_struct`](crate::define_mmio_struct).
+pub trait Block: Sized {
+    /// The description of this block and all of its registers.
+    const INFO: BlockInfo;
+
+    /// Create a new instance of this block at the given address.
+    ///
+    /// # Safety
+    ///
+    /// The safety arguments of [`VolAddr`] and
+    /// it's `new` method must be guaranteed.
+    unsafe fn new(addr: usize) -> Self;
+}
+
+/// A [`Block`] that can't be copied, and borrows the memory it is located in for the lifetime `'a`.
+///
+/// This trait is implemented for every struct that is defined in the ownership mode
+/// of [`define_mmio_struct`](crate::define_mmio_struct), and allows to safely create
+/// the struct inside memory that is only valid for `'a`, like a
+/// `linux::Mapping`.
+///
+/// # Safety
+///
+/// The type must not be `Copy` or `Clone`, and it must not be possible to access
+/// any register after `'a` ended.
+pub unsafe trait Owned<'a>: Block {}
+
+/// A register type that can be used inside a MMIO struct.
+///
+/// This trait is implemented for [`Lit`] and every register that is defined using
+/// [`define_mmio_register`](crate::define_mmio_register). The register types have
+/// an [`Access`] as their last type parameter, which defaults to [`Shared`].
+pub trait Register: Describe {
+    /// This register type, but with the access `A`.
+    type View<A: Access>;
+
+    /// Create the register at the given address.
+    ///
+    /// Used by the generated code.
+    ///
+    /// # Safety
+    ///
+    /// The safety arguments of [`VolAddr`] and it's `new` method must be guaranteed.
+    #[doc(hidden)]
+    unsafe fn __new<A: Access>(addr: VolAddr<u8>) -> Self::View<A>;
+
+    /// Read the register at the given address using its barriers, and return the raw value.
+    ///
+    /// Used by the generated code.
+    ///
+    /// # Safety
+    ///
+    /// The safety arguments of [`VolAddr`] and it's `new` method must be guaranteed.
+    #[doc(hidden)]
+    unsafe fn __read_raw(addr: VolAddr<u8>) -> u64;
+
+    /// Write the raw value into the register at the given address using its barriers.
+    ///
+    /// Used by the generated code.
+    ///
+    /// # Safety
+    ///
+    /// The safety arguments of [`VolAddr`] and it's `new` method must be guaranteed.
+    #[doc(hidden)]
+    unsafe fn __write_raw(addr: VolAddr<u8>, val: u64);
+}
+
+/// The way a register can be accessed, which is the last type parameter of every register type.
+///
+/// - [`Shared`] registers can be copied, read and written. This is the default, and
+///   it is used by the `Copy` structs of [`define_mmio_struct`](crate::define_mmio_struct).
+/// - [`Borrowed`] registers are returned by the `&self` accessors of owned structs.
+///   They can only be read.
+/// - [`Exclusive`] registers are returned by the `&mut self` accessors of owned structs,
+///   which are the ones marked with `mut`. They can be read and written.
+///
+/// Borrowed and exclusive registers can't be copied, and the lifetime ties them to
+/// the borrow of the struct, so they can't be used after the struct is gone.
+pub trait Access: Sealed {
+    /// The type state of the struct the register was accessed through.
+    ///
+    /// This is [`AnyState`], unless the register was returned by a type state struct.
+    /// The fields of the register can only be accessed if they implement [`InState`]
+    /// for this state.
+    type State;
+}
+
+/// An [`Access`] that allows to write the register.
+pub trait WriteAccess: Access {}
+
+/// The state of registers that are not accessed through a type state struct.
+///
+/// Every field can be accessed in this state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AnyState {}
+
+/// A field that can be accessed while the struct is in the type state `S`.
+///
+/// This trait is implemented by every field of a [`define_mmio_register`](crate::define_mmio_register)
+/// register. A field that is followed by `in [...]` only implements it for the listed states
+/// and [`AnyState`], all other fields implement it for every state.
+#[diagnostic::on_unimplemented(message = "the field `{Self}` can't be accessed in the state `{S}`")]
+pub trait InState<S> {}
+
+/// The [`Access`] of registers that can be copied freely.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Shared {}
+impl Access for Shared {
+    type State = AnyState;
+}
+impl WriteAccess for Shared {}
+impl Sealed for Shared {}
+
+/// The [`Access`] of registers that borrow an owned MMIO struct immutably for `'a`,
+/// while it is in the state `S`.
+pub struct Borrowed<'a, S = AnyState>(PhantomData<(&'a (), S)>);
+impl<S> Access for Borrowed<'_, S> {
+    type State = S;
+}
+impl<S> Sealed for Borrowed<'_, S> {}
+
+/// The [`Access`] of registers that borrow an owned MMIO struct mutably for `'a`,
+/// while it is in the state `S`.
+pub struct Exclusive<'a, S = AnyState>(PhantomData<(&'a mut (), S)>);
+impl<S> Access for Exclusive<'_, S> {
+    type State = S;
+}
+impl<S> WriteAccess for Exclusive<'_, S> {}
+impl<S> Sealed for Exclusive<'_, S> {}
+
+// The register types derive these traits, which requires them for the access type.
+// The access types are never instantiated, so they don't do anything.
+macro_rules! impl_access_traits {
+    ($($access:ident),*) => {$(
+        impl<S> fmt::Debug for $access<'_, S> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(stringify!($access))
+            }
+        }
+
+        impl<S> PartialEq for $access<'_, S> {
+            fn eq(&self, _: &Self) -> bool {
+                true
+            }
+        }
+
+        impl<S> Eq for $access<'_, S> {}
+
+        impl<S> PartialOrd for $access<'_, S> {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<S> Ord for $access<'_, S> {
+            fn cmp(&self, _: &Self) -> core::cmp::Ordering {
+                core::cmp::Ordering::Equal
+            }
+        }
+    )*};
+}
+
+impl_access_traits!(Borrowed, Exclusive);
+
+/// The register type `R` that immutably borrows an owned MMIO struct in the state `S`.
+///
+/// This is returned by the accessors of owned structs, that are not marked with `mut`.
+pub type Ref<'a, R, S = AnyState> = <R as Register>::View<Borrowed<'a, S>>;
+
+/// The register type `R` that mutably borrows an owned MMIO struct in the state `S`.
+///
+/// This is returned by the accessors of owned structs, that are marked with `mut`.
+pub type RefMut<'a, R, S = AnyState> = <R as Register>::View<Exclusive<'a, S>>;
+
+/// The error that is returned when writing a field, which is locked by another field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Locked;
+
+impl fmt::Display for Locked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the field is locked")
+    }
+}
+
+/// A structure that represents any type, and can be used to have
+/// any integer type, that implements [`Int`], inside a MMIO struct.
 ///
 /// # Example
 ///
@@ -18,22 +211,36 @@ use core::{fmt, marker::PhantomData, num::NonZeroUsize};
 ///     }
 /// }
 /// ```
+///
+/// Other types can't be used inside a MMIO struct, because every register must be
+/// accessed by a single read or write:
+///
+/// ```compile_fail
+/// # use rumio::mmio::Lit;
+/// rumio::define_mmio_struct! {
+///     pub struct Device {
+///         (0x00 => bytes: Lit<[u8; 3]>),
+///     }
+/// }
+/// ```
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Lit<T>(VolAddr<T>);
+pub struct Lit<T, A = Shared>(VolAddr<T>, PhantomData<A>);
 
 impl<T> Lit<T> {
     /// Create a new `Lit` at the given address.
     pub fn new(addr: VolAddr<T>) -> Self {
-        Self(addr)
+        Self(addr, PhantomData)
     }
 
     /// Return the size for the inner `T`.
     pub const fn size() -> usize {
         core::mem::size_of::<T>()
     }
+}
 
+impl<T, A: Access> Lit<T, A> {
     /// Return the underlying `VolAddr`.
-    pub fn addr(self) -> VolAddr<T> {
+    pub fn addr(&self) -> VolAddr<T> {
         self.0
     }
 
@@ -42,7 +249,7 @@ impl<T> Lit<T> {
     /// This method is safe, because all safety guarantees must be provided
     /// when creating a new [`VolAddr`], and the [`Copy`] bound prevents the returning value
     /// from running code in the [`Drop`] implementation.
-    pub fn read(self) -> T
+    pub fn read(&self) -> T
     where
         T: Copy,
     {
@@ -56,17 +263,57 @@ impl<T> Lit<T> {
     /// This method doesn't require the `Copy` bound for `T`, and thus the caller
     /// must make sure that dropping the returned value multiple times doesn't cause UB.
     #[inline]
-    pub unsafe fn read_non_copy(self) -> T {
+    pub unsafe fn read_non_copy(&self) -> T {
         self.0.read_non_copy()
     }
 
+    /// Perfoms a volatile read of this address, surrounded by the barriers of `O`.
+    ///
+    /// If `O` is a single [`Barrier`], it is issued after the read.
+    pub fn read_with<O: Ordered>(&self) -> T
+    where
+        T: Copy,
+    {
+        O::BeforeRead::barrier();
+        let val = self.0.read();
+        O::AfterRead::barrier();
+        val
+    }
+}
+
+impl<T, A: WriteAccess> Lit<T, A> {
     /// Performs a volatile write to this address using the given value.
     ///
     /// Note that the `Drop` implementation of `T` will never be run.
     #[inline]
-    pub fn write(self, val: T) {
+    pub fn write(&self, val: T) {
         self.0.write(val);
     }
+
+    /// Performs a volatile write to this address, surrounded by the barriers of `O`.
+    ///
+    /// If `O` is a single [`Barrier`], it is issued before the write.
+    pub fn write_with<O: Ordered>(&self, val: T) {
+        O::BeforeWrite::barrier();
+        self.0.write(val);
+        O::AfterWrite::barrier();
+    }
+}
+
+impl<T: Int> Register for Lit<T> {
+    type View<A: Access> = Lit<T, A>;
+
+    unsafe fn __new<A: Access>(addr: VolAddr<u8>) -> Lit<T, A> {
+        Lit(addr.cast(), PhantomData)
+    }
+
+    unsafe fn __read_raw(addr: VolAddr<u8>) -> u64 {
+        __read::<NoBarrier, T>(addr.cast(), Self::INFO.name, None).to_u64()
+    }
+
+    unsafe fn __write_raw(addr: VolAddr<u8>, val: u64) {
+        __write::<NoBarrier, T>(addr.cast(), T::from_u64(val), Self::INFO.name, None)
+    }
 }
 
 /// An address that can only be accessed by volatile reads and writes.
@@ -134,6 +381,14 @@ impl<T> VolAddr<T> {
         }
     }
 
+    /// Return the address as a number.
+    ///
+    /// Used by the generated code.
+    #[doc(hidden)]
+    pub const fn __addr(self) -> usize {
+        self.addr.get()
+    }
+
     /// Perfoms a volatile read of this address, and returns a copy of the inner `T`.
     ///
     /// This method is safe, because all safety guarantees must be provided
@@ -167,12 +422,82 @@ impl<T> VolAddr<T> {
     }
 }
 
+/// Read the register at the given address surrounded by the barriers of `O`,
+/// and report the access, if the `trace` feature is enabled.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __read<O: Ordered, I: Int>(
+    addr: VolAddr<I>,
+    register: &'static str,
+    field: Option<&'static str>,
+) -> I {
+    O::BeforeRead::barrier();
+    let val = addr.read();
+    O::AfterRead::barrier();
+    #[cfg(feature = "trace")]
+    crate::trace::record(&__access(
+        addr,
+        register,
+        field,
+        val,
+        crate::trace::Direction::Read,
+    ));
+    #[cfg(not(feature = "trace"))]
+    let _ = (register, field);
+    val
+}
+
+/// Write the register at the given address surrounded by the barriers of `O`,
+/// and report the access, if the `trace` feature is enabled.
+///
+/// Used by the generated code.
+#[doc(hidden)]
+#[inline(always)]
+pub fn __write<O: Ordered, I: Int>(
+    addr: VolAddr<I>,
+    val: I,
+    register: &'static str,
+    field: Option<&'static str>,
+) {
+    #[cfg(feature = "trace")]
+    crate::trace::record(&__access(
+        addr,
+        register,
+        field,
+        val,
+        crate::trace::Direction::Write,
+    ));
+    #[cfg(not(feature = "trace"))]
+    let _ = (register, field);
+    O::BeforeWrite::barrier();
+    addr.write(val);
+    O::AfterWrite::barrier();
+}
+
+#[cfg(feature = "trace")]
+#[inline(always)]
+fn __access<I: Int>(
+    addr: VolAddr<I>,
+    register: &'static str,
+    field: Option<&'static str>,
+    val: I,
+    direction: crate::trace::Direction,
+) -> crate::trace::Access {
+    crate::trace::Access {
+        register,
+        field,
+        location: crate::trace::Location::Mmio(addr.addr.get()),
+        width: (core::mem::size_of::<I>() * 8) as u8,
+        value: val.to_u64(),
+        direction,
+    }
+}
+
 impl<T> Clone for VolAddr<T> {
     fn clone(&self) -> Self {
-        Self {
-            addr: self.addr,
-            _type: PhantomData,
-        }
+        *self
     }
 }
 impl<T> Copy for VolAddr<T> {}
@@ -197,7 +522,7 @@ impl<T> Eq for VolAddr<T> {}
 
 impl<T> PartialOrd for VolAddr<T> {
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
-        self.addr.partial_cmp(&other.addr)
+        Some(self.cmp(other))
     }
 }
 impl<T> Ord for VolAddr<T> {
@@ -33,13 +33,91 @@
 /// }
 /// ```
 ///
+/// # Locked fields
+///
+/// A writable field can be followed by `unless LOCK`, where `LOCK` is a single bit
+/// inside the same register. The `set` method of such a field first checks the lock bit,
+/// and returns [`Locked`](crate::mmio::Locked) if it is set. For hot paths, where the lock
+/// is known to be clear, `set_unchecked` skips the check.
+///
+/// ```
+/// rumio::define_mmio_register! {
+///     Ctrl: u32 {
+///         rw EN: 0 unless LOCK,
+///         rw BAUD: 1..8 = enum Baud [
+///             Slow = 0x10,
+///             Fast = 0x01,
+///         ] unless LOCK,
+///         rw LOCK: 31,
+///     }
+/// }
+///
+/// let mut mem = 0u32;
+/// let ctrl = Ctrl::new(unsafe { rumio::mmio::VolAddr::new(&mut mem as *mut u32 as usize) });
+///
+/// assert_eq!(ctrl.BAUD().set(Baud::Fast), Ok(()));
+/// ctrl.LOCK().set(true);
+/// assert_eq!(ctrl.EN().set(true), Err(rumio::mmio::Locked));
+/// assert!(!ctrl.EN().get());
+///
+/// ctrl.EN().set_unchecked(true);
+/// assert!(ctrl.EN().get());
+/// ```
+///
+/// The lock is only checked by the setters of the locked field, by the `try_modify`
+/// method of the register, and by the `modify` steps of [`init`](crate::init) tables.
+/// The register wide `set`, `write` and `modify` methods, and therefore also the `SET`
+/// and `CLEAR` values of the fields, write the register without checking any lock:
+///
+/// ```
+/// # rumio::define_mmio_register! {
+/// #     Ctrl: u32 {
+/// #         rw EN: 0 unless LOCK,
+/// #         rw LOCK: 31,
+/// #     }
+/// # }
+/// # let mut mem = 0u32;
+/// # let ctrl = Ctrl::new(unsafe { rumio::mmio::VolAddr::new(&mut mem as *mut u32 as usize) });
+/// ctrl.LOCK().set(true);
+/// assert_eq!(ctrl.try_modify(EN::SET), Err(rumio::Error::Locked));
+///
+/// ctrl.modify(EN::SET);
+/// assert!(ctrl.EN().get());
+/// ```
+///
+/// Only writable fields can be locked, so `unless` on a read-only field fails to compile:
+///
+/// ```compile_fail
+/// rumio::define_mmio_register! {
+///     Ctrl: u32 {
+///         r EN: 0 unless LOCK,
+///         rw LOCK: 31,
+///     }
+/// }
+/// ```
+///
+/// # Type states
+///
+/// A field can be followed by `in [...]`, a list of states of a type state struct,
+/// which is defined using [`define_mmio_struct`](crate::define_mmio_struct).
+/// The field can then only be accessed through that struct while it is in one of the
+/// listed states. Registers that are not accessed through a type state struct
+/// can always access the field. See [`InState`](crate::mmio::InState) for more details.
+///
+/// # Barriers
+///
+/// A [`Barrier`](crate::barrier::Barrier) can be declared in brackets after the number type,
+/// e.g. `Doorbell: u32 [Dmb] { ... }`. It is issued before every write and after every read
+/// of the register. Using `[before Dmb]` or `[after Dmb]` issues it before or after every
+/// access instead, and `[before Dmb, after Dsb]` issues two different barriers.
+/// See the [`barrier`](crate::barrier) module for more details.
 ///
 /// To explore the whole generated api, take a look at the
 /// `example_generated` module on docs.rs
 #[macro_export]
 macro_rules! define_mmio_register {
     ($(#[$reg_attr:meta])*
-     $reg_name:ident: $num_ty:ty { $(
+     $reg_name:ident: $num_ty:ty $([$($barrier:tt)*])? { $(
      $(#[$field_attr:meta])*
      $perm:ident $name:ident: $from:literal $( .. $to:literal =
          $(#[$kind_attr:meta])*
@@ -49,7 +127,7 @@ macro_rules! define_mmio_register {
                  $kind_variant:ident = $kind_variant_val:expr
              ),*$(,)?
          ]
-     )?
+     )? $(unless $lock:ident)? $(in [$($field_state:path),* $(,)?])?
     ),*$(,)?
     }) => {
         const _: fn() = || {
@@ -73,33 +151,50 @@ macro_rules! define_mmio_register {
             $(#[$field_attr])*
             #[derive(Clone, Copy, Debug, PartialEq, Eq)]
             #[allow(non_camel_case_types)]
-            pub struct $name($crate::mmio::VolAddr<$num_ty>);
+            pub struct $name<A = $crate::mmio::Shared>(
+                $crate::mmio::VolAddr<$num_ty>,
+                ::core::marker::PhantomData<A>,
+            );
         )*
 
         $(#[$reg_attr])*
         #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-        pub struct $reg_name($crate::mmio::VolAddr<$num_ty>);
+        pub struct $reg_name<A = $crate::mmio::Shared>(
+            $crate::mmio::VolAddr<$num_ty>,
+            ::core::marker::PhantomData<A>,
+        );
 
         #[allow(dead_code)]
         impl $reg_name {
             /// Create a new instance of this register at the given address.
             #[inline]
             pub const fn new(addr: $crate::mmio::VolAddr<$num_ty>) -> Self {
-                Self(addr)
+                Self(addr, ::core::marker::PhantomData)
+            }
+
+            /// Wrap the given raw value, so it can be printed field by field.
+            pub fn decode(val: $num_ty) -> $crate::decode::Decoded<Self> {
+                $crate::decode::Decoded::new(val)
             }
+        }
 
+        #[allow(dead_code)]
+        impl<A: $crate::mmio::Access> $reg_name<A> {
             $crate::__generate_if_perm__! { @read
                 /// Get the raw value from this MMIO register.
-                pub fn get(self) -> $num_ty {
-                    $crate::mmio::VolAddr::<$num_ty>::read(self.0)
+                pub fn get(&self) -> $num_ty {
+                    $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::None)
                 }
                 => $($perm) *
             }
 
-            $crate::__generate_if_perm__! { @write
-                /// Write the raw value into this MMIO register.
-                pub fn set(self, val: $num_ty) {
-                    $crate::mmio::VolAddr::<$num_ty>::write(self.0, val);
+            $crate::__generate_if_perm__! { @read
+                /// Get the raw value from this MMIO register, using the barriers of `O`
+                /// instead of the barriers of the register.
+                ///
+                /// If `O` is a single [`Barrier`](crate::barrier::Barrier), it is issued after the read.
+                pub fn get_with<O: $crate::barrier::Ordered>(&self) -> $num_ty {
+                    $crate::mmio::__read::<O, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::None)
                 }
                 => $($perm) *
             }
@@ -108,8 +203,8 @@ macro_rules! define_mmio_register {
                 /// Check if one of the given fields is set.
                 ///
                 /// Returns `true` if the value specified by the field is not null.
-                pub fn is_set<P: $crate::perm::Permission>(self, field: $crate::Field<$num_ty, P>) -> ::core::primitive::bool {
-                    let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
+                pub fn is_set<P: $crate::perm::Permission>(&self, field: $crate::Field<$num_ty, P>) -> ::core::primitive::bool {
+                    let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::None);
                     $crate::Field::<$num_ty, P>::read(field, val) != 0
                 }
                 => $($perm) *
@@ -117,89 +212,425 @@ macro_rules! define_mmio_register {
 
             $crate::__generate_if_perm__! { @read
                 /// Read the given field from this register.
-                pub fn read<P: $crate::perm::Permission>(self, field: $crate::Field<$num_ty, P>) -> $num_ty {
-                    let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
+                pub fn read<P: $crate::perm::Permission>(&self, field: $crate::Field<$num_ty, P>) -> $num_ty {
+                    let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::None);
                     $crate::Field::<$num_ty, P>::read(field, val)
                 }
                 => $($perm) *
             }
 
+            $crate::__generate_if_perm__! { @read
+                /// Read this register once, and return the value which can be printed field by field.
+                pub fn snapshot(&self) -> $crate::decode::Decoded<$reg_name> {
+                    $crate::decode::Decoded::new(self.get())
+                }
+                => $($perm) *
+            }
+
+            $crate::__generate_if_perm__! { @read
+                /// Wait until the given closure returns `true` for the value of this register.
+                ///
+                /// Returns the value that satisfied the condition.
+                pub fn wait_until<D: $crate::poll::Deadline>(
+                    &self,
+                    deadline: D,
+                    mut f: impl FnMut($num_ty) -> ::core::primitive::bool,
+                ) -> ::core::result::Result<$num_ty, $crate::poll::Timeout> {
+                    $crate::poll::wait_until(deadline, || {
+                        let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::None);
+                        if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+                    })
+                }
+                => $($perm) *
+            }
+
+            $crate::__generate_if_perm__! { @read
+                /// Wait until one of the given fields is set.
+                pub fn wait_for_set<P: $crate::perm::Readable, D: $crate::poll::Deadline>(
+                    &self,
+                    field: $crate::Field<$num_ty, P>,
+                    deadline: D,
+                ) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                    $crate::poll::wait_until(deadline, || self.is_set(field).then_some(()))
+                }
+                => $($perm) *
+            }
+
+            $crate::__generate_if_perm__! { @read
+                /// Wait until all of the given fields are cleared.
+                pub fn wait_for_clear<P: $crate::perm::Readable, D: $crate::poll::Deadline>(
+                    &self,
+                    field: $crate::Field<$num_ty, P>,
+                    deadline: D,
+                ) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                    $crate::poll::wait_until(deadline, || (!self.is_set(field)).then_some(()))
+                }
+                => $($perm) *
+            }
+
+            $crate::__if_async__! {
+                $crate::__generate_if_perm__! { @read
+                    /// `async` version of [`wait_until`](Self::wait_until).
+                    pub fn wait_until_async<S: $crate::future::WakerSource>(
+                        &self,
+                        source: S,
+                        mut f: impl FnMut($num_ty) -> ::core::primitive::bool,
+                    ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<$num_ty>> {
+                        let this = Self(self.0, ::core::marker::PhantomData);
+                        $crate::future::wait_until(source, move || {
+                            let val = this.get();
+                            if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+                        })
+                    }
+                    => $($perm) *
+                }
+
+                $crate::__generate_if_perm__! { @read
+                    /// `async` version of [`wait_for_set`](Self::wait_for_set).
+                    pub fn wait_for_set_async<P: $crate::perm::Readable, S: $crate::future::WakerSource>(
+                        &self,
+                        field: $crate::Field<$num_ty, P>,
+                        source: S,
+                    ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<()>> {
+                        let this = Self(self.0, ::core::marker::PhantomData);
+                        $crate::future::wait_until(source, move || this.is_set(field).then_some(()))
+                    }
+                    => $($perm) *
+                }
+
+                $crate::__generate_if_perm__! { @read
+                    /// `async` version of [`wait_for_clear`](Self::wait_for_clear).
+                    pub fn wait_for_clear_async<P: $crate::perm::Readable, S: $crate::future::WakerSource>(
+                        &self,
+                        field: $crate::Field<$num_ty, P>,
+                        source: S,
+                    ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<()>> {
+                        let this = Self(self.0, ::core::marker::PhantomData);
+                        $crate::future::wait_until(source, move || (!this.is_set(field)).then_some(()))
+                    }
+                    => $($perm) *
+                }
+            }
+
+            $(#[allow(non_snake_case)]
+            $(#[$field_attr])*
+            pub fn $name(&self) -> $name<A>
+            where
+                $name: $crate::mmio::InState<<A as $crate::mmio::Access>::State>,
+            {
+                $name(self.0, ::core::marker::PhantomData)
+            })*
+        }
+
+        #[allow(dead_code)]
+        impl<A: $crate::mmio::WriteAccess> $reg_name<A> {
+            $crate::__generate_if_perm__! { @write
+                /// Write the raw value into this MMIO register, using the barriers of `O`
+                /// instead of the barriers of the register.
+                ///
+                /// If `O` is a single [`Barrier`](crate::barrier::Barrier), it is issued before the write.
+                /// Fields that are locked by another field are not checked.
+                pub fn set_with<O: $crate::barrier::Ordered>(&self, val: $num_ty) {
+                    $crate::mmio::__write::<O, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::None);
+                }
+                => $($perm) *
+            }
+
+            $crate::__generate_if_perm__! { @write
+                /// Write the raw value into this MMIO register.
+                ///
+                /// Fields that are locked by another field are not checked.
+                pub fn set(&self, val: $num_ty) {
+                    $crate::mmio::__write::<$reg_name, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::None);
+                }
+                => $($perm) *
+            }
+
             $crate::__generate_if_perm__! { @write
                 /// Write the given values into this register and set all other bits to 0.
-                pub fn write(self, val: $crate::Value<$num_ty>) {
+                ///
+                /// Fields that are locked by another field are not checked.
+                pub fn write(&self, val: $crate::Value<$num_ty>) {
                     let val = $crate::Value::<$num_ty>::modify(val, 0);
-                    $crate::mmio::VolAddr::<$num_ty>::write(self.0, val);
+                    $crate::mmio::__write::<$reg_name, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::None);
                 }
                 => $($perm) *
             }
 
             $crate::__generate_if_perm__! { @read_write
                 /// Modify this register to match the given value, but keep all other bits untouched.
-                pub fn modify(self, val: $crate::Value<$num_ty>) {
-                    let reg = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
+                ///
+                /// Fields that are locked by another field are not checked, use `try_modify` instead.
+                pub fn modify(&self, val: $crate::Value<$num_ty>) {
+                    let reg = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::None);
                     let reg = $crate::Value::<$num_ty>::modify(val, reg);
-                    $crate::mmio::VolAddr::<$num_ty>::write(self.0, reg);
+                    $crate::mmio::__write::<$reg_name, _>(self.0, reg, ::core::stringify!($reg_name), ::core::option::Option::None);
                 }
                 => $($perm) *
             }
 
-            $(#[allow(non_snake_case)]
-            $(#[$field_attr])*
-            pub fn $name(&self) -> $name {
-                $name(self.0)
-            })*
+            $crate::__generate_if_perm__! { @read_write
+                /// Like [`modify`](Self::modify), but returns an error without touching the register,
+                /// if the value covers any bit that is not part of a writable field, or a field
+                /// which is currently locked.
+                pub fn try_modify(&self, val: $crate::Value<$num_ty>) -> ::core::result::Result<(), $crate::Error> {
+                    let writable = <$reg_name as $crate::meta::Describe>::INFO
+                        .fields
+                        .iter()
+                        .filter(|field| field.perm.is_writable())
+                        .fold(0, |mask, field| mask | field.mask());
+                    if $crate::Int::to_u64($crate::Value::<$num_ty>::mask(val)) & !writable != 0 {
+                        return ::core::result::Result::Err($crate::Error::OutOfRange);
+                    }
+                    let reg = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::None);
+                    $( $crate::define_mmio_register!(@lock_check, [$($lock)?], reg, val, $from $(.. $to)?); )*
+                    let reg = $crate::Value::<$num_ty>::modify(val, reg);
+                    $crate::mmio::__write::<$reg_name, _>(self.0, reg, ::core::stringify!($reg_name), ::core::option::Option::None);
+                    ::core::result::Result::Ok(())
+                }
+                => $($perm) *
+            }
+        }
+
+        impl $crate::barrier::Ordered for $reg_name {
+            type BeforeRead = <$crate::define_mmio_register!(@ordered $($($barrier)*)?) as $crate::barrier::Ordered>::BeforeRead;
+            type AfterRead = <$crate::define_mmio_register!(@ordered $($($barrier)*)?) as $crate::barrier::Ordered>::AfterRead;
+            type BeforeWrite = <$crate::define_mmio_register!(@ordered $($($barrier)*)?) as $crate::barrier::Ordered>::BeforeWrite;
+            type AfterWrite = <$crate::define_mmio_register!(@ordered $($($barrier)*)?) as $crate::barrier::Ordered>::AfterWrite;
+        }
+
+        impl $crate::mmio::Register for $reg_name {
+            type View<A: $crate::mmio::Access> = $reg_name<A>;
+
+            unsafe fn __new<A: $crate::mmio::Access>(addr: $crate::mmio::VolAddr<u8>) -> $reg_name<A> {
+                $reg_name($crate::mmio::VolAddr::cast(addr), ::core::marker::PhantomData)
+            }
+
+            unsafe fn __read_raw(addr: $crate::mmio::VolAddr<u8>) -> u64 {
+                let val = $crate::mmio::__read::<$reg_name, $num_ty>($crate::mmio::VolAddr::cast(addr), ::core::stringify!($reg_name), ::core::option::Option::None);
+                $crate::Int::to_u64(val)
+            }
+
+            unsafe fn __write_raw(addr: $crate::mmio::VolAddr<u8>, val: u64) {
+                let val = <$num_ty as $crate::Int>::from_u64(val);
+                $crate::mmio::__write::<$reg_name, $num_ty>($crate::mmio::VolAddr::cast(addr), val, ::core::stringify!($reg_name), ::core::option::Option::None);
+            }
+        }
+
+        impl<A: $crate::mmio::Access> $crate::init::InitRegister for $reg_name<A> {
+            type Int = $num_ty;
+
+            fn __addr(&self) -> ::core::primitive::usize {
+                $crate::mmio::VolAddr::__addr(self.0)
+            }
+
+            #[allow(unused)]
+            fn __check_locks(&self, reg: $num_ty, val: $crate::Value<$num_ty>) -> ::core::result::Result<(), $crate::Error> {
+                $( $crate::define_mmio_register!(@lock_check, [$($lock)?], reg, val, $from $(.. $to)?); )*
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        $crate::__generate_if_perm__! { @read
+            impl<A: $crate::mmio::Access> $crate::init::InitRead for $reg_name<A> {
+                fn __get(&self) -> $num_ty {
+                    self.get()
+                }
+            }
+            => $($perm) *
+        }
+
+        $crate::__generate_if_perm__! { @write
+            impl<A: $crate::mmio::WriteAccess> $crate::init::InitWrite for $reg_name<A> {
+                fn __set(&self, val: $num_ty) {
+                    self.set(val)
+                }
+            }
+            => $($perm) *
+        }
+
+        impl $crate::meta::Describe for $reg_name {
+            const INFO: $crate::meta::RegisterInfo = {
+                const FIELDS: &[$crate::meta::FieldInfo] = &[$(
+                    $crate::__field_info__!($perm $name: $from $(.. $to =
+                        $kind_type $kind_name [
+                            $($kind_variant = $kind_variant_val),*
+                        ]
+                    )?),
+                )*];
+
+                $crate::meta::RegisterInfo {
+                    name: ::core::stringify!($reg_name),
+                    width: ::core::mem::size_of::<$num_ty>() * 8,
+                    perm: $crate::meta::Perm::of_fields(FIELDS),
+                    fields: FIELDS,
+                }
+            };
+        }
+
+        impl $crate::decode::Decode for $reg_name {
+            type Int = $num_ty;
+
+            const NAME: &'static ::core::primitive::str = ::core::stringify!($reg_name);
+
+            #[allow(unused)]
+            fn decode_fields(val: $num_ty, f: &mut ::core::fmt::DebugStruct<'_, '_>) {
+                $(
+                    $crate::__decode_field__!(f, val, $perm $name: $from $(.. $to =
+                        $kind_type $kind_name [
+                            $($kind_variant = $kind_variant_val),*
+                        ]
+                    )?);
+                )*
+            }
         }
 
         $(
-            $crate::define_mmio_register!(@internal, $num_ty, $perm $name: $from $(.. $to =
+            $crate::define_mmio_register!(@internal, $num_ty, $reg_name, [$($lock)?], $perm $name: $from $(.. $to =
                 $kind_type $kind_name [
                     $($kind_variant = $kind_variant_val),*
                 ]
             )?);
+
+            $crate::define_mmio_register!(@check_lock, $perm $name $(, $lock)?);
+            $crate::define_mmio_register!(@in_state, $name $(, [$($field_state),*])?);
         )*
     };
 
+    (@ordered) => { $crate::barrier::NoBarrier };
+    (@ordered before $before:ty, after $after:ty) => { $crate::barrier::Both<$before, $after> };
+    (@ordered before $before:ty) => { $crate::barrier::Before<$before> };
+    (@ordered after $after:ty) => { $crate::barrier::After<$after> };
+    (@ordered $barrier:ty) => { $barrier };
+
+    (@check_lock, r $name:ident, $lock:ident) => {
+        ::core::compile_error!(::core::concat!(
+            "the read-only field `", ::core::stringify!($name),
+            "` can't be locked by `", ::core::stringify!($lock), "`",
+        ));
+    };
+
+    (@check_lock, $perm:ident $name:ident $(, $lock:ident)?) => {};
+
+    (@in_state, $name:ident) => {
+        impl<S> $crate::mmio::InState<S> for $name {}
+    };
+
+    (@in_state, $name:ident, [$($state:path),*]) => {
+        impl $crate::mmio::InState<$crate::mmio::AnyState> for $name {}
+        $( impl $crate::mmio::InState<$state> for $name {} )*
+    };
+
     // =====================================
     // Read and write bitflags
     // =====================================
 
-    (@internal, $num_ty:ty, rw $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+    (@internal, $num_ty:ty, $reg_name:ident, $lock:tt, rw $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        $crate::define_mmio_register!(@internal, $num_ty, r $name: $from .. $to = flags $kind_name [
+        $crate::define_mmio_register!(@internal, $num_ty, $reg_name, $lock, r $name: $from .. $to = flags $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
 
-        $crate::define_mmio_register!(@internal, $num_ty, w $name: $from .. $to = flags $kind_name [
+        $crate::define_mmio_register!(@internal, $num_ty, $reg_name, $lock, w $name: $from .. $to = flags $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
     };
 
-    (@internal, $num_ty:ty, r $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+    (@internal, $num_ty:ty, $reg_name:ident, $lock:tt, r $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        impl $name {
+        impl<A: $crate::mmio::Access> $name<A> {
             /// Read the raw bits from the register and return a struct representing
             /// all flags of this bit range.
             #[allow(unused)]
             pub fn get(&self) -> $kind_name {
-                let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
+                let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
                 $kind_name::from_bits_truncate($crate::get_bits(val, ($from, $to)))
             }
+
+            /// Like [`get`](Self::get), but returns an error if any bit is set, that isn't a known flag.
+            #[allow(unused)]
+            pub fn try_get(&self) -> ::core::result::Result<$kind_name, $crate::Error> {
+                let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+                $kind_name::from_bits($crate::get_bits(val, ($from, $to))).ok_or($crate::Error::InvalidPattern)
+            }
+
+            /// Wait until all of the given flags are set.
+            #[allow(unused)]
+            pub fn wait_for_set<D: $crate::poll::Deadline>(&self, flags: $kind_name, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || self.get().contains(flags).then_some(()))
+            }
+
+            /// Wait until none of the given flags are set.
+            #[allow(unused)]
+            pub fn wait_for_clear<D: $crate::poll::Deadline>(&self, flags: $kind_name, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || (!self.get().intersects(flags)).then_some(()))
+            }
+
+            /// Wait until the given closure returns `true` for the flags of this bit range.
+            ///
+            /// Returns the flags that satisfied the condition.
+            #[allow(unused)]
+            pub fn wait_until<D: $crate::poll::Deadline>(
+                &self,
+                deadline: D,
+                mut f: impl FnMut($kind_name) -> ::core::primitive::bool,
+            ) -> ::core::result::Result<$kind_name, $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || {
+                    let flags = self.get();
+                    if f(flags) { ::core::option::Option::Some(flags) } else { ::core::option::Option::None }
+                })
+            }
+
+            $crate::__if_async__! {
+                /// `async` version of [`wait_for_set`](Self::wait_for_set).
+                #[allow(unused)]
+                pub fn wait_for_set_async<S: $crate::future::WakerSource>(
+                    &self,
+                    flags: $kind_name,
+                    source: S,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<()>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || this.get().contains(flags).then_some(()))
+                }
+
+                /// `async` version of [`wait_for_clear`](Self::wait_for_clear).
+                #[allow(unused)]
+                pub fn wait_for_clear_async<S: $crate::future::WakerSource>(
+                    &self,
+                    flags: $kind_name,
+                    source: S,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<()>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || (!this.get().intersects(flags)).then_some(()))
+                }
+
+                /// `async` version of [`wait_until`](Self::wait_until).
+                #[allow(unused)]
+                pub fn wait_until_async<S: $crate::future::WakerSource>(
+                    &self,
+                    source: S,
+                    mut f: impl FnMut($kind_name) -> ::core::primitive::bool,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<$kind_name>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || {
+                        let flags = this.get();
+                        if f(flags) { ::core::option::Option::Some(flags) } else { ::core::option::Option::None }
+                    })
+                }
+            }
         }
     };
 
-    (@internal, $num_ty:ty, w $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
+    (@internal, $num_ty:ty, $reg_name:ident, $lock:tt, w $name:ident: $from:literal .. $to:literal = flags $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        impl $name {
-            /// Set this bit range to the given bitflags.
-            #[allow(unused)]
-            pub fn set(&self, flags: $kind_name) {
-                let bits = $kind_name::bits(&flags);
-                let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
-                $crate::mmio::VolAddr::<$num_ty>::write(self.0, $crate::set_bits(val, ($from, $to), bits));
-            }
+        impl<A: $crate::mmio::WriteAccess> $name<A> {
+            $crate::define_mmio_register!(@set, $lock, $reg_name, $name, ($from, $to),
+                /// Set this bit range to the given bitflags.
+                fn(flags: $kind_name) -> { $kind_name::bits(&flags) }
+            );
         }
     };
 
@@ -207,48 +638,100 @@ macro_rules! define_mmio_register {
     // Read and write a enum range of bits
     // =====================================
 
-    (@internal, $num_ty:ty, rw $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+    (@internal, $num_ty:ty, $reg_name:ident, $lock:tt, rw $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        $crate::define_mmio_register!(@internal, $num_ty, r $name: $from .. $to = enum $kind_name [
+        $crate::define_mmio_register!(@internal, $num_ty, $reg_name, $lock, r $name: $from .. $to = enum $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
 
-        $crate::define_mmio_register!(@internal, $num_ty, w $name: $from .. $to = enum $kind_name [
+        $crate::define_mmio_register!(@internal, $num_ty, $reg_name, $lock, w $name: $from .. $to = enum $kind_name [
             $($kind_variant = $kind_variant_val),*
         ]);
     };
 
-    (@internal, $num_ty:ty, r $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+    (@internal, $num_ty:ty, $reg_name:ident, $lock:tt, r $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        impl $name {
+        impl<A: $crate::mmio::Access> $name<A> {
             /// Read the raw bits from the register, and then try to map them to an enum.
             #[allow(unused)]
             pub fn get(&self) -> ::core::option::Option<$kind_name> {
-                let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
+                let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
                 match $crate::get_bits(val, ($from, $to)) {
                     $($kind_variant_val => ::core::option::Option::Some($kind_name::$kind_variant),)*
                     _ => ::core::option::Option::None,
                 }
             }
+
+            /// Like [`get`](Self::get), but returns an error if the bits don't match any variant.
+            #[allow(unused)]
+            pub fn try_get(&self) -> ::core::result::Result<$kind_name, $crate::Error> {
+                self.get().ok_or($crate::Error::InvalidPattern)
+            }
+
+            /// Wait until this bit range holds the given variant.
+            #[allow(unused)]
+            pub fn wait_for<D: $crate::poll::Deadline>(&self, variant: $kind_name, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || (self.get() == ::core::option::Option::Some(variant)).then_some(()))
+            }
+
+            /// Wait until the given closure returns `true` for the value of this bit range.
+            ///
+            /// Returns the value that satisfied the condition.
+            #[allow(unused)]
+            pub fn wait_until<D: $crate::poll::Deadline>(
+                &self,
+                deadline: D,
+                mut f: impl FnMut(::core::option::Option<$kind_name>) -> ::core::primitive::bool,
+            ) -> ::core::result::Result<::core::option::Option<$kind_name>, $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || {
+                    let val = self.get();
+                    if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+                })
+            }
+
+            $crate::__if_async__! {
+                /// `async` version of [`wait_for`](Self::wait_for).
+                #[allow(unused)]
+                pub fn wait_for_async<S: $crate::future::WakerSource>(
+                    &self,
+                    variant: $kind_name,
+                    source: S,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<()>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || (this.get() == ::core::option::Option::Some(variant)).then_some(()))
+                }
+
+                /// `async` version of [`wait_until`](Self::wait_until).
+                #[allow(unused)]
+                pub fn wait_until_async<S: $crate::future::WakerSource>(
+                    &self,
+                    source: S,
+                    mut f: impl FnMut(::core::option::Option<$kind_name>) -> ::core::primitive::bool,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<::core::option::Option<$kind_name>>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || {
+                        let val = this.get();
+                        if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+                    })
+                }
+            }
         }
     };
 
-    (@internal, $num_ty:ty, w $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
+    (@internal, $num_ty:ty, $reg_name:ident, $lock:tt, w $name:ident: $from:literal .. $to:literal = enum $kind_name:ident [
         $($kind_variant:ident = $kind_variant_val:expr),*
     ]) => {
-        impl $name {
-            /// Set this bits to the given value.
-            #[allow(unused)]
-            pub fn set(&self, val: $kind_name) {
-                let bits = match val {
-                    $($kind_name::$kind_variant => $kind_variant_val,)*
-                };
-                let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
-                let val = $crate::set_bits(val, ($from, $to), bits);
-                $crate::mmio::VolAddr::<$num_ty>::write(self.0, val);
-            }
+        impl<A: $crate::mmio::WriteAccess> $name<A> {
+            $crate::define_mmio_register!(@set, $lock, $reg_name, $name, ($from, $to),
+                /// Set this bits to the given value.
+                fn(variant: $kind_name) -> {
+                    match variant {
+                        $($kind_name::$kind_variant => $kind_variant_val,)*
+                    }
+                }
+            );
         }
     };
 
@@ -256,53 +739,233 @@ macro_rules! define_mmio_register {
     // Read and write a single bit
     // =====================================
 
-    (@internal, $num_ty:ty, $perm:ident $name:ident: $bit:literal) => {
+    (@internal, $num_ty:ty, $reg_name:ident, $lock:tt, $perm:ident $name:ident: $bit:literal) => {
         impl $name {
             /// A `Field` that covers this single bit.
             #[allow(unused)]
             pub const FIELD: $crate::Field<$num_ty, $crate::__perm_for_name__!($perm)> = $crate::Field::<$num_ty, _>::new(1 << $bit);
         }
 
-        $crate::define_mmio_register!(@internal_bit, $num_ty, $perm $name: $bit);
+        $crate::define_mmio_register!(@internal_bit, $num_ty, $reg_name, $lock, $perm $name: $bit);
     };
 
-    (@internal_bit, $num_ty:ty, rw $name:ident: $bit:literal) => {
-        $crate::define_mmio_register!(@internal_bit, $num_ty, r $name: $bit);
-        $crate::define_mmio_register!(@internal_bit, $num_ty, w $name: $bit);
+    (@internal_bit, $num_ty:ty, $reg_name:ident, $lock:tt, rw $name:ident: $bit:literal) => {
+        $crate::define_mmio_register!(@internal_bit, $num_ty, $reg_name, $lock, r $name: $bit);
+        $crate::define_mmio_register!(@internal_bit, $num_ty, $reg_name, $lock, w $name: $bit);
     };
 
-    (@internal_bit, $num_ty:ty, r $name:ident: $bit:literal) => {
-        impl $name {
+    (@internal_bit, $num_ty:ty, $reg_name:ident, $lock:tt, r $name:ident: $bit:literal) => {
+        impl<A: $crate::mmio::Access> $name<A> {
             /// Check if this bit is set inside the MMIO.
             #[allow(unused)]
             pub fn get(&self) -> ::core::primitive::bool {
-                let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
+                let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
                 val & (1 << $bit) != 0
             }
+
+            /// Wait until this bit is set.
+            #[allow(unused)]
+            pub fn wait_for_set<D: $crate::poll::Deadline>(&self, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || self.get().then_some(()))
+            }
+
+            /// Wait until this bit is cleared.
+            #[allow(unused)]
+            pub fn wait_for_clear<D: $crate::poll::Deadline>(&self, deadline: D) -> ::core::result::Result<(), $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || (!self.get()).then_some(()))
+            }
+
+            /// Wait until the given closure returns `true` for the value of this bit.
+            ///
+            /// Returns the value that satisfied the condition.
+            #[allow(unused)]
+            pub fn wait_until<D: $crate::poll::Deadline>(
+                &self,
+                deadline: D,
+                mut f: impl FnMut(::core::primitive::bool) -> ::core::primitive::bool,
+            ) -> ::core::result::Result<::core::primitive::bool, $crate::poll::Timeout> {
+                $crate::poll::wait_until(deadline, || {
+                    let val = self.get();
+                    if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+                })
+            }
+
+            $crate::__if_async__! {
+                /// `async` version of [`wait_for_set`](Self::wait_for_set).
+                #[allow(unused)]
+                pub fn wait_for_set_async<S: $crate::future::WakerSource>(
+                    &self,
+                    source: S,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<()>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || this.get().then_some(()))
+                }
+
+                /// `async` version of [`wait_for_clear`](Self::wait_for_clear).
+                #[allow(unused)]
+                pub fn wait_for_clear_async<S: $crate::future::WakerSource>(
+                    &self,
+                    source: S,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<()>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || (!this.get()).then_some(()))
+                }
+
+                /// `async` version of [`wait_until`](Self::wait_until).
+                #[allow(unused)]
+                pub fn wait_until_async<S: $crate::future::WakerSource>(
+                    &self,
+                    source: S,
+                    mut f: impl FnMut(::core::primitive::bool) -> ::core::primitive::bool,
+                ) -> $crate::future::Wait<S, impl FnMut() -> ::core::option::Option<::core::primitive::bool>> {
+                    let this = Self(self.0, ::core::marker::PhantomData);
+                    $crate::future::wait_until(source, move || {
+                        let val = this.get();
+                        if f(val) { ::core::option::Option::Some(val) } else { ::core::option::Option::None }
+                    })
+                }
+            }
         }
     };
 
-    (@internal_bit, $num_ty:ty, w $name:ident: $bit:literal) => {
+    (@internal_bit, $num_ty:ty, $reg_name:ident, [$($lock:ident)?], w $name:ident: $bit:literal) => {
         impl $name {
             /// A `Value` that will set this bit to high when modifying a register.
+            $(
+                ///
+                #[doc = ::core::concat!("`", ::core::stringify!($lock), "` is only checked if the value is written using `try_modify`.")]
+            )?
             #[allow(unused)]
             pub const SET: $crate::Value<$num_ty> = $crate::Value::<$num_ty>::new(1 << $bit, 1 << $bit);
 
             /// A `Value` that will set this bit to low when modifying a register.
+            $(
+                ///
+                #[doc = ::core::concat!("`", ::core::stringify!($lock), "` is only checked if the value is written using `try_modify`.")]
+            )?
             #[allow(unused)]
             pub const CLEAR: $crate::Value<$num_ty> = $crate::Value::<$num_ty>::new(1 << $bit, 0);
+        }
 
-            /// Set the value of this bit inside the MMIO.
-            #[allow(unused)]
-            pub fn set(&self, x: ::core::primitive::bool) {
-                const MASK: $num_ty = 1 << $bit;
-                let val = $crate::mmio::VolAddr::<$num_ty>::read(self.0);
-                let val = match x {
-                    true => val | MASK,
-                    false => val & !MASK,
-                };
-                $crate::mmio::VolAddr::<$num_ty>::write(self.0, val);
+        impl<A: $crate::mmio::WriteAccess> $name<A> {
+            $crate::define_mmio_register!(@set, [$($lock)?], $reg_name, $name, ($bit, $bit),
+                /// Set the value of this bit inside the MMIO.
+                fn(x: ::core::primitive::bool) -> { x as $num_ty }
+            );
+        }
+    };
+
+    // =====================================
+    // Setters of a field
+    // =====================================
+
+    (@set, [], $reg_name:ident, $name:ident, $range:tt,
+        $(#[$attr:meta])*
+        fn($arg:ident: $arg_ty:ty) -> $bits:block
+    ) => {
+        $(#[$attr])*
+        #[allow(unused)]
+        pub fn set(&self, $arg: $arg_ty) {
+            let bits = $bits;
+            let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            let val = $crate::set_bits(val, $range, bits);
+            $crate::mmio::__write::<$reg_name, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+        }
+
+        /// Like [`set`](Self::set), but writes the register using the barriers of `O`,
+        /// instead of the barriers of the register.
+        #[allow(unused)]
+        pub fn set_with<O: $crate::barrier::Ordered>(&self, $arg: $arg_ty) {
+            let bits = $bits;
+            let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            let val = $crate::set_bits(val, $range, bits);
+            $crate::mmio::__write::<O, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+        }
+
+        $crate::define_mmio_register!(@try_set, [], $reg_name, $name, $range, fn($arg: $arg_ty) -> $bits);
+    };
+
+    (@set, [$lock:ident], $reg_name:ident, $name:ident, $range:tt,
+        $(#[$attr:meta])*
+        fn($arg:ident: $arg_ty:ty) -> $bits:block
+    ) => {
+        $(#[$attr])*
+        ///
+        #[doc = ::core::concat!("Returns an error, without touching the register, if `", ::core::stringify!($lock), "` is set.")]
+        #[allow(unused)]
+        pub fn set(&self, $arg: $arg_ty) -> ::core::result::Result<(), $crate::mmio::Locked> {
+            let bits = $bits;
+            let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            if $crate::Field::read($lock::FIELD, val) != 0 {
+                return ::core::result::Result::Err($crate::mmio::Locked);
+            }
+            let val = $crate::set_bits(val, $range, bits);
+            $crate::mmio::__write::<$reg_name, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            ::core::result::Result::Ok(())
+        }
+
+        $(#[$attr])*
+        ///
+        #[doc = ::core::concat!("Unlike [`set`](Self::set), this doesn't check if `", ::core::stringify!($lock), "` is set.")]
+        #[allow(unused)]
+        pub fn set_unchecked(&self, $arg: $arg_ty) {
+            let bits = $bits;
+            let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            let val = $crate::set_bits(val, $range, bits);
+            $crate::mmio::__write::<$reg_name, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+        }
+
+        /// Like [`set`](Self::set), but writes the register using the barriers of `O`,
+        /// instead of the barriers of the register.
+        #[allow(unused)]
+        pub fn set_with<O: $crate::barrier::Ordered>(&self, $arg: $arg_ty) -> ::core::result::Result<(), $crate::mmio::Locked> {
+            let bits = $bits;
+            let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            if $crate::Field::read($lock::FIELD, val) != 0 {
+                return ::core::result::Result::Err($crate::mmio::Locked);
             }
+            let val = $crate::set_bits(val, $range, bits);
+            $crate::mmio::__write::<O, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            ::core::result::Result::Ok(())
+        }
+
+        $crate::define_mmio_register!(@try_set, [$lock], $reg_name, $name, $range, fn($arg: $arg_ty) -> $bits);
+    };
+
+    (@try_set, [$($lock:ident)?], $reg_name:ident, $name:ident, $range:tt,
+        fn($arg:ident: $arg_ty:ty) -> $bits:block
+    ) => {
+        /// Set the value of this field, or return an error if the value doesn't fit into the field.
+        $(
+            ///
+            #[doc = ::core::concat!("Also returns an error, without touching the register, if `", ::core::stringify!($lock), "` is set.")]
+        )?
+        #[allow(unused)]
+        pub fn try_set(&self, $arg: $arg_ty) -> ::core::result::Result<(), $crate::Error> {
+            let bits = $crate::__check_range($bits, $range)?;
+            let val = $crate::mmio::__read::<$reg_name, _>(self.0, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            $(
+                if $crate::Field::read($lock::FIELD, val) != 0 {
+                    return ::core::result::Result::Err($crate::Error::Locked);
+                }
+            )?
+            let val = $crate::set_bits(val, $range, bits);
+            $crate::mmio::__write::<$reg_name, _>(self.0, val, ::core::stringify!($reg_name), ::core::option::Option::Some(::core::stringify!($name)));
+            ::core::result::Result::Ok(())
+        }
+    };
+
+    (@lock_check, [], $reg:ident, $val:ident, $($bits:tt)*) => {};
+
+    (@lock_check, [$lock:ident], $reg:ident, $val:ident, $from:literal) => {
+        $crate::define_mmio_register!(@lock_check, [$lock], $reg, $val, $from .. $from);
+    };
+
+    (@lock_check, [$lock:ident], $reg:ident, $val:ident, $from:literal .. $to:literal) => {
+        if $crate::Value::mask($val) & $crate::set_bits(0, ($from, $to), !0) != 0
+            && $crate::Field::read($lock::FIELD, $reg) != 0
+        {
+            return ::core::result::Result::Err($crate::Error::Locked);
         }
     };
 }
@@ -353,18 +1016,524 @@ macro_rules! define_mmio_register {
 /// ```
 ///
 ///
-/// To explore the whole generated api, take a look at the
-/// `example_generated` module on docs.rs
-#[macro_export]
-macro_rules! define_mmio_struct {
-    ($(#[$attr:meta])*
-     $pub:vis struct $name:ident {$(
-         $(#[$field_attr:meta])*
-         ($field_offset:expr => $field_name:ident: $field_ty:ty)
-    ),*$(,)?}) => { $crate::defile::item! {
-        $(#[$attr])*
-        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-        $pub struct $name($crate::mmio::VolAddr<u8>);
+/// # Ownership
+///
+/// By default the generated struct is `Copy`, so any number of copies of it can exist.
+/// If the struct is declared with a lifetime parameter, an owned handle is generated
+/// instead:
+///
+/// - The struct is not `Copy` or `Clone`.
+/// - The lifetime ties the struct to the memory that backs it, see `new_in`.
+/// - Every accessor borrows the struct. Registers that are marked with `mut` are accessed
+///   through `&mut self` and return a [`RefMut`](crate::mmio::RefMut), which can be read
+///   and written. All other registers are accessed through `&self` and return a
+///   [`Ref`](crate::mmio::Ref), which can only be read.
+/// - The returned registers and their fields can't be copied, and can't outlive the borrow
+///   of the struct. See [`Access`](crate::mmio::Access) for more details.
+///
+/// ```
+/// # use rumio::mmio::Lit;
+/// rumio::define_mmio_struct! {
+///     pub struct Uart<'a> {
+///         (0x00 => status: Lit<u32>),
+///         (0x04 => mut data: Lit<u32>),
+///         (0x08 => mut fifo: [Lit<u8>; 4]),
+///     }
+/// }
+///
+/// fn transmit(uart: &mut Uart<'_>, byte: u32) {
+///     uart.data().write(byte);
+/// }
+///
+/// let mut mem = [0u32; 4];
+/// let mut uart = unsafe { Uart::new_in(mem.as_mut_ptr() as usize, &mem) };
+/// transmit(&mut uart, 0xAB);
+/// assert_eq!(uart.status().read(), 0);
+/// ```
+///
+/// Writing a register that is not marked with `mut` fails to compile:
+///
+/// ```compile_fail
+/// # use rumio::mmio::Lit;
+/// rumio::define_mmio_struct! {
+///     pub struct Uart<'a> {
+///         (0x00 => status: Lit<u32>),
+///     }
+/// }
+///
+/// let mut mem = 0u32;
+/// let uart = unsafe { Uart::new_in(&mut mem as *mut u32 as usize, &mem) };
+/// uart.status().write(1);
+/// ```
+///
+/// And so does using a register after the struct is gone:
+///
+/// ```compile_fail
+/// # use rumio::mmio::Lit;
+/// rumio::define_mmio_struct! {
+///     pub struct Uart<'a> {
+///         (0x00 => mut data: Lit<u32>),
+///     }
+/// }
+///
+/// let mut mem = 0u32;
+/// let mut uart = unsafe { Uart::new_in(&mut mem as *mut u32 as usize, &mem) };
+/// let data = uart.data();
+/// drop(uart);
+/// data.write(1);
+/// ```
+///
+/// # Singletons
+///
+/// A struct that is declared with a fixed address after an `@` becomes a singleton.
+/// It has no `new` method; instead `take` returns the struct only the first time it is called,
+/// so at most one owner of the block can exist. Like owned structs, it is not `Copy`
+/// and every accessor borrows it.
+///
+/// The address must be a single token, e.g. a literal, a constant, or an expression in parentheses.
+///
+/// ```
+/// # use rumio::mmio::Lit;
+/// rumio::define_mmio_struct! {
+///     pub struct Timer @ 0x4000_1000 {
+///         (0x00 => count: Lit<u32>),
+///     }
+/// }
+///
+/// let timer = Timer::take().unwrap();
+/// assert!(Timer::take().is_none());
+/// assert_eq!(Timer::ADDRESS, 0x4000_1000);
+/// # drop(timer);
+/// ```
+///
+/// For situations where the owner can't be reached anymore, like panic handlers,
+/// the `unsafe` method `steal` returns the struct even if it was taken before.
+/// A singleton can't be created inside a `linux::Mapping`, because it is
+/// not an owned struct.
+///
+/// # Type states
+///
+/// Some registers are only meaningful while the block is in a certain mode.
+/// These modes can be encoded in the type of the struct, by declaring the `states`
+/// and the `transitions` between them after the registers.
+///
+/// - The struct gets a type parameter for the current state. The states are declared as
+///   `states name [...]`, and one marker type is generated for every state, inside a new
+///   module with the given name.
+/// - A register that is followed by `in [...]` can only be accessed in the listed states.
+///   All other registers can be accessed in every state.
+/// - A field of a register can be restricted in the same way, by following it with `in [...]`
+///   inside [`define_mmio_register`](crate::define_mmio_register). The registers returned by
+///   the struct carry its state, so the field can only be accessed in the listed states.
+/// - Every transition consumes the struct and returns it in the new state. It can have an action,
+///   which is a closure that receives the struct in the old state mutably, and is run before
+///   the transition.
+///
+/// Like owned structs, the struct is not `Copy`, every accessor borrows it, and only registers
+/// marked with `mut` can be written.
+///
+/// ```
+/// rumio::define_mmio_register! {
+///     Ctrl: u32 {
+///         rw EN: 0,
+///         rw BAUD: 1..4 = enum Baud [
+///             Slow = 0b1000,
+///             Fast = 0b0001,
+///         ] in [uart_state::Disabled],
+///     }
+/// }
+///
+/// rumio::define_mmio_register! {
+///     Fifo: u32 {
+///         rw DATA: 0..7 = enum Data [
+///             Zero = 0,
+///         ],
+///     }
+/// }
+///
+/// rumio::define_mmio_struct! {
+///     pub struct Uart {
+///         (0x00 => mut ctrl: Ctrl),
+///         (0x04 => mut fifo: Fifo) in [Enabled],
+///     }
+///
+///     states uart_state [Disabled, Enabled]
+///
+///     transitions {
+///         enable: Disabled => Enabled = |uart| uart.ctrl().EN().set(true),
+///         disable: Enabled => Disabled = |uart| uart.ctrl().EN().set(false),
+///     }
+/// }
+///
+/// use uart_state::Disabled;
+///
+/// let mut mem = [0u32; 2];
+/// let mut uart = unsafe { Uart::<Disabled>::new(mem.as_mut_ptr() as usize) };
+/// uart.ctrl().BAUD().set(Baud::Fast);
+///
+/// let mut uart = uart.enable();
+/// assert!(uart.ctrl().EN().get());
+/// uart.fifo().DATA().set(Data::Zero);
+/// // `uart.ctrl().BAUD()` doesn't exist in the `Enabled` state
+/// let _uart: Uart<Disabled> = uart.disable();
+/// ```
+///
+/// The struct can only be created in one of its states, and not in
+/// [`AnyState`](crate::mmio::AnyState), which would allow to access every field:
+///
+/// ```compile_fail
+/// # use rumio::mmio::Lit;
+/// rumio::define_mmio_struct! {
+///     pub struct Uart {
+///         (0x04 => mut baud: Lit<u32>),
+///     }
+///
+///     states uart_state [Disabled, Enabled]
+///
+///     transitions {
+///         enable: Disabled => Enabled,
+///     }
+/// }
+///
+/// let uart = unsafe { Uart::<rumio::mmio::AnyState>::new(0x1000) };
+/// ```
+///
+/// Accessing a register in any other state fails to compile:
+///
+/// ```compile_fail
+/// # use rumio::mmio::Lit;
+/// rumio::define_mmio_struct! {
+///     pub struct Uart {
+///         (0x04 => mut baud: Lit<u32>) in [Disabled],
+///     }
+///
+///     states uart_state [Disabled, Enabled]
+///
+///     transitions {
+///         enable: Disabled => Enabled,
+///     }
+/// }
+///
+/// let mut uart = unsafe { Uart::<uart_state::Enabled>::new(0x1000) };
+/// uart.baud().write(0);
+/// ```
+///
+/// And so does accessing a field in any other state:
+///
+/// ```compile_fail
+/// rumio::define_mmio_register! {
+///     Ctrl: u32 {
+///         rw EN: 0,
+///         rw BAUD: 1..4 = enum Baud [
+///             Fast = 0b0001,
+///         ] in [uart_state::Disabled],
+///     }
+/// }
+///
+/// rumio::define_mmio_struct! {
+///     pub struct Uart {
+///         (0x00 => mut ctrl: Ctrl),
+///     }
+///
+///     states uart_state [Disabled, Enabled]
+///
+///     transitions {
+///         enable: Disabled => Enabled,
+///     }
+/// }
+///
+/// let mut uart = unsafe { Uart::<uart_state::Enabled>::new(0x1000) };
+/// uart.ctrl().BAUD().set(Baud::Fast);
+/// ```
+///
+/// # Saving state
+///
+/// Every struct has a `save` and a `restore` method. The registers of an optional
+/// `restore [...]` list after the registers are restored last, in the given order.
+/// See the [`save`](crate::save) module for more details.
+///
+/// To explore the whole generated api, take a look at the
+/// `example_generated` module on docs.rs
+#[macro_export]
+macro_rules! define_mmio_struct {
+    ($(#[$attr:meta])*
+     $pub:vis struct $name:ident {$(
+         $(#[$field_attr:meta])*
+         ($($entry:tt)*) $(in [$($field_state:ident),* $(,)?])?
+     ),*$(,)?}
+
+     $(restore [$($order:ident),* $(,)?])?
+
+     states $states:ident [$($(#[$state_attr:meta])* $state:ident),* $(,)?]
+
+     transitions {$(
+         $(#[$trans_attr:meta])*
+         $trans:ident: $from:ident => $to:ident $(= $action:expr)?
+     ),*$(,)?}) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        $pub struct $name<S>(
+            $crate::mmio::VolAddr<u8>,
+            ::core::marker::PhantomData<S>,
+        );
+
+        #[doc = ::core::concat!("The states of [`", ::core::stringify!($name), "`].")]
+        $pub mod $states {
+            $(
+                $(#[$state_attr])*
+                #[derive(Debug)]
+                pub enum $state {}
+            )*
+        }
+
+        $(
+            impl $name<$states::$state> {
+                /// Create a new MMIO region at the given address, which is in this state.
+                ///
+                /// The struct can only be created in one of its declared states, so the
+                /// state can't be skipped by creating it in another type, like `AnyState`.
+                ///
+                /// # Safety
+                ///
+                /// The safety arguments of `VolAddr` and
+                /// it's `new` method must be guaranteed,
+                /// and the hardware must be in this state.
+                #[allow(unused)]
+                pub const unsafe fn new(addr: ::core::primitive::usize) -> Self {
+                    Self(
+                        $crate::mmio::VolAddr::<u8>::new(addr),
+                        ::core::marker::PhantomData,
+                    )
+                }
+
+                $crate::define_mmio_struct!(@init_mut);
+            }
+
+            impl $crate::mmio::Block for $name<$states::$state> {
+                const INFO: $crate::meta::BlockInfo = $name::<$states::$state>::INFO;
+
+                unsafe fn new(addr: ::core::primitive::usize) -> Self {
+                    $name::<$states::$state>::new(addr)
+                }
+            }
+        )*
+
+        impl<S> $name<S> {
+            /// The description of this MMIO block and all of its registers.
+            #[allow(unused)]
+            pub const INFO: $crate::meta::BlockInfo = $crate::meta::BlockInfo {
+                name: ::core::stringify!($name),
+                registers: &[$(
+                    $crate::define_mmio_struct!(@owned_entry, $($entry)*),
+                )*],
+                restore_order: &[$($(::core::stringify!($order)),*)?],
+            };
+
+            $crate::define_mmio_struct!(@save, { 0 $(+ $crate::define_mmio_struct!(@owned_count, $($entry)*))* });
+            // the accessors depend on the state, so the `restore` list is checked using `INFO`
+            $crate::define_mmio_struct!(@restore_mut, { 0 $(+ $crate::define_mmio_struct!(@owned_count, $($entry)*))* }, []);
+        }
+
+        const _: () = {$($(
+            ::core::assert!(
+                $crate::meta::BlockInfo::__has_register(&$name::<()>::INFO, ::core::stringify!($order)),
+                ::core::concat!("`", ::core::stringify!($order), "` is not a register of `", ::core::stringify!($name), "`"),
+            );
+        )*)?};
+
+        $(
+            $crate::define_mmio_struct!(
+                @state_field, $name, $states, [$($($field_state),*)?], $(#[$field_attr])*, $($entry)*
+            );
+        )*
+
+        $(
+            impl $name<$states::$from> {
+                $(#[$trans_attr])*
+                #[allow(unused)]
+                pub fn $trans(mut self) -> $name<$states::$to> {
+                    $(
+                        let action: fn(&mut Self) = $action;
+                        action(&mut self);
+                    )?
+                    $name(self.0, ::core::marker::PhantomData)
+                }
+            }
+        )*
+
+    };
+
+    ($(#[$attr:meta])*
+     $pub:vis struct $name:ident @ $base:tt {$(
+         $(#[$field_attr:meta])*
+         ($($entry:tt)*)
+    ),*$(,)?}
+
+     $(restore [$($order:ident),* $(,)?])?) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        $pub struct $name($crate::mmio::VolAddr<u8>);
+
+        impl $name {
+            /// The address of this MMIO block.
+            #[allow(unused)]
+            pub const ADDRESS: ::core::primitive::usize = $base;
+
+            /// The description of this MMIO block and all of its registers.
+            #[allow(unused)]
+            pub const INFO: $crate::meta::BlockInfo = $crate::meta::BlockInfo {
+                name: ::core::stringify!($name),
+                registers: &[$(
+                    $crate::define_mmio_struct!(@owned_entry, $($entry)*),
+                )*],
+                restore_order: &[$($(::core::stringify!($order)),*)?],
+            };
+
+            fn __taken() -> &'static ::core::sync::atomic::AtomicBool {
+                static TAKEN: ::core::sync::atomic::AtomicBool =
+                    ::core::sync::atomic::AtomicBool::new(false);
+                &TAKEN
+            }
+
+            /// Return the MMIO block, if it wasn't taken before.
+            ///
+            /// This returns `Some` exactly once.
+            ///
+            /// On targets without atomic compare-and-swap, like `thumbv6m-none-eabi`,
+            /// the flag is checked and set using a separate load and store, so calls
+            /// of this method must not race with each other, e.g. from an interrupt handler.
+            #[allow(unused)]
+            pub fn take() -> ::core::option::Option<Self> {
+                #[cfg(target_has_atomic = "8")]
+                let taken = Self::__taken().swap(true, ::core::sync::atomic::Ordering::AcqRel);
+
+                #[cfg(not(target_has_atomic = "8"))]
+                let taken = {
+                    let taken = Self::__taken().load(::core::sync::atomic::Ordering::Acquire);
+                    Self::__taken().store(true, ::core::sync::atomic::Ordering::Release);
+                    taken
+                };
+
+                if taken {
+                    ::core::option::Option::None
+                } else {
+                    ::core::option::Option::Some(unsafe { Self::steal() })
+                }
+            }
+
+            /// Return the MMIO block, even if it was taken before.
+            ///
+            /// # Safety
+            ///
+            /// The MMIO block may be aliased afterwards, so the caller
+            /// must ensure that no other owner of the block is used anymore.
+            #[allow(unused)]
+            pub unsafe fn steal() -> Self {
+                Self::__taken().store(true, ::core::sync::atomic::Ordering::Release);
+                Self($crate::mmio::VolAddr::<u8>::new(Self::ADDRESS))
+            }
+
+            $crate::define_mmio_struct!(@save, { 0 $(+ $crate::define_mmio_struct!(@owned_count, $($entry)*))* });
+            $crate::define_mmio_struct!(@init_mut);
+            $crate::define_mmio_struct!(@restore_mut, { 0 $(+ $crate::define_mmio_struct!(@owned_count, $($entry)*))* }, [$($($order),*)?]);
+
+            $( $crate::define_mmio_struct!(@owned_field, $crate::mmio::AnyState, $(#[$field_attr])*, $($entry)*); )*
+        }
+
+        impl $crate::mmio::Block for $name {
+            const INFO: $crate::meta::BlockInfo = $name::INFO;
+
+            unsafe fn new(addr: ::core::primitive::usize) -> Self {
+                Self($crate::mmio::VolAddr::<u8>::new(addr))
+            }
+        }
+    };
+
+    ($(#[$attr:meta])*
+     $pub:vis struct $name:ident<$lt:lifetime> {$(
+         $(#[$field_attr:meta])*
+         ($($entry:tt)*)
+    ),*$(,)?}
+
+     $(restore [$($order:ident),* $(,)?])?) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        $pub struct $name<$lt>(
+            $crate::mmio::VolAddr<u8>,
+            ::core::marker::PhantomData<&$lt mut ()>,
+        );
+
+        impl<$lt> $name<$lt> {
+            /// Create a new MMIO region at the given address.
+            ///
+            /// # Safety
+            ///
+            /// The safety arguments of `VolAddr` and
+            /// it's `new` method must be guaranteed,
+            /// for as long as the lifetime of the returned struct.
+            #[allow(unused)]
+            pub const unsafe fn new(addr: ::core::primitive::usize) -> Self {
+                Self(
+                    $crate::mmio::VolAddr::<u8>::new(addr),
+                    ::core::marker::PhantomData,
+                )
+            }
+
+            /// Create a new MMIO region at the given address, which
+            /// can't outlive `backing`, the owner of the memory.
+            ///
+            /// # Safety
+            ///
+            /// The safety arguments of `VolAddr` and
+            /// it's `new` method must be guaranteed,
+            /// for as long as `backing` is alive.
+            #[allow(unused)]
+            pub unsafe fn new_in<T: ?::core::marker::Sized>(
+                addr: ::core::primitive::usize,
+                backing: &$lt T,
+            ) -> Self {
+                let _ = backing;
+                Self::new(addr)
+            }
+
+            /// The description of this MMIO block and all of its registers.
+            #[allow(unused)]
+            pub const INFO: $crate::meta::BlockInfo = $crate::meta::BlockInfo {
+                name: ::core::stringify!($name),
+                registers: &[$(
+                    $crate::define_mmio_struct!(@owned_entry, $($entry)*),
+                )*],
+                restore_order: &[$($(::core::stringify!($order)),*)?],
+            };
+
+            $crate::define_mmio_struct!(@save, { 0 $(+ $crate::define_mmio_struct!(@owned_count, $($entry)*))* });
+            $crate::define_mmio_struct!(@init_mut);
+            $crate::define_mmio_struct!(@restore_mut, { 0 $(+ $crate::define_mmio_struct!(@owned_count, $($entry)*))* }, [$($($order),*)?]);
+
+            $( $crate::define_mmio_struct!(@owned_field, $crate::mmio::AnyState, $(#[$field_attr])*, $($entry)*); )*
+        }
+
+        impl<$lt> $crate::mmio::Block for $name<$lt> {
+            const INFO: $crate::meta::BlockInfo = $name::INFO;
+
+            unsafe fn new(addr: ::core::primitive::usize) -> Self {
+                $name::new(addr)
+            }
+        }
+
+        unsafe impl<$lt> $crate::mmio::Owned<$lt> for $name<$lt> {}
+    };
+
+    ($(#[$attr:meta])*
+     $pub:vis struct $name:ident {$(
+         $(#[$field_attr:meta])*
+         ($field_offset:expr => $field_name:ident: $field_ty:ty)
+    ),*$(,)?}
+
+     $(restore [$($order:ident),* $(,)?])?) => { $crate::defile::item! {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        $pub struct $name($crate::mmio::VolAddr<u8>);
 
         impl $name {
             /// Create a new MMIO region at the given address.
@@ -378,17 +1547,221 @@ macro_rules! define_mmio_struct {
                 Self($crate::mmio::VolAddr::<u8>::new(addr))
             }
 
+            /// The description of this MMIO block and all of its registers.
+            #[allow(unused)]
+            pub const INFO: $crate::meta::BlockInfo = $crate::meta::BlockInfo {
+                name: ::core::stringify!($name),
+                registers: &[$(
+                    $crate::define_mmio_struct!(@@entry, $field_name, @$field_ty, $field_offset),
+                )*],
+                restore_order: &[$($(::core::stringify!($order)),*)?],
+            };
+
+            $crate::define_mmio_struct!(@@save, { 0 $(+ $crate::define_mmio_struct!(@@count, @$field_ty))* });
+            $crate::define_mmio_struct!(@@init);
+            $crate::define_mmio_struct!(@@restore, { 0 $(+ $crate::define_mmio_struct!(@@count, @$field_ty))* }, [$($($order),*)?]);
+
             $( $crate::define_mmio_struct!(@@create_field, $(#[$field_attr])*, $field_name, @$field_ty, $field_offset); )*
         }
+
+        impl $crate::mmio::Block for $name {
+            const INFO: $crate::meta::BlockInfo = $name::INFO;
+
+            unsafe fn new(addr: ::core::primitive::usize) -> Self {
+                $name::new(addr)
+            }
+        }
     }};
 
+    (@save, $len:tt) => {
+        /// Save the values of all registers, that have a readable and writable field.
+        #[allow(unused)]
+        pub fn save(&self) -> $crate::save::Saved<$len> {
+            unsafe { $crate::save::__save(self.0, &Self::INFO) }
+        }
+    };
+
+    (@init) => {
+        /// Run the steps of the initialization table in order, and stop at the first step that fails.
+        ///
+        /// Every wait uses a new deadline that is created by `deadline`, and every delay
+        /// calls `delay` with the number of ticks. See the `rumio::init` module for more details.
+        #[allow(unused)]
+        pub fn init<D: $crate::poll::Deadline>(
+            &self,
+            table: &[$crate::init::RegisterInit<Self>],
+            deadline: impl FnMut() -> D,
+            delay: impl FnMut(::core::primitive::u64),
+        ) -> ::core::result::Result<(), $crate::init::InitError> {
+            let mut block = *self;
+            $crate::init::__run(self.0, &mut block, table, deadline, delay)
+        }
+    };
+
+    (@init_mut) => {
+        /// Run the steps of the initialization table in order, and stop at the first step that fails.
+        ///
+        /// Every wait uses a new deadline that is created by `deadline`, and every delay
+        /// calls `delay` with the number of ticks. See the `rumio::init` module for more details.
+        #[allow(unused)]
+        pub fn init<D: $crate::poll::Deadline>(
+            &mut self,
+            table: &[$crate::init::RegisterInit<Self>],
+            deadline: impl FnMut() -> D,
+            delay: impl FnMut(::core::primitive::u64),
+        ) -> ::core::result::Result<(), $crate::init::InitError> {
+            $crate::init::__run(self.0, self, table, deadline, delay)
+        }
+    };
+
+    (@restore, $len:tt, [$($order:ident),*]) => {
+        /// Write the saved values of the readable and writable fields back into the registers.
+        ///
+        /// The registers are written in declaration order, except the
+        /// registers of the `restore` list, which are written last.
+        #[allow(unused)]
+        pub fn restore(&self, saved: &$crate::save::Saved<$len>) {
+            $( let _ = Self::$order; )*
+            ::core::debug_assert_eq!(saved.block(), &Self::INFO);
+            unsafe { $crate::save::__restore(self.0, &Self::INFO, saved) }
+        }
+    };
+
+    (@restore_mut, $len:tt, [$($order:ident),*]) => {
+        /// Write the saved values of the readable and writable fields back into the registers.
+        ///
+        /// The registers are written in declaration order, except the
+        /// registers of the `restore` list, which are written last.
+        /// For type state structs, all registers are written regardless of the current state.
+        #[allow(unused)]
+        pub fn restore(&mut self, saved: &$crate::save::Saved<$len>) {
+            $( let _ = Self::$order; )*
+            ::core::debug_assert_eq!(saved.block(), &Self::INFO);
+            unsafe { $crate::save::__restore(self.0, &Self::INFO, saved) }
+        }
+    };
+
+    (@count, [$T:ty; $N:expr]) => { $N };
+
+    (@count, $T:ty) => { 1 };
+
+    (@owned_count, $off:expr => mut $name:ident: $($T:tt)*) => {
+        $crate::define_mmio_struct!(@count, $($T)*)
+    };
+
+    (@owned_count, $off:expr => $name:ident: $($T:tt)*) => {
+        $crate::define_mmio_struct!(@count, $($T)*)
+    };
+
+    (@state_field, $name:ident, $states:ident, [], $(#[$attr:meta])*, $($entry:tt)*) => {
+        impl<S> $name<S> {
+            $crate::define_mmio_struct!(@owned_field, S, $(#[$attr])*, $($entry)*);
+        }
+    };
+
+    (@state_field, $name:ident, $states:ident, [$state:ident], $(#[$attr:meta])*, $($entry:tt)*) => {
+        impl $name<$states::$state> {
+            $crate::define_mmio_struct!(@owned_field, $states::$state, $(#[$attr])*, $($entry)*);
+        }
+    };
+
+    (@state_field, $name:ident, $states:ident, [$state:ident, $($rest:ident),+], $(#[$attr:meta])*, $($entry:tt)*) => {
+        $crate::define_mmio_struct!(@state_field, $name, $states, [$state], $(#[$attr])*, $($entry)*);
+        $crate::define_mmio_struct!(@state_field, $name, $states, [$($rest),+], $(#[$attr])*, $($entry)*);
+    };
+
+    (@owned_entry, $off:expr => mut $name:ident: $($T:tt)*) => {
+        $crate::define_mmio_struct!(@owned_entry, $off => $name: $($T)*)
+    };
+
+    (@owned_entry, $off:expr => $name:ident: [$T:ty; $N:expr]) => {
+        $crate::define_mmio_struct!(@entry, $name, [$T; $N], $off)
+    };
+
+    (@owned_entry, $off:expr => $name:ident: $T:ty) => {
+        $crate::define_mmio_struct!(@entry, $name, $T, $off)
+    };
+
+    (@owned_field, $S:ty, $(#[$attr:meta])*, $off:expr => mut $name:ident: [$T:ty; $N:expr]) => {
+        $(#[$attr])*
+        #[allow(unused)]
+        pub fn $name(&mut self, idx: usize) -> $crate::mmio::RefMut<'_, $T, $S> {
+            $crate::define_mmio_struct!(@owned_get, self, [$T; $N], $off, idx)
+        }
+    };
+
+    (@owned_field, $S:ty, $(#[$attr:meta])*, $off:expr => mut $name:ident: $T:ty) => {
+        $(#[$attr])*
+        #[allow(unused, non_snake_case)]
+        pub fn $name(&mut self) -> $crate::mmio::RefMut<'_, $T, $S> {
+            $crate::define_mmio_struct!(@owned_get, self, $T, $off)
+        }
+    };
+
+    (@owned_field, $S:ty, $(#[$attr:meta])*, $off:expr => $name:ident: [$T:ty; $N:expr]) => {
+        $(#[$attr])*
+        #[allow(unused)]
+        pub fn $name(&self, idx: usize) -> $crate::mmio::Ref<'_, $T, $S> {
+            $crate::define_mmio_struct!(@owned_get, self, [$T; $N], $off, idx)
+        }
+    };
+
+    (@owned_field, $S:ty, $(#[$attr:meta])*, $off:expr => $name:ident: $T:ty) => {
+        $(#[$attr])*
+        #[allow(unused, non_snake_case)]
+        pub fn $name(&self) -> $crate::mmio::Ref<'_, $T, $S> {
+            $crate::define_mmio_struct!(@owned_get, self, $T, $off)
+        }
+    };
+
+    (@owned_get, $self:ident, [$T:ty; $N:expr], $off:expr, $idx:ident) => {{
+        let offset = ($off + <$T>::size() * $idx) as isize;
+        unsafe {
+            <$T as $crate::mmio::Register>::__new(
+                $crate::mmio::VolAddr::offset($self.0, offset)
+            )
+        }
+    }};
+
+    (@owned_get, $self:ident, $T:ty, $off:expr) => {{
+        let offset: isize = $off;
+        unsafe {
+            <$T as $crate::mmio::Register>::__new(
+                $crate::mmio::VolAddr::offset($self.0, offset)
+            )
+        }
+    }};
+
+    (@entry, $name:ident, [$T:ty; $N:expr], $off:expr) => {
+        $crate::meta::RegisterEntry {
+            name: ::core::stringify!($name),
+            offset: $off,
+            count: $N,
+            info: &<$T as $crate::meta::Describe>::INFO,
+            __read: <$T as $crate::mmio::Register>::__read_raw,
+            __write: <$T as $crate::mmio::Register>::__write_raw,
+        }
+    };
+
+    (@entry, $name:ident, $T:ty, $off:expr) => {
+        $crate::meta::RegisterEntry {
+            name: ::core::stringify!($name),
+            offset: $off,
+            count: 1,
+            info: &<$T as $crate::meta::Describe>::INFO,
+            __read: <$T as $crate::mmio::Register>::__read_raw,
+            __write: <$T as $crate::mmio::Register>::__write_raw,
+        }
+    };
+
     (@create_field, $(#[$attr:meta])*, $name:ident, [$T:ty; $N:expr], $off:expr) => {
         $(#[$attr])*
         #[allow(unused)]
         pub fn $name(&self, idx: usize) -> $T {
+            let offset = ($off + <$T>::size() * idx) as isize;
             <$T>::new(unsafe {
                 $crate::mmio::VolAddr::cast(
-                    $crate::mmio::VolAddr::offset(self.0, ($off + <$T>::size() * idx) as isize)
+                    $crate::mmio::VolAddr::offset(self.0, offset)
                 )
             })
         }
@@ -398,9 +1771,10 @@ macro_rules! define_mmio_struct {
         $(#[$attr])*
         #[allow(unused, non_snake_case)]
         pub fn $name(&self) -> $T {
+            let offset: isize = $off;
             <$T>::new(unsafe {
                 $crate::mmio::VolAddr::cast(
-                    $crate::mmio::VolAddr::offset(self.0, $off)
+                    $crate::mmio::VolAddr::offset(self.0, offset)
                 )
             })
         }
@@ -0,0 +1,149 @@
+//! Utilities for waiting until a register reaches a specific state.
+//!
+//! Every readable register and field that is generated by the macros of this crate
+//! has `wait_*` methods, which spin on the register until a condition is met.
+//! How long they are allowed to spin is controlled by a [`Deadline`].
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::poll::{self, Iterations, Timeout};
+//!
+//! let mut busy = 3;
+//! let res = poll::wait_until(Iterations::new(10), || {
+//!     busy -= 1;
+//!     if busy == 0 { Some(()) } else { None }
+//! });
+//! assert_eq!(res, Ok(()));
+//!
+//! let res = poll::wait_until(Iterations::new(10), || None::<()>);
+//! assert_eq!(res, Err(Timeout));
+//! ```
+
+use core::fmt;
+
+/// The error that is returned if a condition was not met before the deadline expired.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Timeout;
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("timed out while waiting for a register condition")
+    }
+}
+
+/// A monotonic source of time.
+///
+/// The unit of the returned ticks is up to the implementation,
+/// it only has to be the same unit that is used for [`ClockDeadline`].
+pub trait Clock {
+    /// Return the current time in ticks.
+    ///
+    /// The counter is allowed to wrap around.
+    fn now(&self) -> u64;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> u64 {
+        C::now(*self)
+    }
+}
+
+/// Decides when a polling loop has to give up.
+pub trait Deadline {
+    /// Called every time the condition was checked and not met.
+    ///
+    /// Returns `true` if no further attempts should be made.
+    fn expired(&mut self) -> bool;
+}
+
+impl<D: Deadline + ?Sized> Deadline for &mut D {
+    fn expired(&mut self) -> bool {
+        D::expired(*self)
+    }
+}
+
+/// A [`Deadline`] that never expires.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Forever;
+
+impl Deadline for Forever {
+    #[inline]
+    fn expired(&mut self) -> bool {
+        false
+    }
+}
+
+/// A [`Deadline`] that expires after the condition was checked a fixed number of times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Iterations(usize);
+
+impl Iterations {
+    /// Create a new budget that allows to check the condition at most `count` times.
+    ///
+    /// The condition is always checked at least once, even if `count` is `0`.
+    pub const fn new(count: usize) -> Self {
+        Self(count)
+    }
+
+    /// Return the number of remaining attempts.
+    pub const fn remaining(&self) -> usize {
+        self.0
+    }
+}
+
+impl Deadline for Iterations {
+    #[inline]
+    fn expired(&mut self) -> bool {
+        self.0 = self.0.saturating_sub(1);
+        self.0 == 0
+    }
+}
+
+/// A [`Deadline`] that expires after a given amount of ticks of a [`Clock`] have passed.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockDeadline<C> {
+    clock: C,
+    start: u64,
+    ticks: u64,
+}
+
+impl<C: Clock> ClockDeadline<C> {
+    /// Create a new deadline that expires `ticks` ticks after now.
+    pub fn after(clock: C, ticks: u64) -> Self {
+        let start = clock.now();
+        Self {
+            clock,
+            start,
+            ticks,
+        }
+    }
+}
+
+impl<C: Clock> Deadline for ClockDeadline<C> {
+    #[inline]
+    fn expired(&mut self) -> bool {
+        self.clock.now().wrapping_sub(self.start) >= self.ticks
+    }
+}
+
+/// Repeatedly call `poll` until it returns `Some`, or the deadline expires.
+///
+/// This is the building block for all generated `wait_*` methods.
+pub fn wait_until<D, T, F>(mut deadline: D, mut poll: F) -> Result<T, Timeout>
+where
+    D: Deadline,
+    F: FnMut() -> Option<T>,
+{
+    loop {
+        if let Some(val) = poll() {
+            return Ok(val);
+        }
+
+        if deadline.expired() {
+            return Err(Timeout);
+        }
+
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,529 @@
+//! Ready-made definitions for the standard RISC-V machine and supervisor CSRs.
+//!
+//! Every CSR has its own module, which contains the register type, that implements
+//! [`RegisterRead`](crate::cpu::RegisterRead) and [`RegisterWrite`](crate::cpu::RegisterWrite),
+//! and the API that is generated by [`define_cpu_register`](crate::define_cpu_register).
+//! CSRs which only hold a single value, like `mepc` or `mscratch`, only have plain `read`
+//! and `write` functions.
+//!
+//! On RISC-V, the registers are accessed using the `csrr` and `csrw` instructions.
+//! Single bit fields are set and cleared using the native `csrs` and `csrc` instructions,
+//! so changing them is a single instruction, that can't race with an interrupt handler.
+//! Fields with multiple bits, and `modify`, read the CSR, change the bits and write it back.
+//!
+//! The CSRs only exist on RISC-V, and on other targets if the `std` feature is enabled.
+//! There every CSR is a mock register from the `cpu::mock` module, so code that uses them
+//! can be tested on the host.
+//!
+//! Fields whose position depends on `XLEN`, like `mstatus.SD`, are not defined.
+//!
+//! The layout of page table entries is defined in [`paging`], which is available on every target.
+//!
+//! This module is only available with the `riscv` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rumio::riscv::{mie, mstatus};
+//!
+//! mie::MTIE::set(true);
+//! mstatus::MPP::set(mstatus::Mode::Supervisor);
+//! mstatus::MIE::set(true);
+//!
+//! println!("{}", mstatus::snapshot());
+//! ```
+
+pub mod paging;
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+/// Define the register type for a CSR with the given number, and implement the CSR traits.
+///
+/// On other targets, the register is a mock register instead.
+macro_rules! csr {
+    ($name:ident = $num:literal) => {
+        #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+        csr!(@read $name = $num);
+
+        #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+        impl crate::cpu::RegisterWrite<usize> for $name {
+            #[inline]
+            fn write(val: usize) {
+                csr!(@asm "csrw {csr}, {0}", in(reg) val, csr = const $num);
+            }
+
+            #[inline]
+            fn set(mask: usize) {
+                csr!(@asm "csrs {csr}, {0}", in(reg) mask, csr = const $num);
+            }
+
+            #[inline]
+            fn clear(mask: usize) {
+                csr!(@asm "csrc {csr}, {0}", in(reg) mask, csr = const $num);
+            }
+        }
+
+        #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+        csr!(@mock $name = $num);
+    };
+
+    ($name:ident = $num:literal, r) => {
+        #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+        csr!(@read $name = $num);
+
+        #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+        csr!(@mock $name = $num);
+    };
+
+    (@read $name:ident = $num:literal) => {
+        #[doc = ::core::concat!("The CSR with the number `", ::core::stringify!($num), "`.")]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name;
+
+        impl crate::cpu::RegisterRead<usize> for $name {
+            #[inline]
+            fn read() -> usize {
+                let val: usize;
+                csr!(@asm "csrr {0}, {csr}", out(reg) val, csr = const $num);
+                val
+            }
+        }
+    };
+
+    (@mock $name:ident = $num:literal) => {
+        crate::define_mock_register! {
+            #[doc = ::core::concat!("A mock of the CSR with the number `", ::core::stringify!($num), "`.")]
+            pub struct $name: usize = 0;
+        }
+    };
+
+    (@asm $($asm:tt)*) => {
+        unsafe {
+            ::core::arch::asm!($($asm)*, options(nostack));
+        }
+    };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+/// Generate plain `read` and `write` functions for a CSR without fields.
+macro_rules! plain {
+    ($name:ident) => {
+        plain!($name, r);
+
+        /// Write the raw value into this CSR.
+        #[inline]
+        pub fn write(val: usize) {
+            crate::cpu::__write::<$name, usize>(val, ::core::stringify!($name), None);
+        }
+    };
+
+    ($name:ident, r) => {
+        /// Read the raw value of this CSR.
+        #[inline]
+        pub fn read() -> usize {
+            crate::cpu::__read::<$name, usize>(::core::stringify!($name), None)
+        }
+    };
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mstatus {
+    //! The machine status register, which keeps track of the current operating state of the hart.
+
+    csr!(Mstatus = 0x300);
+
+    crate::define_cpu_register! { Mstatus as usize =>
+        /// Supervisor interrupt enable.
+        rw SIE: 1,
+        /// Machine interrupt enable.
+        rw MIE: 3,
+        /// The value of `SIE` before the last trap into supervisor mode.
+        rw SPIE: 5,
+        /// Big-endian memory accesses in user mode.
+        rw UBE: 6,
+        /// The value of `MIE` before the last trap into machine mode.
+        rw MPIE: 7,
+        /// The privilege mode before the last trap into supervisor mode.
+        /// `0` is user mode, `1` is supervisor mode.
+        rw SPP: 8,
+        /// State of the vector extension.
+        rw VS: 9..10 =
+            /// The state of the vector extension.
+            enum VectorState [
+                /// The extension is disabled.
+                Off = 0b00,
+                /// The state is in its initial value.
+                Initial = 0b01,
+                /// The state has not been modified since it was last saved.
+                Clean = 0b10,
+                /// The state has been modified since it was last saved.
+                Dirty = 0b11,
+            ],
+        /// The privilege mode before the last trap into machine mode.
+        rw MPP: 11..12 =
+            /// A privilege mode.
+            enum Mode [
+                /// User mode.
+                User = 0b00,
+                /// Supervisor mode.
+                Supervisor = 0b01,
+                /// Machine mode.
+                Machine = 0b11,
+            ],
+        /// State of the floating point extension.
+        rw FS: 13..14 =
+            /// The state of the floating point extension.
+            enum FloatState [
+                /// The extension is disabled.
+                Off = 0b00,
+                /// The state is in its initial value.
+                Initial = 0b01,
+                /// The state has not been modified since it was last saved.
+                Clean = 0b10,
+                /// The state has been modified since it was last saved.
+                Dirty = 0b11,
+            ],
+        /// Summarized state of additional user mode extensions.
+        r XS: 15..16 =
+            /// The summarized state of additional user mode extensions.
+            enum ExtensionState [
+                /// The extension is disabled.
+                Off = 0b00,
+                /// The state is in its initial value.
+                Initial = 0b01,
+                /// The state has not been modified since it was last saved.
+                Clean = 0b10,
+                /// The state has been modified since it was last saved.
+                Dirty = 0b11,
+            ],
+        /// Use the privilege mode of `MPP` for loads and stores.
+        rw MPRV: 17,
+        /// Permit supervisor mode accesses to user memory.
+        rw SUM: 18,
+        /// Make executable pages readable.
+        rw MXR: 19,
+        /// Trap virtual memory management in supervisor mode.
+        rw TVM: 20,
+        /// Timeout wait, which traps `wfi` in lower privilege modes.
+        rw TW: 21,
+        /// Trap `sret` in supervisor mode.
+        rw TSR: 22,
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mie {
+    //! The machine interrupt enable register.
+
+    csr!(Mie = 0x304);
+
+    crate::define_cpu_register! { Mie as usize =>
+        /// Supervisor software interrupt enable.
+        rw SSIE: 1,
+        /// Machine software interrupt enable.
+        rw MSIE: 3,
+        /// Supervisor timer interrupt enable.
+        rw STIE: 5,
+        /// Machine timer interrupt enable.
+        rw MTIE: 7,
+        /// Supervisor external interrupt enable.
+        rw SEIE: 9,
+        /// Machine external interrupt enable.
+        rw MEIE: 11,
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mip {
+    //! The machine interrupt pending register.
+
+    csr!(Mip = 0x344);
+
+    crate::define_cpu_register! { Mip as usize =>
+        /// Supervisor software interrupt pending.
+        rw SSIP: 1,
+        /// Machine software interrupt pending.
+        r MSIP: 3,
+        /// Supervisor timer interrupt pending.
+        rw STIP: 5,
+        /// Machine timer interrupt pending.
+        r MTIP: 7,
+        /// Supervisor external interrupt pending.
+        rw SEIP: 9,
+        /// Machine external interrupt pending.
+        r MEIP: 11,
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mtvec {
+    //! The machine trap vector base address register.
+    //!
+    //! The base address is stored in the upper bits, and can be accessed
+    //! using the raw [`get`] and [`set`] functions.
+
+    csr!(Mtvec = 0x305);
+
+    crate::define_cpu_register! { Mtvec as usize =>
+        /// The mode of the trap vector.
+        rw MODE: 0..1 =
+            /// The mode of the trap vector.
+            enum TrapMode [
+                /// All traps set `pc` to the base address.
+                Direct = 0b00,
+                /// Interrupts set `pc` to the base address plus four times the cause.
+                Vectored = 0b01,
+            ],
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mscratch {
+    //! The machine scratch register.
+
+    csr!(Mscratch = 0x340);
+    plain!(Mscratch);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mepc {
+    //! The machine exception program counter.
+
+    csr!(Mepc = 0x341);
+    plain!(Mepc);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mcause {
+    //! The machine trap cause register.
+    //!
+    //! The highest bit is set if the trap was caused by an interrupt.
+
+    csr!(Mcause = 0x342);
+    plain!(Mcause);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mtval {
+    //! The machine trap value register.
+
+    csr!(Mtval = 0x343);
+    plain!(Mtval);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod mhartid {
+    //! The ID of the hart that is running the code.
+
+    csr!(Mhartid = 0xF14, r);
+    plain!(Mhartid, r);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod sstatus {
+    //! The supervisor status register, which is a restricted view of `mstatus`.
+
+    csr!(Sstatus = 0x100);
+
+    crate::define_cpu_register! { Sstatus as usize =>
+        /// Supervisor interrupt enable.
+        rw SIE: 1,
+        /// The value of `SIE` before the last trap into supervisor mode.
+        rw SPIE: 5,
+        /// Big-endian memory accesses in user mode.
+        rw UBE: 6,
+        /// The privilege mode before the last trap into supervisor mode.
+        /// `0` is user mode, `1` is supervisor mode.
+        rw SPP: 8,
+        /// State of the vector extension.
+        rw VS: 9..10 =
+            /// The state of the vector extension.
+            enum VectorState [
+                /// The extension is disabled.
+                Off = 0b00,
+                /// The state is in its initial value.
+                Initial = 0b01,
+                /// The state has not been modified since it was last saved.
+                Clean = 0b10,
+                /// The state has been modified since it was last saved.
+                Dirty = 0b11,
+            ],
+        /// State of the floating point extension.
+        rw FS: 13..14 =
+            /// The state of the floating point extension.
+            enum FloatState [
+                /// The extension is disabled.
+                Off = 0b00,
+                /// The state is in its initial value.
+                Initial = 0b01,
+                /// The state has not been modified since it was last saved.
+                Clean = 0b10,
+                /// The state has been modified since it was last saved.
+                Dirty = 0b11,
+            ],
+        /// Summarized state of additional user mode extensions.
+        r XS: 15..16 =
+            /// The summarized state of additional user mode extensions.
+            enum ExtensionState [
+                /// The extension is disabled.
+                Off = 0b00,
+                /// The state is in its initial value.
+                Initial = 0b01,
+                /// The state has not been modified since it was last saved.
+                Clean = 0b10,
+                /// The state has been modified since it was last saved.
+                Dirty = 0b11,
+            ],
+        /// Permit supervisor mode accesses to user memory.
+        rw SUM: 18,
+        /// Make executable pages readable.
+        rw MXR: 19,
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod sie {
+    //! The supervisor interrupt enable register.
+
+    csr!(Sie = 0x104);
+
+    crate::define_cpu_register! { Sie as usize =>
+        /// Supervisor software interrupt enable.
+        rw SSIE: 1,
+        /// Supervisor timer interrupt enable.
+        rw STIE: 5,
+        /// Supervisor external interrupt enable.
+        rw SEIE: 9,
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod sip {
+    //! The supervisor interrupt pending register.
+
+    csr!(Sip = 0x144);
+
+    crate::define_cpu_register! { Sip as usize =>
+        /// Supervisor software interrupt pending.
+        rw SSIP: 1,
+        /// Supervisor timer interrupt pending.
+        r STIP: 5,
+        /// Supervisor external interrupt pending.
+        r SEIP: 9,
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod stvec {
+    //! The supervisor trap vector base address register.
+    //!
+    //! The base address is stored in the upper bits, and can be accessed
+    //! using the raw [`get`] and [`set`] functions.
+
+    csr!(Stvec = 0x105);
+
+    crate::define_cpu_register! { Stvec as usize =>
+        /// The mode of the trap vector.
+        rw MODE: 0..1 =
+            /// The mode of the trap vector.
+            enum TrapMode [
+                /// All traps set `pc` to the base address.
+                Direct = 0b00,
+                /// Interrupts set `pc` to the base address plus four times the cause.
+                Vectored = 0b01,
+            ],
+    }
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod sscratch {
+    //! The supervisor scratch register.
+
+    csr!(Sscratch = 0x140);
+    plain!(Sscratch);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod sepc {
+    //! The supervisor exception program counter.
+
+    csr!(Sepc = 0x141);
+    plain!(Sepc);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod scause {
+    //! The supervisor trap cause register.
+    //!
+    //! The highest bit is set if the trap was caused by an interrupt.
+
+    csr!(Scause = 0x142);
+    plain!(Scause);
+}
+
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64", feature = "std"))]
+pub mod stval {
+    //! The supervisor trap value register.
+
+    csr!(Stval = 0x143);
+    plain!(Stval);
+}
+
+#[cfg(all(
+    target_pointer_width = "32",
+    any(target_arch = "riscv32", target_arch = "riscv64", feature = "std")
+))]
+pub mod satp {
+    //! The supervisor address translation and protection register, for RV32.
+    //!
+    //! The physical page number of the root page table can be accessed
+    //! using the raw [`get`] and [`set`] functions, or [`PPN_MASK`].
+
+    csr!(Satp = 0x180);
+
+    /// The bits of the physical page number of the root page table.
+    pub const PPN_MASK: usize = (1 << 22) - 1;
+
+    crate::define_cpu_register! { Satp as usize =>
+        /// The address translation scheme.
+        rw MODE: 31..31 =
+            /// An address translation scheme.
+            enum TranslationMode [
+                /// No translation or protection.
+                Bare = 0,
+                /// Page-based 32-bit virtual addressing.
+                Sv32 = 1,
+            ],
+    }
+}
+
+#[cfg(all(
+    target_pointer_width = "64",
+    any(target_arch = "riscv32", target_arch = "riscv64", feature = "std")
+))]
+pub mod satp {
+    //! The supervisor address translation and protection register, for RV64.
+    //!
+    //! The physical page number of the root page table can be accessed
+    //! using the raw [`get`] and [`set`] functions, or [`PPN_MASK`].
+
+    csr!(Satp = 0x180);
+
+    /// The bits of the physical page number of the root page table.
+    pub const PPN_MASK: usize = (1 << 44) - 1;
+
+    crate::define_cpu_register! { Satp as usize =>
+        /// The address translation scheme.
+        rw MODE: 60..63 =
+            /// An address translation scheme.
+            enum TranslationMode [
+                /// No translation or protection.
+                Bare = 0,
+                /// Page-based 39-bit virtual addressing.
+                Sv39 = 8,
+                /// Page-based 48-bit virtual addressing.
+                Sv48 = 9,
+                /// Page-based 57-bit virtual addressing.
+                Sv57 = 10,
+            ],
+    }
+}
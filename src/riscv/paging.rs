@@ -0,0 +1,101 @@
+//! Page table entries for the Sv39, Sv48 and Sv57 translation schemes.
+//!
+//! All three schemes use the same 64-bit entry layout, and only differ in the number
+//! of levels, so a single [`PageTableEntry`] type covers all of them. The scheme
+//! itself is selected using the `MODE` field of [`satp`](super::satp).
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::riscv::paging::{self, PageTableEntry};
+//!
+//! // a leaf entry that maps the kernel at `0x8020_0000`
+//! let pte = PageTableEntry::new(0)
+//!     .with_valid(true)
+//!     .with_read(true)
+//!     .with_execute(true)
+//!     .with_address(0x8020_0000);
+//!
+//! assert!(pte.is_leaf());
+//! assert_eq!(pte.ppn(), 0x80200);
+//! assert_eq!(paging::vpn(0xFFFF_FFFF_8020_0000, 2), 510);
+//! ```
+
+/// The size of a base page.
+pub const PAGE_SIZE: u64 = 4096;
+
+/// The number of entries in a single page table.
+pub const ENTRIES: usize = 512;
+
+crate::define_bitfield! {
+    /// An entry of a Sv39, Sv48 or Sv57 page table.
+    pub struct PageTableEntry: u64 {
+        /// The entry is valid.
+        rw VALID [valid, with_valid]: 0,
+        /// The page is readable.
+        rw READ [read, with_read]: 1,
+        /// The page is writable.
+        rw WRITE [write, with_write]: 2,
+        /// The page is executable.
+        rw EXECUTE [execute, with_execute]: 3,
+        /// The page is accessible in user mode.
+        rw USER [user, with_user]: 4,
+        /// The mapping exists in all address spaces.
+        rw GLOBAL [global, with_global]: 5,
+        /// The page was accessed since the bit was cleared.
+        rw ACCESSED [accessed, with_accessed]: 6,
+        /// The page was written since the bit was cleared.
+        rw DIRTY [dirty, with_dirty]: 7,
+        /// Reserved for use by supervisor software.
+        rw RSW [rsw, with_rsw]: 8..9 =
+            /// The bits that are reserved for supervisor software.
+            flags Software [
+                /// The first software bit.
+                SW0 = 0b01,
+                /// The second software bit.
+                SW1 = 0b10,
+            ],
+        /// The physical page number this entry points to.
+        rw PPN [ppn, with_ppn]: 10..53,
+        /// The memory type of the page, if the `Svpbmt` extension is implemented.
+        rw PBMT [pbmt, with_pbmt]: 61..62 =
+            /// A page-based memory type.
+            enum MemoryType [
+                /// Use the attributes of the physical memory.
+                Pma = 0,
+                /// Non-cacheable, idempotent main memory.
+                Nc = 1,
+                /// Non-cacheable, non-idempotent I/O memory.
+                Io = 2,
+            ],
+        /// The entry is part of a naturally aligned power-of-two range,
+        /// if the `Svnapot` extension is implemented.
+        rw NAPOT [napot, with_napot]: 63,
+    }
+}
+
+impl PageTableEntry {
+    /// Return the physical address this entry points to.
+    pub const fn address(self) -> u64 {
+        self.ppn() * PAGE_SIZE
+    }
+
+    /// Return a copy that points to the given physical address.
+    ///
+    /// The offset inside the page is ignored.
+    pub const fn with_address(self, addr: u64) -> Self {
+        self.with_ppn(addr / PAGE_SIZE)
+    }
+
+    /// Check if this entry maps a page, instead of pointing to the next level of the page table.
+    pub const fn is_leaf(self) -> bool {
+        self.read() || self.execute()
+    }
+}
+
+/// Return the index into the page table at the given level, for the virtual address.
+///
+/// The level of the root page table is `2` for Sv39, `3` for Sv48 and `4` for Sv57.
+pub const fn vpn(addr: u64, level: usize) -> usize {
+    ((addr >> (12 + 9 * level)) & 0x1FF) as usize
+}
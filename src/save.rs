@@ -0,0 +1,310 @@
+//! Saving, restoring and comparing the registers of MMIO blocks.
+//!
+//! Every struct that is defined using [`define_mmio_struct`](crate::define_mmio_struct)
+//! has a `save` method, which reads every register that has at least one field that is both
+//! readable and writable into a [`Saved`] value, and a `restore` method, which writes them back
+//! in declaration order. Registers without any fields, like [`Lit`](crate::mmio::Lit),
+//! are saved as a whole.
+//!
+//! Only the fields that are both readable and writable are restored, all other bits
+//! of a register are written as `0`. This prevents that read-only status bits are written
+//! back into write-only fields, which often trigger an action.
+//!
+//! Often registers must be restored in a specific order, for example a clock divider must be
+//! written before the enable bit. The registers listed after `restore` are restored last,
+//! in the given order:
+//!
+//! ```
+//! # use rumio::mmio::Lit;
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart {
+//!         (0x00 => ctrl: Lit<u32>),
+//!         (0x04 => div: Lit<u32>),
+//!         (0x08 => data: Lit<u32>),
+//!     }
+//!
+//!     restore [div, ctrl]
+//! }
+//!
+//! assert_eq!(Uart::INFO.restore_order, ["div", "ctrl"]);
+//! ```
+//!
+//! Two saved states can be compared using [`Saved::diff`], which reports every changed
+//! field by name, using the field definitions of the registers.
+//!
+//! # Example
+//!
+//! ```
+//! rumio::define_mmio_register! {
+//!     Ctrl: u32 {
+//!         rw EN: 0,
+//!         rw MODE: 1..2 = enum Mode [
+//!             Slow = 0b00,
+//!             Fast = 0b01,
+//!         ],
+//!     }
+//! }
+//!
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart {
+//!         (0x00 => ctrl: Ctrl),
+//!     }
+//! }
+//!
+//! let mut mem = 0u32;
+//! let uart = unsafe { Uart::new(&mut mem as *mut u32 as usize) };
+//!
+//! let before = uart.save();
+//! uart.ctrl().EN().set(true);
+//! uart.ctrl().MODE().set(Mode::Fast);
+//!
+//! let changes = before.diff(&uart.save()).map(|c| c.to_string()).collect::<Vec<_>>();
+//! assert_eq!(changes, ["ctrl.EN: 0x0 -> 0x1", "ctrl.MODE: 0x0 -> 0x1"]);
+//!
+//! uart.restore(&before);
+//! assert!(!uart.ctrl().EN().get());
+//! ```
+
+use crate::{
+    meta::{BlockInfo, FieldInfo, Perm, RegisterEntry, RegisterInfo},
+    mmio::VolAddr,
+};
+use core::fmt;
+
+/// The saved values of all registers of a MMIO block, that have a readable and writable field.
+///
+/// `N` is the total number of registers inside the block, where arrays count
+/// as one register per element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Saved<const N: usize> {
+    block: &'static BlockInfo,
+    values: [u64; N],
+}
+
+impl<const N: usize> Saved<N> {
+    /// Return the description of the block these values were saved from.
+    pub fn block(&self) -> &'static BlockInfo {
+        self.block
+    }
+
+    /// Return the saved value of the register with the given name and index.
+    ///
+    /// The index must be `0` for registers that are not an array.
+    /// Returns `None` if the register doesn't exist, or wasn't saved.
+    pub fn get(&self, register: &str, idx: usize) -> Option<u64> {
+        self.iter()
+            .find(|(entry, i, _)| entry.name == register && *i == idx)
+            .map(|(_, _, val)| val)
+    }
+
+    /// Iterate over all saved registers in declaration order.
+    ///
+    /// Every item is the register, the index inside the register array, and the saved value.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static RegisterEntry, usize, u64)> + '_ {
+        slots(self.block)
+            .filter(|(entry, _, _)| is_saved(entry.info))
+            .map(move |(entry, idx, slot)| (entry, idx, self.values[slot]))
+    }
+
+    /// Compare these values with the `other` values, and return every field that differs.
+    ///
+    /// Registers without any fields, like [`Lit`](crate::mmio::Lit), are reported as a whole.
+    pub fn diff<'a>(&'a self, other: &'a Saved<N>) -> Diff<'a, N> {
+        Diff {
+            old: self,
+            new: other,
+            slot: 0,
+            field: 0,
+        }
+    }
+}
+
+/// A single difference between two [`Saved`] values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// The register that changed.
+    pub register: &'static RegisterEntry,
+    /// The index inside the register array, or `0` if the register is not an array.
+    pub index: usize,
+    /// The field that changed, or `None` if the register has no fields.
+    pub field: Option<&'static FieldInfo>,
+    /// The old value of the field or register.
+    pub old: u64,
+    /// The new value of the field or register.
+    pub new: u64,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.register.name)?;
+        if self.register.count > 1 {
+            write!(f, "[{}]", self.index)?;
+        }
+        if let Some(field) = self.field {
+            write!(f, ".{}", field.name)?;
+        }
+        write!(f, ": {:#x} -> {:#x}", self.old, self.new)
+    }
+}
+
+/// Iterator over all [`Change`]s between two [`Saved`] values.
+///
+/// Created by [`Saved::diff`].
+#[derive(Clone, Debug)]
+pub struct Diff<'a, const N: usize> {
+    old: &'a Saved<N>,
+    new: &'a Saved<N>,
+    slot: usize,
+    field: usize,
+}
+
+impl<const N: usize> Iterator for Diff<'_, N> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        loop {
+            let (entry, index, _) = slots(self.old.block).nth(self.slot)?;
+            let (old, new) = (self.old.values[self.slot], self.new.values[self.slot]);
+
+            if !is_saved(entry.info) || old == new {
+                self.slot += 1;
+                self.field = 0;
+                continue;
+            }
+
+            let fields = entry.info.fields;
+            if fields.is_empty() {
+                self.slot += 1;
+                return Some(Change {
+                    register: entry,
+                    index,
+                    field: None,
+                    old,
+                    new,
+                });
+            }
+
+            while let Some(field) = fields.get(self.field) {
+                self.field += 1;
+                let (old, new) = (field.extract(old), field.extract(new));
+                if old != new {
+                    return Some(Change {
+                        register: entry,
+                        index,
+                        field: Some(field),
+                        old,
+                        new,
+                    });
+                }
+            }
+
+            self.slot += 1;
+            self.field = 0;
+        }
+    }
+}
+
+/// Iterate over every register of the block, including every element of an array.
+///
+/// Every item is the register, the index inside the array and the index of the slot.
+fn slots(
+    block: &'static BlockInfo,
+) -> impl Iterator<Item = (&'static RegisterEntry, usize, usize)> {
+    block
+        .registers
+        .iter()
+        .flat_map(|entry| (0..entry.count).map(move |idx| (entry, idx)))
+        .enumerate()
+        .map(|(slot, (entry, idx))| (entry, idx, slot))
+}
+
+fn is_saved(info: &RegisterInfo) -> bool {
+    let rw = |perm: Perm| perm.is_readable() && perm.is_writable();
+    let saved = if info.fields.is_empty() {
+        rw(info.perm)
+    } else {
+        info.fields.iter().any(|field| rw(field.perm))
+    };
+    saved && matches!(info.size(), 1 | 2 | 4 | 8)
+}
+
+/// Return the bits of the register that are restored, which are all readable and writable fields.
+fn restore_mask(info: &RegisterInfo) -> u64 {
+    if info.fields.is_empty() {
+        return !0;
+    }
+
+    info.fields
+        .iter()
+        .filter(|field| field.perm == Perm::ReadWrite)
+        .fold(0, |mask, field| mask | field.mask())
+}
+
+/// Read the register inside the block at `base`, using its barriers.
+unsafe fn read(base: VolAddr<u8>, entry: &RegisterEntry, idx: usize) -> u64 {
+    (entry.__read)(base.offset(entry.offset_of(idx) as isize))
+}
+
+/// Write the register inside the block at `base`, using its barriers.
+unsafe fn write(base: VolAddr<u8>, entry: &RegisterEntry, idx: usize, val: u64) {
+    (entry.__write)(base.offset(entry.offset_of(idx) as isize), val)
+}
+
+/// Save all registers of the block at `base`.
+///
+/// Used by the generated code.
+///
+/// # Safety
+///
+/// `base` must point to a block that is described by `block`.
+#[doc(hidden)]
+pub unsafe fn __save<const N: usize>(base: VolAddr<u8>, block: &'static BlockInfo) -> Saved<N> {
+    let mut values = [0; N];
+    for (entry, idx, slot) in slots(block) {
+        if is_saved(entry.info) {
+            values[slot] = read(base, entry, idx);
+        }
+    }
+    Saved { block, values }
+}
+
+/// Restore all saved registers of the block at `base`.
+///
+/// The registers are taken from `block`, not from the block of `saved`,
+/// so the values are never written to a register outside of `block`.
+///
+/// Used by the generated code.
+///
+/// # Safety
+///
+/// `base` must point to a block that is described by `block`.
+#[doc(hidden)]
+pub unsafe fn __restore<const N: usize>(
+    base: VolAddr<u8>,
+    block: &'static BlockInfo,
+    saved: &Saved<N>,
+) {
+    let order = block.restore_order;
+    let restore = |entry: &RegisterEntry, idx, slot| {
+        write(
+            base,
+            entry,
+            idx,
+            saved.values[slot] & restore_mask(entry.info),
+        );
+    };
+
+    for (entry, idx, slot) in slots(block) {
+        if is_saved(entry.info) && !order.contains(&entry.name) {
+            restore(entry, idx, slot);
+        }
+    }
+
+    for name in order {
+        for (entry, idx, slot) in slots(block) {
+            if entry.name == *name && is_saved(entry.info) {
+                restore(entry, idx, slot);
+            }
+        }
+    }
+}
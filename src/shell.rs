@@ -0,0 +1,700 @@
+//! An interactive shell for inspecting and modifying registers.
+//!
+//! The shell uses the [metadata](crate::meta) generated by
+//! [`define_mmio_struct`](crate::define_mmio_struct) to find registers and fields by name,
+//! and to print the decoded value of every field. It is only available with the `std` feature.
+//!
+//! The memory is accessed through the [`Memory`] trait. On Linux, it is implemented by
+//! [`MappedMemory`], which maps regions of `/dev/mem` or any other file into the process.
+//!
+//! # Commands
+//!
+//! | Command                      | Description                                        |
+//! |------------------------------|----------------------------------------------------|
+//! | `list`                       | List all devices and their registers.              |
+//! | `read DEV.REG`               | Read a register and print all of its fields.       |
+//! | `read DEV.REG.FIELD`         | Read a single field.                               |
+//! | `set DEV.REG VALUE`          | Write a raw value into a register.                 |
+//! | `set DEV.REG.FIELD VALUE`    | Modify a single field and keep all other bits.     |
+//! | `dump DEV`                   | Read every readable register of a device.          |
+//! | `help`                       | Print all commands.                                |
+//!
+//! Array registers are accessed using `DEV.REG[IDX]`. All names are case-insensitive.
+//!
+//! Values can be written in decimal, hex (`0x`) or binary (`0b`). Single bits also accept
+//! `true` and `false`, enums accept the name of a variant and flags accept the names
+//! of the flags separated by `|`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rumio::shell::{MappedMemory, Shell};
+//!
+//! rumio::define_mmio_register! {
+//!     Ctrl: u32 {
+//!         rw EN: 0,
+//!     }
+//! }
+//!
+//! rumio::define_mmio_struct! {
+//!     pub struct Uart {
+//!         (0x00 => ctrl: Ctrl),
+//!     }
+//! }
+//!
+//! let mut mem = MappedMemory::open("/dev/mem").unwrap();
+//! mem.map(0x1000_0000, Uart::INFO.size()).unwrap();
+//!
+//! let mut shell = Shell::new(mem);
+//! shell.device("UART0", 0x1000_0000, &Uart::INFO);
+//!
+//! let stdin = std::io::stdin();
+//! shell.run(stdin.lock(), std::io::stdout()).unwrap();
+//! ```
+
+#[cfg(target_os = "linux")]
+use crate::{linux::Mapping, mmio::VolAddr};
+use crate::meta::{BlockInfo, FieldInfo, FieldKind, RegisterEntry, RegisterInfo};
+#[cfg(target_os = "linux")]
+use std::{
+    fs::{File, OpenOptions},
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+};
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    string::String,
+    vec::Vec,
+};
+
+/// A region of memory that can be read and written by the [`Shell`].
+pub trait Memory {
+    /// Read `size` bytes at the given address.
+    ///
+    /// `size` is always `1`, `2`, `4` or `8`.
+    fn read(&self, addr: u64, size: usize) -> io::Result<u64>;
+
+    /// Write the lower `size` bytes of `val` to the given address.
+    ///
+    /// `size` is always `1`, `2`, `4` or `8`.
+    fn write(&self, addr: u64, size: usize, val: u64) -> io::Result<()>;
+
+    /// Read the register of `entry` at the given address.
+    ///
+    /// This is used for every register access of the shell, and by default just
+    /// calls [`read`](Self::read) with the size of the register. Memories that
+    /// hand out real addresses should access the register through `entry`, so
+    /// the barriers of the register are used and the access is traced.
+    fn read_register(&self, addr: u64, entry: &RegisterEntry) -> io::Result<u64> {
+        self.read(addr, entry.info.size())
+    }
+
+    /// Write `val` into the register of `entry` at the given address.
+    ///
+    /// See [`read_register`](Self::read_register) for more details.
+    fn write_register(&self, addr: u64, entry: &RegisterEntry, val: u64) -> io::Result<()> {
+        self.write(addr, entry.info.size(), val)
+    }
+}
+
+/// A [`Memory`] that maps regions of a file, like `/dev/mem`, into this process,
+/// where the address is the offset inside the file.
+///
+/// Every access is a single volatile read or write of the register width,
+/// so the same rules apply as for the generated register types. Registers are
+/// accessed through their [`RegisterEntry`], which uses the barriers of the register
+/// and reports the access to the trace sink, if the `trace` feature is enabled.
+/// Only regions that were mapped using [`map`](Self::map) can be accessed.
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct MappedMemory {
+    file: File,
+    regions: Vec<(u64, Mapping)>,
+}
+
+#[cfg(target_os = "linux")]
+impl MappedMemory {
+    /// Open the file at the given path for reading and writing.
+    ///
+    /// The file is opened with `O_SYNC`, which disables caching for `/dev/mem`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_SYNC)
+            .open(path)?;
+        Ok(Self::from_file(file))
+    }
+
+    /// Create a new memory from an already opened file.
+    pub fn from_file(file: File) -> Self {
+        Self {
+            file,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Map `len` bytes starting at `addr`, so they can be accessed.
+    ///
+    /// Mapping an empty region does nothing.
+    pub fn map(&mut self, addr: u64, len: usize) -> io::Result<()> {
+        if len == 0 {
+            return Ok(());
+        }
+
+        let mapping = Mapping::file(&self.file, addr, len)?;
+        self.regions.push((addr, mapping));
+        Ok(())
+    }
+
+    /// Return the address inside this process for an access of `size` bytes at `addr`.
+    fn ptr(&self, addr: u64, size: usize) -> io::Result<usize> {
+        // `is_multiple_of` needs Rust 1.87
+        #[allow(clippy::manual_is_multiple_of)]
+        if addr % size as u64 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                std::format!("unaligned access of {} bytes at {:#x}", size, addr),
+            ));
+        }
+
+        self.regions
+            .iter()
+            .find_map(|(start, mapping)| {
+                let offset = addr.checked_sub(*start)?;
+                (offset + size as u64 <= mapping.len() as u64)
+                    .then(|| mapping.addr() + offset as usize)
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    std::format!("address {:#x} is not mapped", addr),
+                )
+            })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Memory for MappedMemory {
+    fn read(&self, addr: u64, size: usize) -> io::Result<u64> {
+        let ptr = self.ptr(addr, size)?;
+
+        // SAFETY: the address is aligned and inside a mapping that lives as long as `self`.
+        unsafe {
+            Ok(match size {
+                1 => u64::from(VolAddr::<u8>::new(ptr).read()),
+                2 => u64::from(VolAddr::<u16>::new(ptr).read()),
+                4 => u64::from(VolAddr::<u32>::new(ptr).read()),
+                _ => VolAddr::<u64>::new(ptr).read(),
+            })
+        }
+    }
+
+    fn write(&self, addr: u64, size: usize, val: u64) -> io::Result<()> {
+        let ptr = self.ptr(addr, size)?;
+
+        // SAFETY: the address is aligned and inside a mapping that lives as long as `self`.
+        unsafe {
+            match size {
+                1 => VolAddr::<u8>::new(ptr).write(val as u8),
+                2 => VolAddr::<u16>::new(ptr).write(val as u16),
+                4 => VolAddr::<u32>::new(ptr).write(val as u32),
+                _ => VolAddr::<u64>::new(ptr).write(val),
+            }
+        }
+        Ok(())
+    }
+
+    fn read_register(&self, addr: u64, entry: &RegisterEntry) -> io::Result<u64> {
+        let ptr = self.ptr(addr, entry.info.size())?;
+
+        // SAFETY: the address is aligned and inside a mapping that lives as long as `self`.
+        unsafe { Ok((entry.__read)(VolAddr::new(ptr))) }
+    }
+
+    fn write_register(&self, addr: u64, entry: &RegisterEntry, val: u64) -> io::Result<()> {
+        let ptr = self.ptr(addr, entry.info.size())?;
+
+        // SAFETY: the address is aligned and inside a mapping that lives as long as `self`.
+        unsafe { (entry.__write)(VolAddr::new(ptr), val) };
+        Ok(())
+    }
+}
+
+/// All errors that can occur while executing a command.
+#[derive(Debug)]
+pub enum Error {
+    /// The command is unknown.
+    UnknownCommand(String),
+    /// The arguments of the command are invalid.
+    Usage(&'static str),
+    /// There's no device with the given name.
+    UnknownDevice(String),
+    /// There's no register with the given name.
+    UnknownRegister(String),
+    /// There's no field with the given name.
+    UnknownField(String),
+    /// The array index is out of bounds.
+    InvalidIndex(usize),
+    /// The value could not be parsed, or doesn't fit into the field.
+    InvalidValue(String),
+    /// The register or field can not be read.
+    NotReadable,
+    /// The register or field can not be written.
+    NotWritable,
+    /// The register has an unsupported size.
+    UnsupportedWidth(usize),
+    /// Accessing the memory failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownCommand(cmd) => write!(f, "unknown command `{}`", cmd),
+            Error::Usage(usage) => write!(f, "usage: {}", usage),
+            Error::UnknownDevice(name) => write!(f, "unknown device `{}`", name),
+            Error::UnknownRegister(name) => write!(f, "unknown register `{}`", name),
+            Error::UnknownField(name) => write!(f, "unknown field `{}`", name),
+            Error::InvalidIndex(idx) => write!(f, "index {} is out of bounds", idx),
+            Error::InvalidValue(val) => write!(f, "invalid value `{}`", val),
+            Error::NotReadable => f.write_str("not readable"),
+            Error::NotWritable => f.write_str("not writable"),
+            Error::UnsupportedWidth(width) => {
+                write!(f, "unsupported register width of {} bits", width)
+            }
+            Error::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+struct Device {
+    name: String,
+    base: u64,
+    info: &'static BlockInfo,
+}
+
+/// A single register that was found using a path like `DEV.REG[IDX].FIELD`.
+struct Target<'a> {
+    device: &'a Device,
+    entry: &'static RegisterEntry,
+    idx: Option<usize>,
+    field: Option<&'static FieldInfo>,
+}
+
+impl Target<'_> {
+    fn addr(&self) -> u64 {
+        self.device.base + self.entry.offset_of(self.idx.unwrap_or(0)) as u64
+    }
+
+    fn name(&self) -> String {
+        let mut name = std::format!("{}.{}", self.device.name, self.entry.name);
+        if let Some(idx) = self.idx {
+            name += &std::format!("[{}]", idx);
+        }
+        if let Some(field) = self.field {
+            name += ".";
+            name += field.name;
+        }
+        name
+    }
+}
+
+const HELP: &str = "\
+list                     list all devices and their registers
+read DEV.REG[.FIELD]     read a register or a single field
+set DEV.REG[.FIELD] VAL  write a register or modify a single field
+dump DEV                 read every readable register of a device
+help                     print this message";
+
+/// An interactive shell, that operates on a set of devices.
+pub struct Shell<M> {
+    mem: M,
+    devices: Vec<Device>,
+}
+
+impl<M: Memory> Shell<M> {
+    /// Create a new shell without any device.
+    pub fn new(mem: M) -> Self {
+        Self {
+            mem,
+            devices: Vec::new(),
+        }
+    }
+
+    /// Add a new device with the given name, that is located at `base`.
+    pub fn device(&mut self, name: &str, base: u64, info: &'static BlockInfo) -> &mut Self {
+        self.devices.push(Device {
+            name: name.into(),
+            base,
+            info,
+        });
+        self
+    }
+
+    /// Read commands line by line, and write their output.
+    ///
+    /// Errors of single commands are written to the output and don't stop the shell.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut out: W) -> io::Result<()> {
+        write!(out, "> ")?;
+        out.flush()?;
+
+        for line in input.lines() {
+            match self.execute(&line?, &mut out) {
+                Ok(()) => {}
+                Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => return Err(err),
+                Err(err) => writeln!(out, "error: {}", err)?,
+            }
+
+            write!(out, "> ")?;
+            out.flush()?;
+        }
+
+        writeln!(out)
+    }
+
+    /// Execute a single command and write the output.
+    pub fn execute<W: Write>(&mut self, line: &str, out: &mut W) -> Result<(), Error> {
+        let mut args = line.split_whitespace();
+        let cmd = match args.next() {
+            Some(cmd) => cmd,
+            None => return Ok(()),
+        };
+        let args = args.collect::<Vec<_>>();
+
+        match (cmd, args.as_slice()) {
+            ("help", []) => writeln!(out, "{}", HELP)?,
+            ("list", []) => self.list(out)?,
+            ("read", [path]) => {
+                let target = self.find(path)?;
+                self.print(&target, out)?;
+            }
+            ("read", _) => return Err(Error::Usage("read DEV.REG[.FIELD]")),
+            ("set", [path, val]) | ("write", [path, val]) => {
+                let target = self.find(path)?;
+                self.set(&target, val)?;
+            }
+            ("set", _) | ("write", _) => return Err(Error::Usage("set DEV.REG[.FIELD] VAL")),
+            ("dump", [name]) => {
+                let device = self.find_device(name)?;
+                for entry in device.info.registers {
+                    if !entry.info.perm.is_readable() {
+                        continue;
+                    }
+
+                    for idx in 0..entry.count {
+                        let target = Target {
+                            device,
+                            entry,
+                            idx: if entry.count > 1 { Some(idx) } else { None },
+                            field: None,
+                        };
+                        self.print(&target, out)?;
+                    }
+                }
+            }
+            ("dump", _) => return Err(Error::Usage("dump DEV")),
+            (cmd, _) => return Err(Error::UnknownCommand(cmd.into())),
+        }
+
+        Ok(())
+    }
+
+    fn list<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for device in &self.devices {
+            writeln!(
+                out,
+                "{} ({}) @ {:#x}",
+                device.name, device.info.name, device.base
+            )?;
+            for entry in device.info.registers {
+                write!(out, "  {:#06x} {}", entry.offset, entry.name)?;
+                if entry.count > 1 {
+                    write!(out, "[{}]", entry.count)?;
+                }
+                writeln!(out, ": {} (u{})", entry.info.name, entry.info.width)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn find_device(&self, name: &str) -> Result<&Device, Error> {
+        self.devices
+            .iter()
+            .find(|d| d.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::UnknownDevice(name.into()))
+    }
+
+    fn find(&self, path: &str) -> Result<Target<'_>, Error> {
+        let mut parts = path.splitn(3, '.');
+        let device = self.find_device(parts.next().unwrap_or_default())?;
+
+        let reg = parts.next().ok_or(Error::Usage("DEV.REG[IDX][.FIELD]"))?;
+        let (reg, idx) = match reg.find('[') {
+            Some(start) if reg.ends_with(']') => {
+                let idx = &reg[start + 1..reg.len() - 1];
+                let idx = idx
+                    .parse::<usize>()
+                    .map_err(|_| Error::InvalidValue(idx.into()))?;
+                (&reg[..start], Some(idx))
+            }
+            _ => (reg, None),
+        };
+
+        let entry = device
+            .info
+            .registers
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(reg))
+            .ok_or_else(|| Error::UnknownRegister(reg.into()))?;
+
+        match idx {
+            Some(idx) if idx >= entry.count => return Err(Error::InvalidIndex(idx)),
+            None if entry.count > 1 => return Err(Error::Usage("DEV.REG[IDX][.FIELD]")),
+            _ => {}
+        }
+
+        let field = match parts.next() {
+            Some(name) => Some(
+                entry
+                    .info
+                    .fields
+                    .iter()
+                    .find(|f| f.name.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| Error::UnknownField(name.into()))?,
+            ),
+            None => None,
+        };
+
+        Ok(Target {
+            device,
+            entry,
+            idx,
+            field,
+        })
+    }
+
+    fn read(&self, target: &Target<'_>) -> Result<u64, Error> {
+        check_width(target.entry.info)?;
+        Ok(self.mem.read_register(target.addr(), target.entry)?)
+    }
+
+    fn print<W: Write>(&self, target: &Target<'_>, out: &mut W) -> Result<(), Error> {
+        let info = target.entry.info;
+        match target.field {
+            Some(field) => {
+                if !field.perm.is_readable() {
+                    return Err(Error::NotReadable);
+                }
+
+                let bits = field.extract(self.read(target)?);
+                writeln!(out, "{} = {}", target.name(), FieldValue(field, bits))?;
+            }
+            None => {
+                if !info.perm.is_readable() {
+                    return Err(Error::NotReadable);
+                }
+
+                let raw = self.read(target)?;
+                write!(
+                    out,
+                    "{} = {:#0width$x}",
+                    target.name(),
+                    raw,
+                    width = info.size() * 2 + 2
+                )?;
+
+                let mut fields = info.fields.iter().filter(|f| f.perm.is_readable());
+                if let Some(field) = fields.next() {
+                    write!(
+                        out,
+                        " {{ {}: {}",
+                        field.name,
+                        FieldValue(field, field.extract(raw))
+                    )?;
+                    for field in fields {
+                        write!(
+                            out,
+                            ", {}: {}",
+                            field.name,
+                            FieldValue(field, field.extract(raw))
+                        )?;
+                    }
+                    write!(out, " }}")?;
+                }
+                writeln!(out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set(&self, target: &Target<'_>, val: &str) -> Result<(), Error> {
+        let info = target.entry.info;
+        check_width(info)?;
+
+        let raw = match target.field {
+            Some(field) => {
+                if !field.perm.is_writable() {
+                    return Err(Error::NotWritable);
+                }
+
+                let bits = parse_field(field, val)?;
+                let raw = if info.perm.is_readable() {
+                    self.read(target)?
+                } else {
+                    0
+                };
+                field.insert(raw, bits)
+            }
+            None => {
+                if !info.perm.is_writable() {
+                    return Err(Error::NotWritable);
+                }
+
+                let raw = parse_int(val)?;
+                if info.width < 64 && raw >> info.width != 0 {
+                    return Err(Error::InvalidValue(val.into()));
+                }
+                raw
+            }
+        };
+
+        Ok(self.mem.write_register(target.addr(), target.entry, raw)?)
+    }
+}
+
+/// Check that the register can be accessed by a single read or write.
+fn check_width(info: &RegisterInfo) -> Result<(), Error> {
+    match info.size() {
+        1 | 2 | 4 | 8 => Ok(()),
+        _ => Err(Error::UnsupportedWidth(info.width)),
+    }
+}
+
+/// Parse a decimal, hex (`0x`) or binary (`0b`) number, which may contain `_` separators.
+pub fn parse_int(val: &str) -> Result<u64, Error> {
+    let val = val.replace('_', "");
+    let res = if let Some(hex) = val.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(bin) = val.strip_prefix("0b") {
+        u64::from_str_radix(bin, 2)
+    } else {
+        val.parse::<u64>()
+    };
+    res.map_err(|_| Error::InvalidValue(val))
+}
+
+fn parse_field(field: &FieldInfo, val: &str) -> Result<u64, Error> {
+    let bits = match field.kind {
+        FieldKind::Bit if val == "true" => 1,
+        FieldKind::Bit if val == "false" => 0,
+        FieldKind::Enum(variants) => {
+            match variants.iter().find(|v| v.name.eq_ignore_ascii_case(val)) {
+                Some(variant) => variant.value,
+                None => parse_int(val)?,
+            }
+        }
+        FieldKind::Flags(flags) if !val.starts_with(|c: char| c.is_ascii_digit()) => {
+            let mut bits = 0;
+            for name in val.split('|').map(str::trim).filter(|n| !n.is_empty()) {
+                bits |= flags
+                    .iter()
+                    .find(|f| f.name.eq_ignore_ascii_case(name))
+                    .ok_or_else(|| Error::InvalidValue(name.into()))?
+                    .value;
+            }
+            bits
+        }
+        _ => parse_int(val)?,
+    };
+
+    if bits & !(field.mask() >> field.from) != 0 {
+        return Err(Error::InvalidValue(val.into()));
+    }
+    Ok(bits)
+}
+
+/// Prints the bits of a single field.
+struct FieldValue<'a>(&'a FieldInfo, u64);
+
+impl fmt::Display for FieldValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let FieldValue(field, bits) = *self;
+        match field.kind {
+            FieldKind::Bit => write!(f, "{}", bits != 0),
+            FieldKind::Number => write!(f, "{:#x}", bits),
+            FieldKind::Enum(_) => match field.variant(bits) {
+                Some(variant) => f.write_str(variant.name),
+                None => write!(f, "{:#x}", bits),
+            },
+            FieldKind::Flags(flags) => {
+                let mut set = flags
+                    .iter()
+                    .filter(|flag| flag.value != 0 && bits & flag.value == flag.value);
+                match set.next() {
+                    Some(flag) => {
+                        f.write_str(flag.name)?;
+                        for flag in set {
+                            write!(f, " | {}", flag.name)?;
+                        }
+                        Ok(())
+                    }
+                    None => f.write_str("(empty)"),
+                }
+            }
+        }
+    }
+}
+
+/// Run a shell on stdin and stdout with the given devices.
+///
+/// The memory file is taken from the first command line argument,
+/// and defaults to `/dev/mem`. The registers of every device are mapped
+/// into the process using [`MappedMemory`]. This is meant to be the whole `main`
+/// function of a board specific shell binary.
+///
+/// ```no_run
+/// # rumio::define_mmio_struct! { pub struct Uart {} }
+/// fn main() {
+///     rumio::shell::main(&[("UART0", 0x1000_0000, &Uart::INFO)]);
+/// }
+/// ```
+#[cfg(target_os = "linux")]
+pub fn main(devices: &[(&str, u64, &'static BlockInfo)]) {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "/dev/mem".into());
+    let mut mem = match MappedMemory::open(&path) {
+        Ok(mem) => mem,
+        Err(err) => {
+            std::eprintln!("failed to open `{}`: {}", path, err);
+            std::process::exit(1);
+        }
+    };
+
+    for &(name, base, info) in devices {
+        if let Err(err) = mem.map(base, info.size()) {
+            std::eprintln!("failed to map `{}` at {:#x}: {}", name, base, err);
+            std::process::exit(1);
+        }
+    }
+
+    let mut shell = Shell::new(mem);
+    for &(name, base, info) in devices {
+        shell.device(name, base, info);
+    }
+
+    let stdin = io::stdin();
+    if let Err(err) = shell.run(stdin.lock(), io::stdout()) {
+        std::eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,256 @@
+//! Tracing of every register access.
+//!
+//! If the `trace` feature is enabled, every read and write that is performed by
+//! the code generated by [`define_mmio_register`](crate::define_mmio_register) and
+//! [`define_cpu_register`](crate::define_cpu_register) is reported to the [`Sink`]
+//! that was installed using [`set_sink`].
+//!
+//! If the feature is disabled, this module doesn't exist and the generated code
+//! accesses the registers directly.
+//!
+//! Ready-made sinks are available for the [`log`](https://docs.rs/log) and
+//! [`defmt`](https://docs.rs/defmt) crates, by enabling the `log` or `defmt` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::trace::{self, Access, Sink};
+//!
+//! struct Printer;
+//!
+//! impl Sink for Printer {
+//!     fn record(&self, access: &Access) {
+//!         println!("{}", access);
+//!     }
+//! }
+//!
+//! trace::set_sink(&Printer).unwrap();
+//! ```
+
+use core::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// The direction of a register access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// The register was read.
+    Read,
+    /// The register was written.
+    Write,
+    /// The bits of the value were set, using the
+    /// [`set`](crate::cpu::RegisterWrite::set) method of a CPU register.
+    Set,
+    /// The bits of the value were cleared, using the
+    /// [`clear`](crate::cpu::RegisterWrite::clear) method of a CPU register.
+    Clear,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Direction::Read => "read",
+            Direction::Write => "write",
+            Direction::Set => "set",
+            Direction::Clear => "clear",
+        })
+    }
+}
+
+/// The location of the register that was accessed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// A MMIO register at the given address.
+    Mmio(usize),
+    /// A CPU register, represented by the given type name.
+    Cpu(&'static str),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Mmio(addr) => write!(f, "{:#x}", addr),
+            Location::Cpu(ty) => f.write_str(ty),
+        }
+    }
+}
+
+/// A single access to a register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Access {
+    /// The name of the register.
+    pub register: &'static str,
+    /// The name of the field, if the access was done through a field.
+    pub field: Option<&'static str>,
+    /// Where the register is located.
+    pub location: Location,
+    /// The width of the register in bits.
+    pub width: u8,
+    /// The value that was read or written.
+    ///
+    /// For [`Direction::Set`] and [`Direction::Clear`], this is the mask.
+    pub value: u64,
+    /// The direction of this access.
+    pub direction: Direction,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<5} {}", self.direction, self.register)?;
+        if let Some(field) = self.field {
+            write!(f, ".{}", field)?;
+        }
+        let digits = usize::from(self.width / 4);
+        write!(
+            f,
+            " @ {} (u{}) = {:#0width$x}",
+            self.location,
+            self.width,
+            self.value,
+            width = digits + 2
+        )
+    }
+}
+
+/// A receiver for register accesses.
+pub trait Sink: Sync {
+    /// Record the given access.
+    ///
+    /// This method is called after a read, and before a write is performed.
+    fn record(&self, access: &Access);
+}
+
+/// The error that is returned by [`set_sink`] if a sink was already installed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SetSinkError;
+
+impl fmt::Display for SetSinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a trace sink was already installed")
+    }
+}
+
+struct NopSink;
+
+impl Sink for NopSink {
+    fn record(&self, _: &Access) {}
+}
+
+const UNINITIALIZED: usize = 0;
+const INITIALIZING: usize = 1;
+const INITIALIZED: usize = 2;
+
+static STATE: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
+static mut SINK: &dyn Sink = &NopSink;
+
+/// Install the global sink that will receive all register accesses.
+///
+/// This function can only be called once, every call after the
+/// first one will return an error.
+///
+/// On targets without atomic compare-and-swap, like `thumbv6m-none-eabi`,
+/// the state is checked and set using a separate load and store, so calls
+/// of this function must not race with each other, e.g. from an interrupt handler.
+pub fn set_sink(sink: &'static dyn Sink) -> Result<(), SetSinkError> {
+    #[cfg(target_has_atomic = "ptr")]
+    let claimed = STATE
+        .compare_exchange(
+            UNINITIALIZED,
+            INITIALIZING,
+            Ordering::Acquire,
+            Ordering::Relaxed,
+        )
+        .is_ok();
+
+    #[cfg(not(target_has_atomic = "ptr"))]
+    let claimed = {
+        let claimed = STATE.load(Ordering::Acquire) == UNINITIALIZED;
+        if claimed {
+            STATE.store(INITIALIZING, Ordering::Relaxed);
+        }
+        claimed
+    };
+
+    if !claimed {
+        return Err(SetSinkError);
+    }
+
+    // SAFETY: the state guarantees that we are the only one writing the sink,
+    // and nobody will read it until the state is `INITIALIZED`.
+    unsafe { SINK = sink };
+    STATE.store(INITIALIZED, Ordering::Release);
+    Ok(())
+}
+
+/// Return the currently installed sink, if there is one.
+pub fn sink() -> Option<&'static dyn Sink> {
+    if STATE.load(Ordering::Acquire) == INITIALIZED {
+        // SAFETY: the sink is never written again after the state is `INITIALIZED`.
+        Some(unsafe { SINK })
+    } else {
+        None
+    }
+}
+
+/// Report the given access to the installed sink.
+#[inline]
+pub fn record(access: &Access) {
+    if let Some(sink) = sink() {
+        sink.record(access);
+    }
+}
+
+/// A [`Sink`] that writes every access to the [`log`](https://docs.rs/log) crate,
+/// using the `trace` level and the `rumio` target.
+#[cfg(feature = "log")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LogSink;
+
+#[cfg(feature = "log")]
+impl Sink for LogSink {
+    fn record(&self, access: &Access) {
+        log::trace!(target: "rumio", "{}", access);
+    }
+}
+
+/// A [`Sink`] that writes every access to the [`defmt`](https://docs.rs/defmt) crate,
+/// using the `trace` level.
+#[cfg(feature = "defmt")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefmtSink;
+
+#[cfg(feature = "defmt")]
+impl Sink for DefmtSink {
+    fn record(&self, access: &Access) {
+        let direction = match access.direction {
+            Direction::Read => "read",
+            Direction::Write => "write",
+            Direction::Set => "set",
+            Direction::Clear => "clear",
+        };
+        let field = access.field.unwrap_or("");
+
+        match access.location {
+            Location::Mmio(addr) => defmt::trace!(
+                "{=str} {=str}.{=str} @ {=usize:#x} (u{=u8}) = {=u64:#x}",
+                direction,
+                access.register,
+                field,
+                addr,
+                access.width,
+                access.value
+            ),
+            Location::Cpu(ty) => defmt::trace!(
+                "{=str} {=str}.{=str} @ {=str} (u{=u8}) = {=u64:#x}",
+                direction,
+                access.register,
+                field,
+                ty,
+                access.width,
+                access.value
+            ),
+        }
+    }
+}
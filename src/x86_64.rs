@@ -0,0 +1,697 @@
+//! Ready-made definitions for the x86_64 control registers and common model-specific registers.
+//!
+//! Every register has its own module, which contains the register type, that implements
+//! [`RegisterRead`] and [`RegisterWrite`], and the API that is generated by
+//! [`define_cpu_register`](crate::define_cpu_register).
+//!
+//! Model-specific registers are accessed using the `rdmsr` and `wrmsr` instructions.
+//! [`Msr`] can access any MSR by its number, and MSRs that only hold a single value,
+//! like [`FsBase`], are just aliases for it. To give your own MSR a set of fields,
+//! define a register type for it using [`define_msr`](crate::define_msr). Families of
+//! MSRs, like [`perfevtsel`], are indexed registers, that take the index of the MSR.
+//!
+//! Note that most of these registers can only be accessed in ring 0.
+//!
+//! The registers only exist on x86_64, and on other targets if the `std` feature is enabled.
+//! There every register is a mock register from the `cpu::mock` module, so code that uses
+//! them can be tested on the host. The mocks of the MSRs are identified by their number,
+//! so an MSR that is defined using `define_msr` shares its mock with the [`Msr`] of the
+//! same number, and the registers of [`perfevtsel`].
+//!
+//! The layout of page table entries is defined in [`paging`], which is available on every target.
+//!
+//! This module is only available with the `x86_64` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rumio::x86_64::{cr0, cr4, efer};
+//!
+//! cr4::PAE::set(true);
+//! efer::LME::set(true);
+//! cr0::PG::set(true);
+//!
+//! assert!(efer::LMA::get());
+//! ```
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+use crate::cpu::{RegisterRead, RegisterWrite};
+
+pub mod paging;
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// Define the register type for a control register, and implement the CPU register traits.
+macro_rules! cr {
+    ($name:ident, $reg:literal) => {
+        #[doc = ::core::concat!("The `", $reg, "` control register.")]
+        #[derive(Clone, Copy, Debug)]
+        pub struct $name;
+
+        #[cfg(not(target_arch = "x86_64"))]
+        crate::define_mock_register!(@state $name: u64 = 0);
+
+        impl RegisterRead<u64> for $name {
+            #[inline]
+            fn read() -> u64 {
+                #[cfg(target_arch = "x86_64")]
+                unsafe {
+                    let val: u64;
+                    ::core::arch::asm!(
+                        ::core::concat!("mov {}, ", $reg),
+                        out(reg) val,
+                        options(nomem, nostack, preserves_flags)
+                    );
+                    val
+                }
+
+                #[cfg(not(target_arch = "x86_64"))]
+                <Self as crate::cpu::mock::Mock>::mock_read()
+            }
+        }
+
+        impl RegisterWrite<u64> for $name {
+            #[inline]
+            fn write(val: u64) {
+                #[cfg(target_arch = "x86_64")]
+                unsafe {
+                    ::core::arch::asm!(
+                        ::core::concat!("mov ", $reg, ", {}"),
+                        in(reg) val,
+                        options(nostack, preserves_flags)
+                    );
+                }
+
+                #[cfg(not(target_arch = "x86_64"))]
+                <Self as crate::cpu::mock::Mock>::mock_write(val)
+            }
+
+            #[inline]
+            fn set(mask: u64) {
+                crate::impl_cpu_set!(Self, mask);
+            }
+
+            #[inline]
+            fn clear(mask: u64) {
+                crate::impl_cpu_clear!(Self, mask);
+            }
+        }
+    };
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// A model-specific register, that is identified by its number `N`.
+///
+/// The register is read using `rdmsr` and written using `wrmsr`.
+///
+/// # Example
+///
+/// ```no_run
+/// use rumio::{cpu::RegisterRead, x86_64::Msr};
+///
+/// let tsc = Msr::<0x10>::read();
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Msr<const N: u32>;
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+impl<const N: u32> RegisterRead<u64> for Msr<N> {
+    #[inline]
+    fn read() -> u64 {
+        #[cfg(target_arch = "x86_64")]
+        return rdmsr(N);
+
+        #[cfg(not(target_arch = "x86_64"))]
+        <Self as crate::cpu::mock::Mock>::mock_read()
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+impl<const N: u32> RegisterWrite<u64> for Msr<N> {
+    #[inline]
+    fn write(val: u64) {
+        #[cfg(target_arch = "x86_64")]
+        wrmsr(N, val);
+
+        #[cfg(not(target_arch = "x86_64"))]
+        <Self as crate::cpu::mock::Mock>::mock_write(val)
+    }
+
+    #[inline]
+    fn set(mask: u64) {
+        crate::impl_cpu_set!(Self, mask);
+    }
+
+    #[inline]
+    fn clear(mask: u64) {
+        crate::impl_cpu_clear!(Self, mask);
+    }
+}
+
+/// Read the MSR with the given number.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn rdmsr(msr: u32) -> u64 {
+    unsafe {
+        let (low, high): (u32, u32);
+        core::arch::asm!(
+            "rdmsr",
+            in("ecx") msr,
+            out("eax") low,
+            out("edx") high,
+            options(nomem, nostack, preserves_flags)
+        );
+        ((high as u64) << 32) | low as u64
+    }
+}
+
+/// Write the given value into the MSR with the given number.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn wrmsr(msr: u32, val: u64) {
+    unsafe {
+        core::arch::asm!(
+            "wrmsr",
+            in("ecx") msr,
+            in("eax") val as u32,
+            in("edx") (val >> 32) as u32,
+            options(nostack, preserves_flags)
+        );
+    }
+}
+
+#[cfg(all(not(target_arch = "x86_64"), feature = "std"))]
+impl<const N: u32> crate::cpu::mock::Mock for Msr<N> {
+    type Int = u64;
+
+    fn with_state<R>(f: impl FnOnce(&mut crate::cpu::mock::State<u64>) -> R) -> R {
+        with_msr_state(N, f)
+    }
+}
+
+/// Run the closure with the mock state of the MSR with the given number for the current thread.
+#[cfg(all(not(target_arch = "x86_64"), feature = "std"))]
+fn with_msr_state<R>(msr: u32, f: impl FnOnce(&mut crate::cpu::mock::State<u64>) -> R) -> R {
+    use crate::cpu::mock::State;
+    use core::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    std::thread_local! {
+        static MSRS: RefCell<BTreeMap<u32, State<u64>>> = const { RefCell::new(BTreeMap::new()) };
+    }
+
+    MSRS.with(|msrs| f(msrs.borrow_mut().entry(msr).or_insert_with(|| State::new(0))))
+}
+
+/// Define a register type for the model-specific register with the given number.
+///
+/// The type implements [`RegisterRead`] and [`RegisterWrite`] by forwarding
+/// to [`Msr`], and can be used with [`define_cpu_register`](crate::define_cpu_register).
+///
+/// On other targets, the type also implements [`Mock`](crate::cpu::mock::Mock)
+/// by forwarding to the mock of the [`Msr`].
+///
+/// # Example
+///
+/// ```no_run
+/// rumio::define_msr! {
+///     /// The `IA32_MISC_ENABLE` MSR.
+///     pub struct MiscEnable = 0x1A0;
+/// }
+///
+/// rumio::define_cpu_register! { MiscEnable as u64 =>
+///     rw FAST_STRINGS: 0,
+///     rw MONITOR: 18,
+/// }
+///
+/// # fn main() {
+/// MONITOR::set(true);
+/// # }
+/// ```
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+#[macro_export]
+macro_rules! define_msr {
+    ($(#[$attr:meta])* $vis:vis struct $name:ident = $num:expr;) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug)]
+        $vis struct $name;
+
+        impl $crate::cpu::RegisterRead<u64> for $name {
+            #[inline]
+            fn read() -> u64 {
+                <$crate::x86_64::Msr<{ $num }> as $crate::cpu::RegisterRead<u64>>::read()
+            }
+        }
+
+        impl $crate::cpu::RegisterWrite<u64> for $name {
+            #[inline]
+            fn write(val: u64) {
+                <$crate::x86_64::Msr<{ $num }> as $crate::cpu::RegisterWrite<u64>>::write(val)
+            }
+
+            #[inline]
+            fn set(mask: u64) {
+                <$crate::x86_64::Msr<{ $num }> as $crate::cpu::RegisterWrite<u64>>::set(mask)
+            }
+
+            #[inline]
+            fn clear(mask: u64) {
+                <$crate::x86_64::Msr<{ $num }> as $crate::cpu::RegisterWrite<u64>>::clear(mask)
+            }
+        }
+
+        $crate::__msr_mock__!($name = $num);
+    };
+}
+
+/// Hidden macro that forwards the mock of a `define_msr` register to its `Msr`.
+#[cfg(all(not(target_arch = "x86_64"), feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __msr_mock__ {
+    ($name:ident = $num:expr) => {
+        impl $crate::cpu::mock::Mock for $name {
+            type Int = u64;
+
+            fn with_state<R>(
+                f: impl ::core::ops::FnOnce(&mut $crate::cpu::mock::State<u64>) -> R,
+            ) -> R {
+                <$crate::x86_64::Msr<{ $num }> as $crate::cpu::mock::Mock>::with_state(f)
+            }
+        }
+    };
+}
+
+/// Hidden macro that forwards the mock of a `define_msr` register to its `Msr`.
+#[cfg(target_arch = "x86_64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __msr_mock__ {
+    ($name:ident = $num:expr) => {};
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_FS_BASE` MSR, which holds the base address of the `fs` segment.
+pub type FsBase = Msr<0xC000_0100>;
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_GS_BASE` MSR, which holds the base address of the `gs` segment.
+pub type GsBase = Msr<0xC000_0101>;
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_KERNEL_GS_BASE` MSR, which is swapped with [`GsBase`] by `swapgs`.
+pub type KernelGsBase = Msr<0xC000_0102>;
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_STAR` MSR, which holds the segment selectors for `syscall` and `sysret`.
+pub type Star = Msr<0xC000_0081>;
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_LSTAR` MSR, which holds the target address of `syscall`.
+pub type Lstar = Msr<0xC000_0082>;
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_FMASK` MSR, which holds the `rflags` bits that are cleared by `syscall`.
+pub type Sfmask = Msr<0xC000_0084>;
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_TSC_AUX` MSR, which is returned by `rdtscp` and `rdpid`.
+pub type TscAux = Msr<0xC000_0103>;
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+/// The `IA32_PAT` MSR, which holds the page attribute table.
+pub type Pat = Msr<0x277>;
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+pub mod cr0 {
+    //! The `cr0` control register, which controls the operating mode of the processor.
+
+    use super::*;
+
+    cr!(Cr0, "cr0");
+
+    crate::define_cpu_register! { Cr0 as u64 =>
+        /// Protected mode enable.
+        rw PE: 0,
+        /// Monitor coprocessor, which controls `wait` together with `TS`.
+        rw MP: 1,
+        /// Emulate the floating point unit.
+        rw EM: 2,
+        /// Task switched, which delays saving the FPU state.
+        rw TS: 3,
+        /// Extension type, which is always set.
+        r ET: 4,
+        /// Report floating point errors natively.
+        rw NE: 5,
+        /// Write protect read-only pages in ring 0.
+        rw WP: 16,
+        /// Alignment mask, which enables alignment checks together with `rflags.AC`.
+        rw AM: 18,
+        /// Not write-through.
+        rw NW: 29,
+        /// Cache disable.
+        rw CD: 30,
+        /// Enable paging.
+        rw PG: 31,
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+pub mod cr3 {
+    //! The `cr3` control register, which holds the address of the root page table.
+    //!
+    //! The address can be accessed using the raw [`get`] and [`set`] functions, or [`ADDRESS_MASK`].
+
+    use super::*;
+
+    cr!(Cr3, "cr3");
+
+    /// The bits of the physical address of the root page table.
+    pub const ADDRESS_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+    crate::define_cpu_register! { Cr3 as u64 =>
+        /// Page-level write-through for the root page table.
+        rw PWT: 3,
+        /// Page-level cache disable for the root page table.
+        rw PCD: 4,
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+pub mod cr4 {
+    //! The `cr4` control register, which enables architectural extensions.
+
+    use super::*;
+
+    cr!(Cr4, "cr4");
+
+    crate::define_cpu_register! { Cr4 as u64 =>
+        /// Virtual-8086 mode extensions.
+        rw VME: 0,
+        /// Protected mode virtual interrupts.
+        rw PVI: 1,
+        /// Restrict `rdtsc` to ring 0.
+        rw TSD: 2,
+        /// Debugging extensions.
+        rw DE: 3,
+        /// Page size extensions.
+        rw PSE: 4,
+        /// Physical address extension.
+        rw PAE: 5,
+        /// Machine check enable.
+        rw MCE: 6,
+        /// Global pages.
+        rw PGE: 7,
+        /// Allow `rdpmc` outside of ring 0.
+        rw PCE: 8,
+        /// Support for `fxsave` and `fxrstor`.
+        rw OSFXSR: 9,
+        /// Support for unmasked SIMD floating point exceptions.
+        rw OSXMMEXCPT: 10,
+        /// User mode instruction prevention.
+        rw UMIP: 11,
+        /// 57-bit linear addresses, using 5-level paging.
+        rw LA57: 12,
+        /// VMX enable.
+        rw VMXE: 13,
+        /// SMX enable.
+        rw SMXE: 14,
+        /// Enable the `rdfsbase` family of instructions.
+        rw FSGSBASE: 16,
+        /// Process context identifiers.
+        rw PCIDE: 17,
+        /// Support for `xsave` and extended states.
+        rw OSXSAVE: 18,
+        /// Supervisor mode execution prevention.
+        rw SMEP: 20,
+        /// Supervisor mode access prevention.
+        rw SMAP: 21,
+        /// Protection keys for user mode pages.
+        rw PKE: 22,
+        /// Control-flow enforcement technology.
+        rw CET: 23,
+        /// Protection keys for supervisor mode pages.
+        rw PKS: 24,
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+pub mod efer {
+    //! The `IA32_EFER` MSR, which enables long mode and other extended features.
+
+    crate::define_msr! {
+        /// The `IA32_EFER` MSR.
+        pub struct Efer = 0xC000_0080;
+    }
+
+    crate::define_cpu_register! { Efer as u64 =>
+        /// Enable `syscall` and `sysret`.
+        rw SCE: 0,
+        /// Long mode enable.
+        rw LME: 8,
+        /// Long mode active, which is set by the processor.
+        r LMA: 10,
+        /// No-execute enable.
+        rw NXE: 11,
+        /// Secure virtual machine enable.
+        rw SVME: 12,
+        /// Long mode segment limit enable.
+        rw LMSLE: 13,
+        /// Fast `fxsave` and `fxrstor`.
+        rw FFXSR: 14,
+        /// Translation cache extension.
+        rw TCE: 15,
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+pub mod apic_base {
+    //! The `IA32_APIC_BASE` MSR, which holds the address and state of the local APIC.
+    //!
+    //! The address can be accessed using the raw [`get`] and [`set`] functions, or [`ADDRESS_MASK`].
+
+    crate::define_msr! {
+        /// The `IA32_APIC_BASE` MSR.
+        pub struct ApicBase = 0x1B;
+    }
+
+    /// The bits of the physical address of the local APIC.
+    pub const ADDRESS_MASK: u64 = 0x000F_FFFF_FFFF_F000;
+
+    crate::define_cpu_register! { ApicBase as u64 =>
+        /// This processor is the bootstrap processor.
+        r BSP: 8,
+        /// Enable x2APIC mode.
+        rw EXTD: 10,
+        /// Enable the local APIC.
+        rw EN: 11,
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+pub mod rflags {
+    //! The `rflags` register, which holds the status flags and the control flags of the processor.
+    //!
+    //! Unlike the other registers, `rflags` can also be read in user mode.
+
+    use super::*;
+
+    /// The `rflags` register.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Rflags;
+
+    #[cfg(not(target_arch = "x86_64"))]
+    crate::define_mock_register!(@state Rflags: u64 = 0);
+
+    impl RegisterRead<u64> for Rflags {
+        #[inline]
+        fn read() -> u64 {
+            #[cfg(target_arch = "x86_64")]
+            unsafe {
+                let val: u64;
+                core::arch::asm!("pushfq", "pop {}", out(reg) val, options(nomem, preserves_flags));
+                val
+            }
+
+            #[cfg(not(target_arch = "x86_64"))]
+            <Self as crate::cpu::mock::Mock>::mock_read()
+        }
+    }
+
+    impl RegisterWrite<u64> for Rflags {
+        #[inline]
+        fn write(val: u64) {
+            #[cfg(target_arch = "x86_64")]
+            unsafe {
+                core::arch::asm!("push {}", "popfq", in(reg) val);
+            }
+
+            #[cfg(not(target_arch = "x86_64"))]
+            <Self as crate::cpu::mock::Mock>::mock_write(val)
+        }
+
+        #[inline]
+        fn set(mask: u64) {
+            crate::impl_cpu_set!(Self, mask);
+        }
+
+        #[inline]
+        fn clear(mask: u64) {
+            crate::impl_cpu_clear!(Self, mask);
+        }
+    }
+
+    crate::define_cpu_register! { Rflags as u64 =>
+        /// Carry flag.
+        rw CF: 0,
+        /// Parity flag.
+        rw PF: 2,
+        /// Auxiliary carry flag.
+        rw AF: 4,
+        /// Zero flag.
+        rw ZF: 6,
+        /// Sign flag.
+        rw SF: 7,
+        /// Trap flag, which enables single stepping.
+        rw TF: 8,
+        /// Interrupt enable flag.
+        rw IF: 9,
+        /// Direction flag.
+        rw DF: 10,
+        /// Overflow flag.
+        rw OF: 11,
+        /// The I/O privilege level.
+        rw IOPL: 12..13 =
+            /// A privilege level.
+            enum PrivilegeLevel [
+                /// Ring 0, the most privileged level.
+                Ring0 = 0b00,
+                /// Ring 1.
+                Ring1 = 0b01,
+                /// Ring 2.
+                Ring2 = 0b10,
+                /// Ring 3, the least privileged level.
+                Ring3 = 0b11,
+            ],
+        /// Nested task flag.
+        rw NT: 14,
+        /// Resume flag, which is cleared by `popf` and can only be set using `iret`.
+        r RF: 16,
+        /// Virtual-8086 mode, which can only be changed using `iret` or a task switch.
+        r VM: 17,
+        /// Alignment check, or access control if `cr4.SMAP` is enabled.
+        rw AC: 18,
+        /// Virtual interrupt flag, which is ignored by `popf`.
+        r VIF: 19,
+        /// Virtual interrupt pending, which is ignored by `popf`.
+        r VIP: 20,
+        /// Able to use `cpuid`.
+        rw ID: 21,
+    }
+}
+
+#[cfg(any(target_arch = "x86_64", feature = "std"))]
+pub mod perfevtsel {
+    //! The `IA32_PERFEVTSELx` MSRs, which select the event that is counted by the
+    //! general-purpose performance counter with the same index.
+    //!
+    //! The event and unit mask can be accessed using the raw [`get`] and [`set`] functions,
+    //! or [`EVENT_MASK`] and [`UMASK_MASK`].
+    //!
+    //! The family has at most 8 registers, but most CPUs implement fewer. The real number
+    //! of general-purpose counters is reported in bits 8..15 of `EAX` by CPUID leaf `0xA`,
+    //! and accessing the MSR of a counter that doesn't exist raises a general protection
+    //! fault (#GP). The generated functions only panic for an index of 8 or above, so the
+    //! index must be checked against CPUID first.
+    //!
+    //! # Example
+    //!
+    //! ```no_run
+    //! use rumio::x86_64::perfevtsel;
+    //!
+    //! // count the unhalted core cycles in ring 0 and ring 3 on the second counter
+    //! perfevtsel::set(1, 0x3C);
+    //! perfevtsel::modify(1, perfevtsel::USR::SET | perfevtsel::OS::SET | perfevtsel::EN::SET);
+    //! ```
+
+    use super::*;
+    use crate::cpu::{Indexed, IndexedRead, IndexedWrite};
+
+    /// The `IA32_PERFEVTSELx` MSRs, starting at `0x186`.
+    #[derive(Clone, Copy, Debug)]
+    pub struct PerfEvtSel;
+
+    /// The number of the first MSR of the family.
+    const BASE: u32 = 0x186;
+
+    impl Indexed for PerfEvtSel {
+        // the maximum number of counters, see the module docs
+        const COUNT: usize = 8;
+    }
+
+    impl IndexedRead<u64> for PerfEvtSel {
+        #[inline]
+        fn read(idx: usize) -> u64 {
+            #[cfg(target_arch = "x86_64")]
+            return rdmsr(BASE + idx as u32);
+
+            #[cfg(not(target_arch = "x86_64"))]
+            <Self as crate::cpu::mock::IndexedMock>::mock_read(idx)
+        }
+    }
+
+    impl IndexedWrite<u64> for PerfEvtSel {
+        #[inline]
+        fn write(idx: usize, val: u64) {
+            #[cfg(target_arch = "x86_64")]
+            wrmsr(BASE + idx as u32, val);
+
+            #[cfg(not(target_arch = "x86_64"))]
+            <Self as crate::cpu::mock::IndexedMock>::mock_write(idx, val)
+        }
+
+        #[inline]
+        fn set(idx: usize, mask: u64) {
+            crate::impl_cpu_set!(Self[idx], mask);
+        }
+
+        #[inline]
+        fn clear(idx: usize, mask: u64) {
+            crate::impl_cpu_clear!(Self[idx], mask);
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    impl crate::cpu::mock::IndexedMock for PerfEvtSel {
+        type Int = u64;
+
+        fn with_state<R>(
+            idx: usize,
+            f: impl FnOnce(&mut crate::cpu::mock::State<u64>) -> R,
+        ) -> R {
+            assert!(
+                idx < 8,
+                "the index is {} but the `PerfEvtSel` family only has 8 registers",
+                idx
+            );
+            with_msr_state(BASE + idx as u32, f)
+        }
+    }
+
+    /// The bits of the event that is counted.
+    pub const EVENT_MASK: u64 = 0xFF;
+
+    /// The bits of the unit mask, which selects the condition of the event.
+    pub const UMASK_MASK: u64 = 0xFF00;
+
+    crate::define_cpu_register! { PerfEvtSel[idx] as u64 =>
+        /// Count the event in ring 1 to 3.
+        rw USR: 16,
+        /// Count the event in ring 0.
+        rw OS: 17,
+        /// Count the edges of the event, instead of the cycles where it occurs.
+        rw E: 18,
+        /// Toggle the `PMi` pins when the event occurs.
+        rw PC: 19,
+        /// Raise an interrupt when the counter overflows.
+        rw INT: 20,
+        /// Count the event on all logical processors of the core.
+        rw ANY: 21,
+        /// Enable the counter.
+        rw EN: 22,
+        /// Invert the counter mask.
+        rw INV: 23,
+    }
+}
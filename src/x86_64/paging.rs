@@ -0,0 +1,77 @@
+//! Page table entries for 4-level and 5-level paging.
+//!
+//! The entries of all levels share the same layout, so a single [`PageTableEntry`]
+//! type covers all of them. Note that bit 7 is the `PAT` bit in entries that map
+//! a 4 KiB page, and the `HUGE_PAGE` bit on all other levels.
+//!
+//! # Example
+//!
+//! ```
+//! use rumio::x86_64::paging::{self, PageTableEntry};
+//!
+//! let pte = PageTableEntry::new(0)
+//!     .with_present(true)
+//!     .with_writable(true)
+//!     .with_no_execute(true)
+//!     .with_address(0x20_0000);
+//!
+//! assert_eq!(pte.address(), 0x20_0000);
+//! assert_eq!(paging::index(0xFFFF_8000_0000_0000, 3), 256);
+//! ```
+
+/// The size of a base page.
+pub const PAGE_SIZE: u64 = 4096;
+
+/// The number of entries in a single page table.
+pub const ENTRIES: usize = 512;
+
+crate::define_bitfield! {
+    /// An entry of a x86_64 page table.
+    pub struct PageTableEntry: u64 {
+        /// The entry is present.
+        rw PRESENT [present, with_present]: 0,
+        /// The page is writable.
+        rw WRITABLE [writable, with_writable]: 1,
+        /// The page is accessible in user mode.
+        rw USER [user, with_user]: 2,
+        /// Use write-through caching.
+        rw WRITE_THROUGH [write_through, with_write_through]: 3,
+        /// Disable caching.
+        rw CACHE_DISABLE [cache_disable, with_cache_disable]: 4,
+        /// The page was accessed since the bit was cleared.
+        rw ACCESSED [accessed, with_accessed]: 5,
+        /// The page was written since the bit was cleared.
+        rw DIRTY [dirty, with_dirty]: 6,
+        /// The entry maps a 2 MiB or 1 GiB page, instead of pointing to the next level.
+        rw HUGE_PAGE [huge_page, with_huge_page]: 7,
+        /// The mapping is not flushed from the TLB when `CR3` is written.
+        rw GLOBAL [global, with_global]: 8,
+        /// The number of the physical page frame this entry points to.
+        rw FRAME [frame, with_frame]: 12..51,
+        /// The protection key of the page, if `CR4.PKE` or `CR4.PKS` is set.
+        rw PROTECTION_KEY [protection_key, with_protection_key]: 59..62,
+        /// The page is not executable, if `EFER.NXE` is set.
+        rw NO_EXECUTE [no_execute, with_no_execute]: 63,
+    }
+}
+
+impl PageTableEntry {
+    /// Return the physical address this entry points to.
+    pub const fn address(self) -> u64 {
+        self.frame() * PAGE_SIZE
+    }
+
+    /// Return a copy that points to the given physical address.
+    ///
+    /// The offset inside the page is ignored.
+    pub const fn with_address(self, addr: u64) -> Self {
+        self.with_frame(addr / PAGE_SIZE)
+    }
+}
+
+/// Return the index into the page table at the given level, for the virtual address.
+///
+/// The level of the root page table is `3` for 4-level paging, and `4` for 5-level paging.
+pub const fn index(addr: u64, level: usize) -> usize {
+    ((addr >> (12 + 9 * level)) & 0x1FF) as usize
+}
@@ -0,0 +1,38 @@
+#![cfg(all(feature = "aarch64", feature = "std", not(target_arch = "aarch64")))]
+
+use rumio::{
+    aarch64::{current_el, daif, mair_el1, sctlr_el1, tcr_el1},
+    cpu::mock::{Access, Mock},
+};
+
+#[test]
+fn fields() {
+    sctlr_el1::M::set(true);
+    sctlr_el1::I::set(true);
+    assert_eq!(sctlr_el1::SctlrEl1::value(), (1 << 12) | 1);
+    sctlr_el1::M::set(false);
+    assert!(!sctlr_el1::M::get());
+
+    tcr_el1::TG1::set(tcr_el1::Granule1::Kb4);
+    tcr_el1::IPS::set(tcr_el1::PhysicalAddressSize::Bits48);
+    assert_eq!(tcr_el1::TcrEl1::value(), (0b10 << 30) | (0b101 << 32));
+    assert_eq!(tcr_el1::TG1::get(), Some(tcr_el1::Granule1::Kb4));
+
+    daif::Daif::set_value(0b1111 << 6);
+    assert!(daif::I::get());
+    daif::I::set(false);
+    assert_eq!(daif::Daif::value(), 0b1101 << 6);
+}
+
+#[test]
+fn plain_registers() {
+    mair_el1::write(0xFF04);
+    assert_eq!(mair_el1::read(), 0xFF04);
+    assert_eq!(
+        mair_el1::MairEl1::log(),
+        [Access::Write(0xFF04), Access::Read(0xFF04)]
+    );
+
+    current_el::CurrentEl::set_value(0b01 << 2);
+    assert_eq!(current_el::EL::get(), Some(current_el::ExceptionLevel::El1));
+}
@@ -0,0 +1,161 @@
+use rumio::{
+    barrier::{After, Barrier},
+    mmio::Lit,
+    mmio::VolAddr,
+};
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+    static MEM: Cell<u32> = const { Cell::new(0) };
+    static LOG: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A barrier that records the value of the register at the time the barrier is issued.
+pub enum Recording {}
+
+impl Barrier for Recording {
+    fn barrier() {
+        let val = MEM.with(Cell::get);
+        LOG.with(|log| log.borrow_mut().push(val));
+    }
+}
+
+fn log() -> Vec<u32> {
+    LOG.with(|log| std::mem::take(&mut *log.borrow_mut()))
+}
+
+fn addr() -> VolAddr<u32> {
+    MEM.with(|mem| unsafe { VolAddr::new(mem.as_ptr() as usize) })
+}
+
+rumio::define_mmio_register! {
+    Ordered: u32 [Recording] {
+        rw EN: 0,
+        rw MODE: 1..2 = enum Mode [
+            A = 0b00,
+            B = 0b11,
+        ],
+    }
+}
+
+rumio::define_mmio_register! {
+    Unordered: u32 {
+        rw BUSY: 0,
+    }
+}
+
+rumio::define_mmio_register! {
+    BeforeAccess: u32 [before Recording] {
+        rw B_EN: 0,
+    }
+}
+
+rumio::define_mmio_register! {
+    AfterAccess: u32 [after Recording] {
+        rw A_EN: 0,
+    }
+}
+
+rumio::define_mmio_register! {
+    AroundAccess: u32 [before Recording, after Recording] {
+        rw BA_EN: 0,
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct Device {
+        (0x00 => ordered: Ordered),
+    }
+}
+
+#[test]
+fn register_barrier() {
+    MEM.with(|mem| mem.set(0));
+    log();
+
+    let reg = Ordered::new(addr());
+
+    // barrier before every write
+    reg.set(0b10);
+    assert_eq!(log(), [0]);
+
+    // barrier after every read
+    assert_eq!(reg.get(), 0b10);
+    assert_eq!(log(), [0b10]);
+
+    // read-modify-write of a field
+    reg.EN().set(true);
+    assert_eq!(log(), [0b10, 0b10]);
+    reg.MODE().set(Mode::B);
+    assert_eq!(log(), [0b11, 0b11]);
+    assert_eq!(reg.get(), 0b111);
+}
+
+#[test]
+fn access_barrier() {
+    MEM.with(|mem| mem.set(0));
+    log();
+
+    let reg = Unordered::new(addr());
+    reg.BUSY().set(true);
+    assert!(reg.BUSY().get());
+    assert_eq!(log(), []);
+
+    reg.set_with::<Recording>(4);
+    assert_eq!(reg.get_with::<Recording>(), 4);
+    assert_eq!(log(), [1, 4]);
+
+    let lit = Lit::new(addr());
+    lit.write_with::<Recording>(7);
+    assert_eq!(lit.read_with::<Recording>(), 7);
+    assert_eq!(log(), [4, 7]);
+}
+
+#[test]
+fn barrier_placement() {
+    MEM.with(|mem| mem.set(0));
+    log();
+
+    let before = BeforeAccess::new(addr());
+    before.set(1);
+    assert_eq!(before.get(), 1);
+    assert_eq!(log(), [0, 1]);
+
+    let after = AfterAccess::new(addr());
+    after.set(2);
+    assert_eq!(after.get(), 2);
+    assert_eq!(log(), [2, 2]);
+
+    let around = AroundAccess::new(addr());
+    around.BA_EN().set(true);
+    assert_eq!(log(), [2, 2, 2, 3]);
+}
+
+#[test]
+fn field_barrier() {
+    MEM.with(|mem| mem.set(0));
+    log();
+
+    let reg = Unordered::new(addr());
+    reg.BUSY().set_with::<Recording>(true);
+    assert_eq!(log(), [0]);
+    reg.BUSY().set_with::<After<Recording>>(false);
+    assert_eq!(log(), [0]);
+    reg.BUSY().set_with::<After<Recording>>(true);
+    assert_eq!(log(), [1]);
+}
+
+#[test]
+fn save_restore_barrier() {
+    MEM.with(|mem| mem.set(0b10));
+    log();
+
+    let dev = unsafe { Device::new(MEM.with(|mem| mem.as_ptr() as usize)) };
+    let saved = dev.save();
+    assert_eq!(log(), [0b10]);
+
+    MEM.with(|mem| mem.set(0));
+    dev.restore(&saved);
+    assert_eq!(log(), [0]);
+    assert_eq!(dev.ordered().get(), 0b10);
+}
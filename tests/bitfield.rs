@@ -0,0 +1,87 @@
+use rumio::{
+    meta::{Describe, FieldKind},
+    Value,
+};
+
+rumio::define_bitfield! {
+    /// A made-up page table entry.
+    pub struct Entry: u64 {
+        rw VALID [valid, with_valid]: 0,
+        rw PERM [perm, with_perm]: 1..3 = flags Perm [
+            READ = 0b001,
+            WRITE = 0b010,
+            EXEC = 0b100,
+        ],
+        r DIRTY [dirty]: 7,
+        rw CACHE [cache, with_cache]: 8..9 = enum Cache [
+            WriteBack = 0b00,
+            WriteThrough = 0b01,
+            Uncached = 0b11,
+        ],
+        w SOFTWARE [with_software]: 10,
+        rw PPN [ppn, with_ppn]: 12..55,
+    }
+}
+
+const KERNEL: Entry = Entry::new(0)
+    .with_valid(true)
+    .with_perm(Perm::READ.union(Perm::EXEC))
+    .with_cache(Cache::Uncached);
+
+#[test]
+fn getters_and_setters() {
+    assert_eq!(KERNEL.bits(), 0b11_0000_1011);
+    assert!(KERNEL.valid());
+    assert_eq!(KERNEL.perm(), Perm::READ | Perm::EXEC);
+    assert_eq!(KERNEL.cache(), Some(Cache::Uncached));
+    assert!(!KERNEL.dirty());
+
+    let entry = KERNEL.with_valid(false).with_cache(Cache::WriteThrough);
+    assert_eq!(entry.bits(), 0b01_0000_1010);
+    assert_eq!(Entry::new(0b10 << 8).cache(), None);
+    assert!(Entry::new(1 << 7).dirty());
+
+    // setters never touch bits outside of the field
+    let entry = Entry::new(!0).with_perm(Perm::empty()).with_software(false);
+    assert_eq!(entry.bits(), !0b100_0000_1110);
+    assert_eq!(core::mem::size_of::<Entry>(), 8);
+}
+
+#[test]
+fn numbers() {
+    let entry = KERNEL.with_ppn(0x8_0200);
+    assert_eq!(entry.ppn(), 0x8_0200);
+    assert_eq!(entry.bits(), (0x8_0200 << 12) | KERNEL.bits());
+
+    // bits that don't fit into the field are ignored
+    let entry = Entry::new(0).with_ppn(!0);
+    assert_eq!(entry.bits(), 0x00FF_FFFF_FFFF_F000);
+    assert_eq!(Entry::new(!0).ppn(), (1 << 44) - 1);
+}
+
+#[test]
+fn values() {
+    let entry = KERNEL.apply(Value::from(Cache::WriteBack) | Perm::WRITE);
+    assert_eq!(entry.bits(), 0b0101);
+
+    let entry = Entry::from(Value::from(Perm::READ));
+    assert_eq!(u64::from(entry), 0b0010);
+
+    let val = Value::from(KERNEL);
+    assert_eq!(val.modify(!0), KERNEL.bits());
+    assert_eq!(Entry::PERM.read(0b1111), 0b1110);
+}
+
+#[test]
+fn decode_and_metadata() {
+    assert_eq!(
+        format!("{:?}", KERNEL),
+        "Entry { VALID: true, PERM: READ | EXEC, DIRTY: false, CACHE: Uncached, PPN: 0x0 }"
+    );
+
+    let info = Entry::INFO;
+    assert_eq!(info.name, "Entry");
+    assert_eq!(info.width, 64);
+    assert_eq!(info.fields.len(), 6);
+    assert_eq!(info.field("PPN").unwrap().kind, FieldKind::Number);
+}
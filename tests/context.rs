@@ -0,0 +1,87 @@
+#![cfg(feature = "std")]
+
+use rumio::cpu::{
+    mock::{Access, Mock},
+    Context,
+};
+
+rumio::define_mock_register! {
+    struct Status: u32 = 0;
+}
+
+rumio::define_cpu_register! { context Status as u32 =>
+    rw IE: 0,
+    r PENDING: 1,
+
+    rw MODE: 2..3 = enum Mode [
+        User = 0b00,
+        Supervisor = 0b01,
+        Machine = 0b11,
+    ],
+
+    rw EVENTS: 4..6 = flags Events [
+        TIMER = 0b001,
+        SOFTWARE = 0b010,
+        EXTERNAL = 0b100,
+    ],
+}
+
+#[derive(Default)]
+struct Vcpu {
+    status: u32,
+}
+
+impl Context<Status, u32> for Vcpu {
+    fn read(&self) -> u32 {
+        self.status
+    }
+
+    fn write(&mut self, val: u32) {
+        self.status = val;
+    }
+}
+
+#[test]
+fn fields() {
+    let mut vcpu = Vcpu::default();
+
+    IE::set_in(&mut vcpu, true);
+    MODE::set_in(&mut vcpu, Mode::Machine);
+    EVENTS::set_in(&mut vcpu, Events::TIMER | Events::EXTERNAL);
+    assert_eq!(vcpu.status, 0b101_1101);
+
+    assert!(IE::get_in(&vcpu));
+    assert!(!PENDING::get_in(&vcpu));
+    assert_eq!(MODE::get_in(&vcpu), Some(Mode::Machine));
+    assert_eq!(EVENTS::get_in(&vcpu), Events::TIMER | Events::EXTERNAL);
+
+    vcpu.status = 0b1010;
+    assert!(PENDING::get_in(&vcpu));
+    assert_eq!(MODE::get_in(&vcpu), None);
+    assert_eq!(MODE::try_get_in(&vcpu), Err(rumio::Error::InvalidPattern));
+
+    // the live register is never touched
+    assert!(Status::log().is_empty());
+}
+
+#[test]
+fn whole_register() {
+    let mut vcpu = Vcpu::default();
+    IE::set(true);
+
+    set_in(&mut vcpu, 0b10);
+    assert!(is_set_in(&vcpu, PENDING::FIELD));
+
+    modify_in(&mut vcpu, IE::SET | Mode::Supervisor);
+    assert_eq!(get_in(&vcpu), 0b111);
+    assert_eq!(read_in(&vcpu, PENDING::FIELD), 0b10);
+
+    assert_eq!(
+        format!("{:?}", snapshot_in(&vcpu)),
+        "Status { IE: true, PENDING: true, MODE: Supervisor, EVENTS: (empty) }"
+    );
+
+    write_in(&mut vcpu, Mode::Machine.into());
+    assert_eq!(get_in(&vcpu), 0b1100);
+    assert_eq!(Status::log(), [Access::Set(0b1)]);
+}
@@ -1,10 +1,15 @@
-use rumio::cpu::{RegisterRead, RegisterWrite};
+use rumio::{
+    cpu::{RegisterRead, RegisterWrite},
+    perm,
+    poll::{Iterations, Timeout},
+    Field, Value,
+};
 use std::sync::atomic::{AtomicU64, Ordering};
 
 const DEFAULT_REG_VALUE: u64 = 0b101;
 
 thread_local! {
-    static REGISTER: AtomicU64 = AtomicU64::new(DEFAULT_REG_VALUE);
+    static REGISTER: AtomicU64 = const { AtomicU64::new(DEFAULT_REG_VALUE) };
 }
 
 fn reset_register() {
@@ -134,3 +139,109 @@ fn read_fields() {
     let x = read(FOO::FIELD | Mode::field() | BAZ::FIELD);
     assert_eq!(x, 0b10101);
 }
+
+#[test]
+fn wait_for_fields() {
+    reset_register();
+    assert_reg_eq(DEFAULT_REG_VALUE);
+
+    assert_eq!(FOO::wait_for_set(Iterations::new(1)), Ok(()));
+    assert_eq!(BAR::wait_for_set(Iterations::new(3)), Err(Timeout));
+    assert_eq!(BAZ::wait_for_clear(Iterations::new(1)), Ok(()));
+
+    assert_eq!(MODE::wait_for(Mode::B, Iterations::new(1)), Ok(()));
+    assert_eq!(
+        MODE::wait_until(Iterations::new(2), |m| m == Some(Mode::C)),
+        Err(Timeout)
+    );
+
+    assert_eq!(
+        FLAGS::wait_for_clear(Flags::all(), Iterations::new(1)),
+        Ok(())
+    );
+    assert_eq!(
+        FLAGS::wait_for_set(Flags::A, Iterations::new(1)),
+        Err(Timeout)
+    );
+
+    assert_eq!(
+        wait_until(Iterations::new(1), |v| v == DEFAULT_REG_VALUE),
+        Ok(DEFAULT_REG_VALUE)
+    );
+    assert_eq!(wait_for_set(FOO::FIELD, Iterations::new(1)), Ok(()));
+    assert_eq!(
+        wait_for_clear(BAR::FIELD | BAZ::FIELD, Iterations::new(1)),
+        Ok(())
+    );
+}
+
+#[test]
+fn decode_values() {
+    reset_register();
+    assert_reg_eq(DEFAULT_REG_VALUE);
+
+    assert_eq!(
+        format!("{:?}", snapshot()),
+        "CpuRegister { MODE: B, FOO: true, BAR: false, BAZ: false, FLAGS: (empty) }"
+    );
+    assert_eq!(
+        format!("{:#?}", decode(0b11_1010)),
+        "CpuRegister {\n    MODE: C,\n    FOO: false,\n    BAR: true,\n    BAZ: true,\n    FLAGS: A,\n}"
+    );
+}
+
+#[test]
+fn metadata() {
+    use rumio::meta::{Describe, Perm};
+
+    let info = <CpuRegister as Describe>::INFO;
+    assert_eq!(info.name, "CpuRegister");
+    assert_eq!(info.width, 64);
+    assert_eq!(info.perm, Perm::ReadWrite);
+    assert_eq!(info.fields.len(), 6);
+    assert_eq!(info.field("WRITE").unwrap().perm, Perm::Write);
+    assert_eq!(info.field("FLAGS").unwrap().from, 5);
+    assert_eq!(info.field("FLAGS").unwrap().to, 8);
+}
+
+#[test]
+fn fallible_operations() {
+    reset_register();
+
+    assert_eq!(MODE::try_get(), Ok(Mode::B));
+    assert_eq!(MODE::try_set(Mode::D), Ok(()));
+    assert_eq!(MODE::try_get(), Ok(Mode::D));
+
+    assert_eq!(FLAGS::try_set(Flags::A | Flags::D), Ok(()));
+    assert_eq!(FLAGS::try_get(), Ok(Flags::A | Flags::D));
+
+    assert_eq!(BAR::try_set(true), Ok(()));
+    assert_reg_eq(0b1_0010_1111);
+
+    assert_eq!(try_modify(Mode::A | BAZ::SET), Ok(()));
+    assert_reg_eq(0b1_0011_1100);
+
+    let foo = Value::<u64>::new(1 << 2, 0);
+    assert_eq!(try_modify(foo), Err(rumio::Error::OutOfRange));
+    let unknown = Value::<u64>::new(1 << 20, 1 << 20);
+    assert_eq!(try_modify(unknown), Err(rumio::Error::OutOfRange));
+    assert_reg_eq(0b1_0011_1100);
+}
+
+#[test]
+fn const_values() {
+    const INIT: Value<u64> = Mode::C
+        .value()
+        .or(BAZ::SET)
+        .or(Flags::A.union(Flags::D).value());
+    static INIT_VALUE: u64 = INIT.apply(DEFAULT_REG_VALUE);
+    const MODE_FIELD: Field<u64, perm::ReadWrite> = Mode::field();
+
+    assert_eq!(INIT_VALUE, 0b1_0011_0110);
+    assert_eq!(MODE_FIELD.extract(INIT_VALUE), 0b10);
+    assert_eq!(Value::from(Mode::C).bits(), Mode::C.value().bits());
+
+    reset_register();
+    modify(INIT);
+    assert_reg_eq(INIT_VALUE);
+}
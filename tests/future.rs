@@ -0,0 +1,122 @@
+#![cfg(feature = "async")]
+
+use rumio::future::{WakerSource, Yield};
+use std::{
+    cell::Cell,
+    future::Future,
+    pin::Pin,
+    ptr,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Poll the future until it is ready, calling `between` after every pending poll.
+fn block_on<F: Future>(fut: F, mut between: impl FnMut()) -> F::Output {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = Box::pin(fut);
+
+    loop {
+        match Pin::as_mut(&mut fut).poll(&mut cx) {
+            Poll::Ready(val) => return val,
+            Poll::Pending => between(),
+        }
+    }
+}
+
+/// A waker source that counts how often it was registered.
+#[derive(Default)]
+struct Counter {
+    registered: Cell<usize>,
+}
+
+impl WakerSource for Counter {
+    fn register(&self, waker: &Waker) {
+        self.registered.set(self.registered.get() + 1);
+        waker.wake_by_ref();
+    }
+}
+
+rumio::define_mmio_register! {
+    Reg: u16 {
+        rw MODE: 0..1 = enum Mode [
+            A = 0b00,
+            B = 0b01,
+            C = 0b10,
+            D = 0b11,
+        ],
+
+        r BUSY: 2,
+
+        rw FLAGS: 5..8 = flags Flags [
+            A = 0b0001,
+            B = 0b0010,
+            C = 0b0100,
+            D = 0b1000,
+        ],
+    }
+}
+
+#[test]
+fn wait_for_bit() {
+    let mut mem = 0b100u16;
+    let addr = &mut mem as *mut u16 as usize;
+    let reg = Reg::new(unsafe { rumio::mmio::VolAddr::new(addr) });
+
+    let counter = Counter::default();
+    let mut polls = 0;
+    block_on(reg.BUSY().wait_for_clear_async(&counter), || {
+        polls += 1;
+        if polls == 3 {
+            unsafe { ptr::write_volatile(addr as *mut u16, 0) };
+        }
+    });
+    assert_eq!(counter.registered.get(), 3);
+
+    unsafe { ptr::write_volatile(addr as *mut u16, 0b100) };
+    block_on(reg.BUSY().wait_for_set_async(Yield), || {
+        panic!("bit is already set")
+    });
+}
+
+#[test]
+fn wait_for_enum_and_flags() {
+    let mut mem = 0u16;
+    let addr = &mut mem as *mut u16 as usize;
+    let reg = Reg::new(unsafe { rumio::mmio::VolAddr::new(addr) });
+
+    let mut polls = 0;
+    block_on(reg.MODE().wait_for_async(Mode::C, Yield), || {
+        polls += 1;
+        if polls == 2 {
+            reg.MODE().set(Mode::C);
+        }
+    });
+    assert_eq!(reg.MODE().get(), Some(Mode::C));
+
+    let mut polls = 0;
+    let flags = block_on(
+        reg.FLAGS()
+            .wait_until_async(Yield, |f| f.contains(Flags::B)),
+        || {
+            polls += 1;
+            reg.FLAGS().set(Flags::from_bits_truncate(polls));
+        },
+    );
+    assert_eq!(flags, Flags::B);
+
+    let val = block_on(reg.wait_until_async(Yield, |v| v & 0b11 == 0b10), || {
+        panic!("register already has the value")
+    });
+    assert_eq!(val, 0b0100_0010);
+
+    block_on(reg.wait_for_clear_async(BUSY::FIELD, Yield), || {
+        panic!("bit is already cleared")
+    });
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "std")]
+
+use rumio::cpu::mock::IndexedMock;
+
+rumio::define_mock_register! {
+    struct Counter[4]: u32 = 0;
+}
+
+fn counter(idx: usize) -> u32 {
+    Counter::value(idx)
+}
+
+rumio::define_cpu_register! { Counter[idx] as u32 =>
+    rw EN: 0,
+    r OVERFLOW: 1,
+
+    rw MODE: 2..3 = enum Mode [
+        Cycles = 0b00,
+        Instructions = 0b01,
+        Misses = 0b10,
+    ],
+
+    rw EVENTS: 4..6 = flags Events [
+        USER = 0b001,
+        SUPERVISOR = 0b010,
+        MACHINE = 0b100,
+    ],
+}
+
+#[test]
+fn fields() {
+    EN::set(1, true);
+    MODE::set(1, Mode::Misses);
+    EVENTS::set(3, Events::USER | Events::MACHINE);
+
+    assert_eq!(counter(0), 0);
+    assert_eq!(counter(1), 0b1001);
+    assert_eq!(counter(3), 0b101_0000);
+
+    assert!(EN::get(1));
+    assert!(!EN::get(2));
+    assert!(!OVERFLOW::get(1));
+    assert_eq!(MODE::get(1), Some(Mode::Misses));
+    assert_eq!(MODE::try_get(3), Ok(Mode::Cycles));
+    assert_eq!(EVENTS::get(3), Events::USER | Events::MACHINE);
+
+    EN::set(1, false);
+    assert_eq!(counter(1), 0b1000);
+}
+
+#[test]
+fn whole_register() {
+    set(2, 0b10);
+    assert_eq!(get(2), 0b10);
+    assert!(is_set(2, OVERFLOW::FIELD));
+
+    modify(2, EN::SET | Mode::Instructions);
+    assert_eq!(get(2), 0b111);
+    write(0, EN::SET);
+    assert_eq!(get(0), 0b1);
+
+    assert_eq!(
+        format!("{:?}", snapshot(2)),
+        "Counter { EN: true, OVERFLOW: true, MODE: Instructions, EVENTS: (empty) }"
+    );
+}
+
+#[test]
+#[should_panic(expected = "the index is 4 but the `Counter` family only has 4 registers")]
+fn index_out_of_range() {
+    EN::set(4, true);
+}
@@ -0,0 +1,185 @@
+use rumio::{
+    init::{InitError, InitErrorKind, RegisterInit},
+    mmio::Lit,
+    poll::Iterations,
+    Value,
+};
+
+rumio::define_mmio_register! {
+    Ctrl: u32 {
+        rw EN: 0 unless LOCK,
+        r READY: 1,
+        rw CLOCK: 2..3 = enum Clock [
+            Off = 0b00,
+            Slow = 0b01,
+            Fast = 0b10,
+        ],
+        rw LOCK: 31,
+    }
+}
+
+rumio::define_mmio_register! {
+    Status: u8 {
+        r BUSY: 0,
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct Device {
+        (0x00 => ctrl: Ctrl),
+        (0x04 => status: Status),
+        (0x08 => div: [Lit<u16>; 2]),
+    }
+}
+
+const IS_READY: Value<u32> = Value::<u32>::new(0b10, 0b10);
+
+static BRING_UP: [RegisterInit<Device>; 6] = [
+    RegisterInit::write_at(Device::div, 1, Value::<u16>::new(!0, 0x1234)),
+    RegisterInit::write(Device::ctrl, Clock::Fast.value()),
+    RegisterInit::delay(10),
+    RegisterInit::wait(Device::ctrl, IS_READY),
+    RegisterInit::modify(Device::ctrl, EN::SET),
+    RegisterInit::wait(Device::status, Value::<u8>::new(0b1, 0)),
+];
+
+fn device(mem: &mut [u32; 3]) -> Device {
+    unsafe { Device::new(mem.as_mut_ptr() as usize) }
+}
+
+#[test]
+fn run_table() {
+    let mut mem = [0u32; 3];
+    let dev = device(&mut mem);
+    let mut delays = vec![];
+
+    let res = dev.init(
+        &BRING_UP,
+        || Iterations::new(5),
+        |ticks| {
+            delays.push(ticks);
+            // the hardware becomes ready after the delay
+            dev.ctrl().set(0b1010);
+        },
+    );
+
+    assert_eq!(res, Ok(()));
+    assert_eq!(delays, [10]);
+    assert_eq!(mem, [0b1011, 0, 0x1234_0000]);
+}
+
+#[test]
+fn failed_steps() {
+    let mut mem = [0u32; 3];
+    let dev = device(&mut mem);
+
+    let err = dev
+        .init(&BRING_UP, || Iterations::new(5), |_| {})
+        .unwrap_err();
+    assert_eq!(
+        err,
+        InitError {
+            step: 3,
+            kind: InitErrorKind::Timeout,
+        }
+    );
+    assert_eq!(err.to_string(), "init step 3 failed: timed out");
+
+    let table = [
+        RegisterInit::modify(Device::ctrl, LOCK::SET),
+        RegisterInit::modify(Device::ctrl, EN::SET),
+    ];
+    let err = dev.init(&table, || Iterations::new(5), |_| {}).unwrap_err();
+    assert_eq!((err.step, err.kind), (1, InitErrorKind::Locked));
+    assert_eq!(mem, [(1 << 31) | 0b1000, 0, 0x1234_0000]);
+}
+
+rumio::define_mmio_struct! {
+    pub struct Owned<'a> {
+        (0x00 => mut ctrl: Ctrl),
+        (0x04 => status: Status),
+        (0x08 => mut div: [Lit<u16>; 2]),
+    }
+
+    restore [ctrl]
+}
+
+#[test]
+fn owned_struct() {
+    let mut mem = [0b10u32, 0, 0];
+    let mut dev = unsafe { Owned::new_in(mem.as_mut_ptr() as usize, &mem) };
+
+    let table = [
+        RegisterInit::write_at(Owned::div, 0, Value::<u16>::new(!0, 0x5678)),
+        RegisterInit::wait(Owned::status, Value::<u8>::new(0b1, 0)),
+        RegisterInit::write(Owned::ctrl, Clock::Slow.value()),
+        RegisterInit::modify(Owned::ctrl, EN::SET),
+        RegisterInit::wait(Owned::ctrl, Value::<u32>::new(0b1, 0b1)),
+    ];
+    assert_eq!(dev.init(&table, || Iterations::new(5), |_| {}), Ok(()));
+    assert_eq!(dev.ctrl().get(), 0b101);
+    assert_eq!(dev.div(0).read(), 0x5678);
+}
+
+#[test]
+fn restore_order() {
+    let mut mem = [0u32; 3];
+    let mut dev = unsafe { Owned::new_in(mem.as_mut_ptr() as usize, &mem) };
+
+    // `ctrl` must be written after all other registers
+    let table = [
+        RegisterInit::modify(Owned::ctrl, EN::SET),
+        RegisterInit::wait(Owned::status, Value::<u8>::new(0b1, 0)),
+        RegisterInit::write_at(Owned::div, 1, Value::<u16>::new(!0, 1)),
+    ];
+    let err = dev.init(&table, || Iterations::new(5), |_| {}).unwrap_err();
+    assert_eq!(
+        err,
+        InitError {
+            step: 2,
+            kind: InitErrorKind::Order,
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "init step 2 failed: the restore order is violated"
+    );
+    // the table is rejected before any step runs
+    assert_eq!(mem, [0; 3]);
+}
+
+rumio::define_mmio_struct! {
+    pub struct Timer @ 0x1000 {
+        (0x00 => mut ctrl: Ctrl),
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct Uart {
+        (0x00 => mut ctrl: Ctrl) in [Enabled],
+        (0x04 => status: Status),
+    }
+
+    states uart_state [Disabled, Enabled]
+
+    transitions {
+        enable: Disabled => Enabled,
+    }
+}
+
+#[test]
+fn singletons_and_type_states() {
+    // a singleton can't be created on the host, so only check that the table compiles
+    static _TIMER: [RegisterInit<Timer>; 1] = [RegisterInit::modify(Timer::ctrl, EN::SET)];
+
+    static ENABLE: [RegisterInit<Uart<uart_state::Enabled>>; 2] = [
+        RegisterInit::wait(Uart::status, Value::<u8>::new(0b1, 0)),
+        RegisterInit::modify(Uart::ctrl, EN::SET),
+    ];
+
+    let mut mem = [0u32; 2];
+    let uart = unsafe { Uart::<uart_state::Disabled>::new(mem.as_mut_ptr() as usize) };
+    let mut uart = uart.enable();
+    assert_eq!(uart.init(&ENABLE, || Iterations::new(5), |_| {}), Ok(()));
+    assert!(uart.ctrl().EN().get());
+}
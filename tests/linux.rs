@@ -0,0 +1,105 @@
+#![cfg(all(feature = "std", target_os = "linux"))]
+
+use rumio::linux::Mapping;
+use std::{
+    fs::{self, OpenOptions},
+    path::PathBuf,
+};
+
+rumio::define_mmio_register! {
+    Ctrl: u32 {
+        rw EN: 0,
+        rw MODE: 1..2 = enum Mode [
+            Slow = 0b00,
+            Fast = 0b01,
+        ],
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct Uart<'a> {
+        (0x00 => mut ctrl: Ctrl),
+        (0x04 => mut data: rumio::mmio::Lit<u32>),
+    }
+}
+
+/// A temporary file that is removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, size: usize) -> Self {
+        let path = std::env::temp_dir().join(format!("rumio-{}-{}", std::process::id(), name));
+        fs::write(&path, vec![0u8; size]).unwrap();
+        Self(path)
+    }
+
+    fn open(&self) -> fs::File {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.0)
+            .unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn map_file() {
+    let file = TempFile::new("map_file", 0x100);
+    let mut mapping = Mapping::file(&file.open(), 0, 0x100).unwrap();
+    assert_eq!(mapping.len(), 0x100);
+
+    let mut uart = mapping.device::<Uart<'_>>().unwrap();
+    uart.ctrl().EN().set(true);
+    uart.ctrl().MODE().set(Mode::Fast);
+    uart.data().write(0xDEAD_BEEF);
+
+    let mem = fs::read(&file.0).unwrap();
+    assert_eq!(&mem[0..4], &0b11u32.to_ne_bytes());
+    assert_eq!(&mem[4..8], &0xDEAD_BEEFu32.to_ne_bytes());
+
+    let mut uart = mapping.device_at::<Uart<'_>>(0x10).unwrap();
+    uart.data().write(0x1234);
+    assert_eq!(
+        &fs::read(&file.0).unwrap()[0x14..0x18],
+        &0x1234u32.to_ne_bytes()
+    );
+}
+
+#[test]
+fn map_unaligned_offset() {
+    let file = TempFile::new("map_unaligned_offset", 0x2000);
+    fs::write(&file.0, {
+        let mut mem = vec![0u8; 0x2000];
+        mem[0x1008..0x100C].copy_from_slice(&0b10u32.to_ne_bytes());
+        mem
+    })
+    .unwrap();
+
+    let mut mapping = Mapping::file(&file.open(), 0x1008, 8).unwrap();
+    assert_eq!(mapping.addr() % 8, 0);
+
+    let mut uart = mapping.device::<Uart<'_>>().unwrap();
+    assert_eq!(uart.ctrl().MODE().get(), Some(Mode::Fast));
+    assert!(!uart.ctrl().EN().get());
+}
+
+#[test]
+fn device_must_fit() {
+    let file = TempFile::new("device_must_fit", 0x100);
+    let mut mapping = Mapping::file(&file.open(), 0, 6).unwrap();
+
+    assert!(mapping.device::<Uart<'_>>().is_none());
+    assert!(mapping.device_at::<Uart<'_>>(usize::MAX).is_none());
+
+    // the registers of `Uart` are 4 byte wide
+    let mut mapping = Mapping::file(&file.open(), 0, 0x20).unwrap();
+    assert!(mapping.device_at::<Uart<'_>>(2).is_none());
+    assert!(mapping.device_at::<Uart<'_>>(4).is_some());
+    assert!(Mapping::file(&file.open(), 0, 0).is_err());
+}
@@ -1,5 +1,9 @@
-use rumio::mmio::Lit;
-use std::{mem::ManuallyDrop, ptr};
+use rumio::{
+    barrier::Barrier,
+    mmio::Lit,
+    poll::{Iterations, Timeout},
+};
+use std::{cell::RefCell, mem::ManuallyDrop, ptr};
 
 struct MmioRegion {
     ptr: *mut u8,
@@ -45,14 +49,131 @@ rumio::define_mmio_register! {
     }
 }
 
+rumio::define_mmio_register! {
+    LockReg: u32 {
+        rw LEN: 0 unless LOCK,
+        rw LMODE: 1..2 = enum LockMode [
+            X = 0b00,
+            Y = 0b11,
+        ] unless LOCK,
+        w LFLAGS: 4..5 = flags LockFlags [
+            A = 0b01,
+            B = 0b10,
+        ] unless LOCK,
+        rw LOCK: 31,
+    }
+}
+
+rumio::define_mmio_register! {
+    TryReg: u32 {
+        rw TMODE: 0..1 = enum TryMode [
+            X = 0b00,
+            Y = 0b01,
+            Z = 0b10,
+        ],
+        rw TFLAGS: 2..3 = flags TryFlags [
+            A = 0b001,
+            B = 0b010,
+            TOO_BIG = 0b100,
+        ] unless TLOCK,
+        rw TLOCK: 4,
+    }
+}
+
 rumio::define_mmio_struct! {
     pub struct Device {
         /// foobar
         (0x00 => one: Reg),
         (0x08 => two: Reg),
-        (0x0A => lit1: Lit<u32>),
-        (0x0E => lit2: Lit<u8>),
-        (0x0F => lit3: [Lit<u8>; 32]),
+        (0x0C => lit1: Lit<u32>),
+        (0x10 => lit2: Lit<u8>),
+        (0x11 => lit3: [Lit<u8>; 32]),
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct OwnedDevice<'a> {
+        (0x00 => mut one: Reg),
+        (0x04 => mut data: Lit<u32>),
+        (0x08 => mut fifo: [Lit<u8>; 4]),
+    }
+}
+
+thread_local! {
+    static WRITES: RefCell<Vec<char>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A barrier that logs the register it is declared for, before every write.
+pub struct Log<const REG: char>;
+
+impl<const REG: char> Barrier for Log<REG> {
+    fn barrier() {
+        WRITES.with(|writes| writes.borrow_mut().push(REG));
+    }
+}
+
+fn writes() -> Vec<char> {
+    WRITES.with(|writes| std::mem::take(&mut *writes.borrow_mut()))
+}
+
+rumio::define_mmio_register! {
+    LogCtrl: u32 [before Log<'c'>] {
+        rw LOG_EN: 0,
+    }
+}
+
+rumio::define_mmio_register! {
+    LogDiv: u16 [before Log<'d'>] {
+        rw LOG_DIV: 0..15 = enum LogDivider [
+            One = 1,
+        ],
+    }
+}
+
+rumio::define_mmio_register! {
+    LogData: u32 [before Log<'x'>] {
+        rw LOG_DATA: 0..7 = enum LogByte [
+            Zero = 0,
+        ],
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct LogDevice {
+        (0x00 => ctrl: LogCtrl),
+        (0x04 => div: LogDiv),
+        (0x08 => data: LogData),
+    }
+
+    restore [div, ctrl]
+}
+
+rumio::define_mmio_register! {
+    StateReg: u32 {
+        rw RUN: 0,
+        rw SPEED: 1..2 = enum Speed [
+            Slow = 0b00,
+            Fast = 0b01,
+        ] in [device_state::Off, device_state::Configuring],
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct StateDevice {
+        (0x00 => mut ctrl: Reg),
+        (0x04 => mut data: Lit<u32>) in [Enabled],
+        (0x08 => mut config: [Lit<u8>; 4]) in [Off, Configuring],
+        (0x0C => mut mode: StateReg),
+    }
+
+    restore [mode, ctrl]
+
+    states device_state [Off, Configuring, Enabled]
+
+    transitions {
+        configure: Off => Configuring,
+        enable: Configuring => Enabled = |dev| dev.ctrl().BAR().set(true),
+        disable: Enabled => Off = |dev| dev.ctrl().BAR().set(false),
     }
 }
 
@@ -104,7 +225,7 @@ fn read_write_flags() {
 
 #[test]
 fn read_write_lit() {
-    let (_guard, addr) = MmioRegion::new(16);
+    let (_guard, addr) = MmioRegion::new(32);
     let mmio = unsafe { Device::new(addr) };
 
     assert_eq!(mmio.lit1().read(), 0);
@@ -119,7 +240,7 @@ fn read_write_lit() {
 
 #[test]
 fn read_write_array() {
-    let (_guard, addr) = MmioRegion::new(48);
+    let (_guard, addr) = MmioRegion::new(64);
     let mmio = unsafe { Device::new(addr) };
 
     for idx in 0..32 {
@@ -130,3 +251,348 @@ fn read_write_array() {
         assert_eq!(mmio.lit3(idx).read(), idx as u8);
     }
 }
+
+#[test]
+fn wait_for_fields() {
+    let (_guard, addr) = MmioRegion::new(16);
+    let mmio = unsafe { Device::new(addr) };
+    unsafe { ptr::write_volatile(addr as *mut u16, 0b1_0001_0101) };
+
+    assert_eq!(mmio.one().FOO().wait_for_set(Iterations::new(1)), Ok(()));
+    assert_eq!(
+        mmio.one().FOO().wait_for_clear(Iterations::new(3)),
+        Err(Timeout)
+    );
+    assert_eq!(mmio.one().BAR().wait_for_clear(Iterations::new(1)), Ok(()));
+
+    assert_eq!(
+        mmio.one().MODE().wait_for(Mode::B, Iterations::new(1)),
+        Ok(())
+    );
+    assert_eq!(
+        mmio.one()
+            .MODE()
+            .wait_until(Iterations::new(1), |m| m == Some(Mode::D)),
+        Err(Timeout)
+    );
+
+    assert_eq!(
+        mmio.one()
+            .FLAGS()
+            .wait_for_set(Flags::D, Iterations::new(1)),
+        Ok(())
+    );
+    assert_eq!(
+        mmio.one()
+            .FLAGS()
+            .wait_for_clear(Flags::A | Flags::D, Iterations::new(1)),
+        Err(Timeout)
+    );
+
+    assert_eq!(
+        mmio.one()
+            .wait_until(Iterations::new(1), |v| v & 0b100 != 0),
+        Ok(0b1_0001_0101)
+    );
+    assert_eq!(
+        mmio.one()
+            .wait_for_set(FOO::FIELD | BAR::FIELD, Iterations::new(1)),
+        Ok(())
+    );
+    assert_eq!(
+        mmio.one().wait_for_clear(BAR::FIELD, Iterations::new(1)),
+        Ok(())
+    );
+}
+
+#[test]
+fn decode_values() {
+    let (_guard, addr) = MmioRegion::new(16);
+    let mmio = unsafe { Device::new(addr) };
+    unsafe { ptr::write_volatile(addr as *mut u16, 0b1_0101_0110) };
+
+    let snapshot = mmio.one().snapshot();
+    assert_eq!(snapshot.raw(), 0b1_0101_0110);
+    assert_eq!(
+        format!("{:?}", snapshot),
+        "Reg { MODE: C, FOO: true, BAR: false, BAZ: true, FLAGS: B | D }"
+    );
+    assert_eq!(
+        format!("{}", Reg::decode(0)),
+        "Reg { MODE: A, FOO: false, BAR: false, BAZ: false, FLAGS: (empty) }"
+    );
+    assert_eq!(format!("{:#06x}", Reg::decode(0xAB)), "0x00ab");
+}
+
+#[test]
+fn metadata() {
+    use rumio::meta::{Describe, FieldKind, Perm, Variant};
+
+    let reg = <Reg as Describe>::INFO;
+    assert_eq!(reg.name, "Reg");
+    assert_eq!(reg.width, 16);
+    assert_eq!(reg.perm, Perm::ReadWrite);
+    assert_eq!(
+        reg.fields.iter().map(|f| f.name).collect::<Vec<_>>(),
+        ["MODE", "FOO", "BAR", "BAZ", "FLAGS"]
+    );
+
+    let foo = reg.field("FOO").unwrap();
+    assert_eq!(foo.perm, Perm::Read);
+    assert_eq!((foo.from, foo.to), (2, 2));
+    assert_eq!(foo.kind, FieldKind::Bit);
+
+    let flags = reg.field("FLAGS").unwrap();
+    assert_eq!(flags.mask(), 0b1_1110_0000);
+    assert_eq!(flags.extract(0b1_0100_0000), 0b1010);
+    assert_eq!(flags.insert(0b1111, 0b0001), 0b10_1111);
+    match flags.kind {
+        FieldKind::Flags(flags) => assert_eq!(
+            flags[3],
+            Variant {
+                name: "D",
+                value: 0b1000
+            }
+        ),
+        kind => panic!("unexpected kind: {:?}", kind),
+    }
+
+    let mode = reg.field("MODE").unwrap();
+    assert_eq!(mode.variant(0b10).map(|v| v.name), Some("C"));
+    assert!(reg.field("NOPE").is_none());
+
+    let block = Device::INFO;
+    assert_eq!(block.name, "Device");
+    assert_eq!(
+        block
+            .registers
+            .iter()
+            .map(|r| (r.name, r.offset, r.count, r.info.name))
+            .collect::<Vec<_>>(),
+        [
+            ("one", 0x00, 1, "Reg"),
+            ("two", 0x08, 1, "Reg"),
+            ("lit1", 0x0C, 1, "Lit"),
+            ("lit2", 0x10, 1, "Lit"),
+            ("lit3", 0x11, 32, "Lit"),
+        ]
+    );
+    assert_eq!(block.register("lit1").unwrap().info.width, 32);
+    assert_eq!(block.register("lit3").unwrap().offset_of(4), 0x15);
+    assert_eq!(block.size(), 0x31);
+}
+
+#[test]
+fn owned_device() {
+    use rumio::mmio::Block;
+
+    fn send(dev: &mut OwnedDevice<'_>, bytes: &[u8]) {
+        for (idx, &byte) in bytes.iter().enumerate() {
+            dev.fifo(idx).write(byte);
+        }
+        dev.data().write(bytes.len() as u32);
+    }
+
+    let mut mem = vec![0u32; 4];
+    {
+        let mut dev = unsafe { OwnedDevice::new_in(mem.as_mut_ptr() as usize, &mem) };
+
+        dev.one().BAR().set(true);
+        assert!(dev.one().BAR().get());
+
+        send(&mut dev, &[0xAB, 0xCD]);
+        assert_eq!(dev.data().read(), 2);
+        assert_eq!(dev.fifo(1).read(), 0xCD);
+    }
+    assert_eq!(mem[1], 2);
+
+    assert_eq!(<OwnedDevice<'_> as Block>::INFO.size(), 12);
+    assert_eq!(
+        OwnedDevice::INFO
+            .registers
+            .iter()
+            .map(|r| (r.name, r.offset, r.count))
+            .collect::<Vec<_>>(),
+        [("one", 0x00, 1), ("data", 0x04, 1), ("fifo", 0x08, 4)]
+    );
+}
+
+#[test]
+fn typestate_device() {
+    use device_state::{Enabled, Off};
+    use rumio::mmio::Block;
+
+    let mut mem = vec![0u32; 4];
+    let mut dev = unsafe { StateDevice::<Off>::new(mem.as_mut_ptr() as usize) };
+    dev.config(0).write(0x12);
+    dev.mode().SPEED().set(Speed::Fast);
+
+    let mut dev = dev.configure();
+    dev.config(3).write(0x34);
+    assert!(!dev.ctrl().BAR().get());
+
+    let mut dev = dev.enable();
+    assert!(dev.ctrl().BAR().get());
+    dev.mode().RUN().set(true);
+    dev.data().write(0xDEAD_BEEF);
+    assert_eq!(dev.data().read(), 0xDEAD_BEEF);
+
+    let mut dev = dev.disable();
+    assert!(!dev.ctrl().BAR().get());
+    assert_eq!(dev.config(0).read(), 0x12);
+    assert_eq!(dev.config(3).read(), 0x34);
+    assert_eq!(dev.mode().SPEED().get(), Some(Speed::Fast));
+    assert!(dev.mode().RUN().get());
+
+    let saved = dev.save();
+    dev.mode().RUN().set(false);
+    dev.config(0).write(0);
+    dev.restore(&saved);
+    assert!(dev.mode().RUN().get());
+    assert_eq!(dev.config(0).read(), 0x12);
+
+    assert_eq!(<StateDevice<Enabled> as Block>::INFO.size(), 16);
+    assert_eq!(StateDevice::<Off>::INFO.restore_order, ["mode", "ctrl"]);
+    assert_eq!(
+        StateDevice::<Off>::INFO.register("data").unwrap().offset,
+        0x04
+    );
+}
+
+#[test]
+fn restore_order() {
+    let mut mem = [0u32; 3];
+    let dev = unsafe { LogDevice::new(mem.as_mut_ptr() as usize) };
+    let saved = dev.save();
+
+    writes();
+    dev.restore(&saved);
+    assert_eq!(writes(), ['x', 'd', 'c']);
+}
+
+#[test]
+fn locked_fields() {
+    use rumio::mmio::{Locked, VolAddr};
+
+    let mut mem = 0u32;
+    let reg = LockReg::new(unsafe { VolAddr::new(&mut mem as *mut u32 as usize) });
+
+    assert_eq!(reg.LEN().set(true), Ok(()));
+    assert_eq!(reg.LMODE().set(LockMode::Y), Ok(()));
+    assert_eq!(reg.LFLAGS().set(LockFlags::B), Ok(()));
+    assert_eq!(reg.get(), 0b10_0111);
+
+    reg.LOCK().set(true);
+    assert_eq!(reg.LEN().set(false), Err(Locked));
+    assert_eq!(reg.LMODE().set(LockMode::X), Err(Locked));
+    assert_eq!(reg.LFLAGS().set(LockFlags::A), Err(Locked));
+    assert_eq!(reg.get(), 0x8000_0027);
+
+    reg.LEN().set_unchecked(false);
+    reg.LMODE().set_unchecked(LockMode::X);
+    reg.LFLAGS().set_unchecked(LockFlags::A);
+    assert_eq!(reg.get(), 0x8000_0010);
+
+    reg.LOCK().set(false);
+    assert_eq!(reg.LEN().set(true), Ok(()));
+    assert!(reg.LEN().get());
+}
+
+#[test]
+fn fallible_operations() {
+    use rumio::{mmio::VolAddr, Error};
+
+    let mut mem = 0u32;
+    let reg = TryReg::new(unsafe { VolAddr::new(&mut mem as *mut u32 as usize) });
+
+    assert_eq!(reg.TMODE().try_get(), Ok(TryMode::X));
+    assert_eq!(reg.TMODE().try_set(TryMode::Z), Ok(()));
+    assert_eq!(reg.TMODE().try_get(), Ok(TryMode::Z));
+    reg.set(0b11);
+    assert_eq!(reg.TMODE().try_get(), Err(Error::InvalidPattern));
+
+    assert_eq!(reg.TFLAGS().try_set(TryFlags::A | TryFlags::B), Ok(()));
+    assert_eq!(reg.TFLAGS().try_get(), Ok(TryFlags::A | TryFlags::B));
+    assert_eq!(
+        reg.TFLAGS().try_set(TryFlags::TOO_BIG),
+        Err(Error::OutOfRange)
+    );
+    assert_eq!(reg.get(), 0b1111);
+
+    reg.TLOCK().set(true);
+    assert_eq!(reg.TFLAGS().try_set(TryFlags::A), Err(Error::Locked));
+    assert_eq!(reg.try_modify(TryFlags::A.into()), Err(Error::Locked));
+    assert_eq!(reg.try_modify(TryMode::Y.into()), Ok(()));
+    assert_eq!(reg.get(), 0b1_1101);
+
+    reg.TLOCK().set(false);
+    assert_eq!(
+        reg.try_modify(TryMode::X | rumio::Value::from(TryFlags::A)),
+        Ok(())
+    );
+    assert_eq!(reg.get(), 0b0_0100);
+
+    // bit 31 is not part of any field
+    let unknown = rumio::Value::<u32>::new(1 << 31, 1 << 31);
+    assert_eq!(reg.try_modify(unknown), Err(Error::OutOfRange));
+    assert_eq!(reg.get(), 0b0_0100);
+
+    let err: Error = Timeout.into();
+    assert_eq!(err.to_string(), Timeout.to_string());
+}
+
+#[test]
+fn save_restore_diff() {
+    let (_guard, addr) = MmioRegion::new(64);
+    let mmio = unsafe { Device::new(addr) };
+
+    mmio.one().MODE().set(Mode::B);
+    mmio.lit1().write(0x1234);
+    mmio.lit3(4).write(0xAA);
+    let saved = mmio.save();
+    assert_eq!(saved.get("one", 0), Some(0b01));
+    assert_eq!(saved.get("lit3", 4), Some(0xAA));
+    assert_eq!(saved.get("lit3", 32), None);
+
+    mmio.one().MODE().set(Mode::D);
+    mmio.one().FLAGS().set(Flags::A);
+    mmio.two().BAR().set(true);
+    mmio.lit3(4).write(0xBB);
+
+    let changes = saved
+        .diff(&mmio.save())
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            "one.MODE: 0x1 -> 0x3",
+            "one.FLAGS: 0x0 -> 0x1",
+            "two.BAR: 0x0 -> 0x1",
+            "lit3[4]: 0xaa -> 0xbb",
+        ]
+    );
+
+    mmio.restore(&saved);
+    assert_eq!(mmio.one().MODE().get(), Some(Mode::B));
+    assert!(mmio.one().FLAGS().get().is_empty());
+    assert!(!mmio.two().BAR().get());
+    assert_eq!(mmio.lit1().read(), 0x1234);
+    assert_eq!(mmio.lit3(4).read(), 0xAA);
+    assert_eq!(saved.diff(&mmio.save()).count(), 0);
+
+    // the read-only `FOO` bit is not written back
+    mmio.one().set(0b1101);
+    let saved = mmio.save();
+    assert_eq!(saved.get("one", 0), Some(0b1101));
+    mmio.one().set(0);
+    mmio.restore(&saved);
+    assert_eq!(mmio.one().get(), 0b1001);
+
+    let mut mem = vec![0u32; 4];
+    let mut owned = unsafe { OwnedDevice::new_in(mem.as_mut_ptr() as usize, &mem) };
+    let saved = owned.save();
+    owned.data().write(7);
+    owned.restore(&saved);
+    assert_eq!(owned.data().read(), 0);
+}
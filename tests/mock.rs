@@ -0,0 +1,110 @@
+#![cfg(feature = "std")]
+
+use rumio::cpu::mock::{Access, Mock};
+
+rumio::define_mock_register! {
+    pub struct Reg: u64 = 0b101;
+}
+
+rumio::define_cpu_register! { Reg as u64 =>
+    rw MODE: 0..1 = enum Mode [
+        A = 0b00,
+        B = 0b01,
+        C = 0b10,
+        D = 0b11,
+    ],
+
+    r READY: 2,
+    rw EN: 3,
+
+    rw FLAGS: 4..5 = flags Flags [
+        X = 0b01,
+        Y = 0b10,
+    ],
+}
+
+#[test]
+fn initial_value_and_log() {
+    assert_eq!(Reg::value(), 0b101);
+    assert_eq!(MODE::get(), Some(Mode::B));
+
+    MODE::set(Mode::C);
+    EN::set(true);
+    EN::set(false);
+
+    assert_eq!(
+        Reg::take_log(),
+        [
+            Access::Read(0b101),
+            Access::Read(0b101),
+            Access::Write(0b110),
+            Access::Set(0b1000),
+            Access::Clear(0b1000),
+        ]
+    );
+    assert!(Reg::log().is_empty());
+    assert_eq!(Reg::value(), 0b110);
+
+    Reg::set_value(0);
+    assert!(Reg::log().is_empty());
+    Reg::reset();
+    assert_eq!(get(), 0b101);
+}
+
+#[test]
+fn read_sequences() {
+    Reg::push_reads([0b000, 0b000, 0b100]);
+
+    assert!(!READY::get());
+    assert!(!READY::get());
+    assert!(READY::get());
+    // the queue is empty, so the current value is returned again
+    assert_eq!(get(), 0b101);
+}
+
+#[test]
+fn hardware_owned_bits() {
+    // `READY` can't be written, and `FLAGS::X` clears itself
+    Reg::on_write(|old, new| (new & !0b1_0100) | (old & 0b100));
+    // every read sets `READY`
+    Reg::on_read(|val| val | 0b100);
+
+    Reg::set_value(0);
+    set(0b11_1111);
+    assert_eq!(Reg::value(), 0b10_1011);
+    assert_eq!(FLAGS::get(), Flags::Y);
+    assert!(READY::get());
+    assert_eq!(Reg::value(), 0b10_1111);
+}
+
+mod counter {
+    rumio::define_mock_register! {
+        pub struct Counter[4]: u32 = 0b10;
+    }
+
+    rumio::define_cpu_register! { Counter[idx] as u32 =>
+        rw EN: 0,
+        r OVERFLOW: 1,
+    }
+}
+
+#[test]
+fn indexed_registers() {
+    use counter::Counter;
+    use rumio::cpu::mock::IndexedMock;
+
+    counter::EN::set(1, true);
+    assert_eq!(Counter::value(1), 0b11);
+    assert_eq!(Counter::value(0), 0b10);
+
+    Counter::push_reads(3, [0]);
+    assert!(!counter::OVERFLOW::get(3));
+    assert!(counter::OVERFLOW::get(3));
+
+    assert_eq!(Counter::take_log(1), [Access::Set(0b1)]);
+    assert_eq!(Counter::log(3), [Access::Read(0), Access::Read(0b10)]);
+
+    Counter::reset();
+    assert_eq!(Counter::value(1), 0b10);
+    assert!(Counter::log(3).is_empty());
+}
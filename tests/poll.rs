@@ -0,0 +1,97 @@
+use rumio::poll::{self, Clock, ClockDeadline, Deadline, Forever, Iterations, Timeout};
+use std::cell::Cell;
+
+/// A fake clock that advances by one tick every time it is queried.
+struct FakeClock {
+    now: Cell<u64>,
+}
+
+impl FakeClock {
+    fn new(start: u64) -> Self {
+        Self {
+            now: Cell::new(start),
+        }
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> u64 {
+        let now = self.now.get();
+        self.now.set(now.wrapping_add(1));
+        now
+    }
+}
+
+#[test]
+fn iterations_budget() {
+    let mut polls = 0;
+    let res = poll::wait_until(Iterations::new(5), || {
+        polls += 1;
+        None::<()>
+    });
+    assert_eq!(res, Err(Timeout));
+    assert_eq!(polls, 5);
+
+    let mut polls = 0;
+    let res = poll::wait_until(Iterations::new(0), || {
+        polls += 1;
+        None::<()>
+    });
+    assert_eq!(res, Err(Timeout));
+    assert_eq!(polls, 1);
+
+    let mut polls = 0;
+    let res = poll::wait_until(Iterations::new(5), || {
+        polls += 1;
+        if polls == 3 {
+            Some(polls)
+        } else {
+            None
+        }
+    });
+    assert_eq!(res, Ok(3));
+}
+
+#[test]
+fn clock_deadline() {
+    let clock = FakeClock::new(0);
+    let mut deadline = ClockDeadline::after(&clock, 3);
+
+    assert!(!deadline.expired());
+    assert!(!deadline.expired());
+    assert!(deadline.expired());
+
+    let clock = FakeClock::new(0);
+    let mut polls = 0;
+    let res = poll::wait_until(ClockDeadline::after(&clock, 10), || {
+        polls += 1;
+        None::<()>
+    });
+    assert_eq!(res, Err(Timeout));
+    assert_eq!(polls, 10);
+}
+
+#[test]
+fn clock_deadline_wraps() {
+    let clock = FakeClock::new(u64::MAX - 1);
+    let mut deadline = ClockDeadline::after(&clock, 4);
+
+    assert!(!deadline.expired());
+    assert!(!deadline.expired());
+    assert!(!deadline.expired());
+    assert!(deadline.expired());
+}
+
+#[test]
+fn forever_and_borrowed_deadline() {
+    let mut polls = 0;
+    let res = poll::wait_until(Forever, || {
+        polls += 1;
+        (polls == 100).then_some(())
+    });
+    assert_eq!(res, Ok(()));
+
+    let mut budget = Iterations::new(4);
+    assert_eq!(poll::wait_until(&mut budget, || None::<()>), Err(Timeout));
+    assert_eq!(budget.remaining(), 0);
+}
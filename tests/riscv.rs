@@ -0,0 +1,107 @@
+#![cfg(feature = "riscv")]
+
+#[cfg(feature = "std")]
+use rumio::{
+    meta::{Describe, Perm},
+    riscv::{mie, mip, mstatus, mtvec},
+};
+
+#[cfg(feature = "std")]
+#[test]
+fn decode_mstatus() {
+    let val = (1 << 3) | (0b11 << 11) | (0b10 << 13) | (1 << 18);
+    let decoded = mstatus::decode(val);
+
+    assert!(decoded.to_string().contains("MIE: true"));
+    assert!(decoded.to_string().contains("MPP: Machine"));
+    assert!(decoded.to_string().contains("FS: Clean"));
+    assert!(decoded.to_string().contains("SUM: true"));
+    assert!(decoded.to_string().contains("SIE: false"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn metadata() {
+    let info = <mstatus::Mstatus as Describe>::INFO;
+    assert_eq!(info.width, usize::BITS as usize);
+    assert_eq!(info.field("MPP").unwrap().from, 11);
+    assert_eq!(info.field("MPP").unwrap().to, 12);
+    assert_eq!(info.field("XS").unwrap().perm, Perm::Read);
+
+    let info = <mip::Mip as Describe>::INFO;
+    assert_eq!(info.field("MTIP").unwrap().perm, Perm::Read);
+    assert_eq!(info.field("STIP").unwrap().perm, Perm::ReadWrite);
+
+    assert_eq!(<mie::Mie as Describe>::INFO.field("MEIE").unwrap().from, 11);
+    assert_eq!(<mtvec::Mtvec as Describe>::INFO.fields.len(), 1);
+}
+
+#[cfg(all(feature = "std", target_pointer_width = "64"))]
+#[test]
+fn satp_modes() {
+    use rumio::riscv::satp;
+
+    assert_eq!(
+        rumio::Value::<usize>::from(satp::TranslationMode::Sv39).bits(),
+        8 << 60
+    );
+    assert_eq!(satp::PPN_MASK, 0xFFF_FFFF_FFFF);
+}
+
+#[cfg(all(
+    feature = "std",
+    not(any(target_arch = "riscv32", target_arch = "riscv64"))
+))]
+#[test]
+fn mock_csrs() {
+    use rumio::cpu::mock::{Access, Mock};
+
+    mstatus::Mstatus::set_value(0b11 << 11);
+    mstatus::MIE::set(true);
+    mstatus::MPP::set(mstatus::Mode::Supervisor);
+
+    assert!(mstatus::MIE::get());
+    assert_eq!(mstatus::MPP::get(), Some(mstatus::Mode::Supervisor));
+    assert_eq!(mstatus::Mstatus::log()[0], Access::Set(1 << 3));
+}
+
+#[test]
+fn page_table_entries() {
+    use rumio::riscv::paging::{self, MemoryType, PageTableEntry, Software};
+
+    // the kernel mapping of Linux: `_PAGE_KERNEL` at `0x8020_0000`
+    let pte = PageTableEntry::new(0)
+        .with_valid(true)
+        .with_read(true)
+        .with_write(true)
+        .with_global(true)
+        .with_accessed(true)
+        .with_dirty(true)
+        .with_address(0x8020_0000);
+    assert_eq!(pte.bits(), 0x2008_00E7);
+    assert!(pte.is_leaf());
+
+    // a pointer to the next level only has the `V` bit set
+    let table = PageTableEntry::new(0x2008_0401);
+    assert!(table.valid() && !table.is_leaf());
+    assert_eq!(table.ppn(), 0x80201);
+    assert_eq!(table.address(), 0x8020_1000);
+
+    // `Svpbmt` memory types and `Svnapot`
+    assert_eq!(pte.with_pbmt(MemoryType::Io).bits(), 0x4000_0000_2008_00E7);
+    assert_eq!(PageTableEntry::new(1 << 61).pbmt(), Some(MemoryType::Nc));
+    assert_eq!(PageTableEntry::new(0b11 << 61).pbmt(), None);
+    assert!(PageTableEntry::new(1 << 63).napot());
+    assert_eq!(pte.with_rsw(Software::SW1).bits() & 0x300, 0x200);
+
+    // the PPN never spills into the other fields
+    let pte = PageTableEntry::new(!0).with_ppn(0);
+    assert_eq!(pte.bits(), 0xFFC0_0000_0000_03FF);
+    assert_eq!(PageTableEntry::PPN.read(!0), 0x003F_FFFF_FFFF_FC00);
+    assert!(format!("{:?}", table).contains("PPN: 0x80201"));
+
+    // Sv39 and Sv48 indices of the first address in the upper half
+    assert_eq!(paging::vpn(0xFFFF_FFC0_0000_0000, 2), 256);
+    assert_eq!(paging::vpn(0xFFFF_8000_0000_0000, 3), 256);
+    assert_eq!(paging::vpn(0x8020_1000, 0), 1);
+}
@@ -0,0 +1,268 @@
+#![cfg(all(feature = "std", target_os = "linux"))]
+
+use rumio::shell::{Error, MappedMemory, Memory, Shell};
+use std::{fs, path::PathBuf};
+
+rumio::define_mmio_register! {
+    Ctrl: u32 {
+        rw EN: 0,
+        rw MODE: 1..2 = enum Mode [
+            Slow = 0b00,
+            Fast = 0b01,
+        ],
+        r BUSY: 3,
+        rw FLAGS: 4..6 = flags Flags [
+            A = 0b001,
+            B = 0b010,
+            C = 0b100,
+        ],
+    }
+}
+
+rumio::define_mmio_register! {
+    Data: u8 {
+        w TX: 0,
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct Uart {
+        (0x00 => ctrl: Ctrl),
+        (0x04 => data: Data),
+        (0x05 => fifo: [rumio::mmio::Lit<u8>; 3]),
+    }
+}
+
+/// A temporary file that is removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, size: usize) -> Self {
+        let path = std::env::temp_dir().join(format!("rumio-{}-{}", std::process::id(), name));
+        fs::write(&path, vec![0u8; size]).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn memory(file: &TempFile) -> MappedMemory {
+    let mut mem = MappedMemory::open(&file.0).unwrap();
+    mem.map(0x10, Uart::INFO.size()).unwrap();
+    mem
+}
+
+fn shell(file: &TempFile) -> Shell<MappedMemory> {
+    let mut shell = Shell::new(memory(file));
+    shell.device("UART0", 0x10, &Uart::INFO);
+    shell
+}
+
+fn exec(shell: &mut Shell<MappedMemory>, cmd: &str) -> Result<String, Error> {
+    let mut out = Vec::new();
+    shell.execute(cmd, &mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn read_and_set() {
+    let file = TempFile::new("read_and_set", 0x20);
+    let mut shell = shell(&file);
+
+    assert_eq!(
+        exec(&mut shell, "read UART0.CTRL").unwrap(),
+        "UART0.ctrl = 0x00000000 { EN: false, MODE: Slow, BUSY: false, FLAGS: (empty) }\n"
+    );
+
+    exec(&mut shell, "set UART0.CTRL.EN 1").unwrap();
+    exec(&mut shell, "set uart0.ctrl.mode fast").unwrap();
+    exec(&mut shell, "set UART0.CTRL.FLAGS A|C").unwrap();
+    assert_eq!(
+        exec(&mut shell, "read UART0.CTRL").unwrap(),
+        "UART0.ctrl = 0x00000053 { EN: true, MODE: Fast, BUSY: false, FLAGS: A | C }\n"
+    );
+    assert_eq!(
+        exec(&mut shell, "read UART0.ctrl.MODE").unwrap(),
+        "UART0.ctrl.MODE = Fast\n"
+    );
+
+    let mem = fs::read(&file.0).unwrap();
+    assert_eq!(&mem[0x10..0x14], &0x53u32.to_ne_bytes());
+
+    exec(&mut shell, "set UART0.ctrl 0x0000_0006").unwrap();
+    assert_eq!(
+        exec(&mut shell, "read UART0.ctrl").unwrap(),
+        "UART0.ctrl = 0x00000006 { EN: false, MODE: 0x3, BUSY: false, FLAGS: (empty) }\n"
+    );
+
+    exec(&mut shell, "set UART0.fifo[2] 0xAB").unwrap();
+    assert_eq!(
+        exec(&mut shell, "read UART0.fifo[2]").unwrap(),
+        "UART0.fifo[2] = 0xab\n"
+    );
+    assert_eq!(memory(&file).read(0x17, 1).unwrap(), 0xAB);
+}
+
+#[test]
+fn dump_and_list() {
+    let file = TempFile::new("dump_and_list", 0x20);
+    let mut shell = shell(&file);
+    exec(&mut shell, "set UART0.fifo[0] 1").unwrap();
+
+    assert_eq!(
+        exec(&mut shell, "dump UART0").unwrap(),
+        "UART0.ctrl = 0x00000000 { EN: false, MODE: Slow, BUSY: false, FLAGS: (empty) }\n\
+         UART0.fifo[0] = 0x01\n\
+         UART0.fifo[1] = 0x00\n\
+         UART0.fifo[2] = 0x00\n"
+    );
+
+    assert_eq!(
+        exec(&mut shell, "list").unwrap(),
+        "UART0 (Uart) @ 0x10\n  \
+         0x0000 ctrl: Ctrl (u32)\n  \
+         0x0004 data: Data (u8)\n  \
+         0x0005 fifo[3]: Lit (u8)\n"
+    );
+}
+
+#[test]
+fn errors() {
+    let file = TempFile::new("errors", 0x20);
+    let mut shell = shell(&file);
+
+    assert!(matches!(
+        exec(&mut shell, "foo"),
+        Err(Error::UnknownCommand(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "read UART1.ctrl"),
+        Err(Error::UnknownDevice(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "read UART0.foo"),
+        Err(Error::UnknownRegister(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "read UART0.ctrl.FOO"),
+        Err(Error::UnknownField(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "read UART0.fifo[3]"),
+        Err(Error::InvalidIndex(3))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "read UART0.fifo"),
+        Err(Error::Usage(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "read UART0.data"),
+        Err(Error::NotReadable)
+    ));
+    assert!(matches!(
+        exec(&mut shell, "set UART0.ctrl.BUSY 1"),
+        Err(Error::NotWritable)
+    ));
+    assert!(matches!(
+        exec(&mut shell, "set UART0.ctrl.EN 2"),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "set UART0.ctrl.MODE Medium"),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "set UART0.fifo[0] 0x100"),
+        Err(Error::InvalidValue(_))
+    ));
+    assert!(matches!(
+        exec(&mut shell, "set UART0.ctrl"),
+        Err(Error::Usage(_))
+    ));
+}
+
+#[test]
+fn run_loop() {
+    let file = TempFile::new("run_loop", 0x20);
+    let mut shell = shell(&file);
+
+    let mut out = Vec::new();
+    shell
+        .run(
+            "set UART0.data.TX true\nread UART0.data\n\n".as_bytes(),
+            &mut out,
+        )
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "> > error: not readable\n> > \n"
+    );
+    assert_eq!(fs::read(&file.0).unwrap()[0x14], 1);
+}
+
+#[test]
+fn mapped_memory() {
+    let file = TempFile::new("mapped_memory", 0x20);
+    let mem = memory(&file);
+
+    mem.write(0x10, 4, 0x1234_5678).unwrap();
+    assert_eq!(mem.read(0x10, 4).unwrap(), 0x1234_5678);
+    assert_eq!(mem.read(0x10, 2).unwrap(), u64::from(0x1234_5678u32 as u16));
+    assert_eq!(
+        &fs::read(&file.0).unwrap()[0x10..0x14],
+        &0x1234_5678u32.to_ne_bytes()
+    );
+
+    assert!(mem.read(0x11, 4).is_err());
+    assert!(mem.read(0x08, 4).is_err());
+    assert!(mem.write(0x18, 1, 0).is_err());
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn traced_accesses() {
+    use rumio::trace::{self, Access, Direction, Sink};
+    use std::sync::Mutex;
+
+    rumio::define_mmio_register! {
+        Traced: u16 {
+            rw READY: 0,
+        }
+    }
+
+    rumio::define_mmio_struct! {
+        pub struct Dma {
+            (0x00 => status: Traced),
+        }
+    }
+
+    struct Recorder(Mutex<Vec<(Direction, u64)>>);
+
+    impl Sink for Recorder {
+        fn record(&self, access: &Access) {
+            if access.register == "Traced" {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((access.direction, access.value));
+            }
+        }
+    }
+
+    static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+    trace::set_sink(&RECORDER).unwrap();
+
+    let file = TempFile::new("traced_accesses", 0x20);
+    let mut shell = Shell::new(memory(&file));
+    shell.device("DMA", 0x10, &Dma::INFO);
+
+    exec(&mut shell, "set DMA.status.READY true").unwrap();
+    assert_eq!(
+        *RECORDER.0.lock().unwrap(),
+        [(Direction::Read, 0), (Direction::Write, 1)]
+    );
+}
@@ -0,0 +1,44 @@
+use rumio::mmio::{Block, Lit};
+
+rumio::define_mmio_struct! {
+    pub struct Timer @ 0x4000_1000 {
+        (0x00 => count: Lit<u32>),
+        (0x04 => mut reload: Lit<u32>),
+    }
+}
+
+rumio::define_mmio_struct! {
+    pub struct Other @ (Timer::ADDRESS + 0x100) {
+        (0x00 => ctrl: [Lit<u8>; 4]),
+    }
+}
+
+#[test]
+fn take_once() {
+    let timer = Timer::take().unwrap();
+    assert!(Timer::take().is_none());
+    assert!(Timer::take().is_none());
+
+    // another singleton has its own flag
+    let other = Other::take().unwrap();
+    assert!(Other::take().is_none());
+    assert_eq!(Other::ADDRESS, 0x4000_1100);
+
+    let _stolen = unsafe { Timer::steal() };
+    assert!(Timer::take().is_none());
+
+    assert_eq!(Timer::INFO.name, "Timer");
+    assert_eq!(<Other as Block>::INFO.size(), 4);
+    let _ = (timer, other);
+}
+
+#[test]
+fn access_registers() {
+    let mut mem = [0u32; 2];
+    let mut timer = unsafe { <Timer as Block>::new(mem.as_mut_ptr() as usize) };
+
+    timer.reload().write(0x1234);
+    assert_eq!(timer.count().read(), 0);
+    assert_eq!(timer.reload().read(), 0x1234);
+    assert_eq!(mem[1], 0x1234);
+}
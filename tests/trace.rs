@@ -0,0 +1,172 @@
+#![cfg(feature = "trace")]
+
+use rumio::{
+    cpu::{RegisterRead, RegisterWrite},
+    trace::{self, Access, Direction, Location, Sink},
+};
+use std::{
+    cell::Cell,
+    sync::{Mutex, MutexGuard},
+};
+
+struct Recorder(Mutex<Vec<Access>>);
+
+impl Sink for Recorder {
+    fn record(&self, access: &Access) {
+        self.0.lock().unwrap().push(*access);
+    }
+}
+
+static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Install the recorder and clear all recorded accesses.
+///
+/// The returned guard must be held for the whole test, because the sink is global.
+fn recorder() -> MutexGuard<'static, ()> {
+    let guard = LOCK.lock().unwrap();
+    let _ = trace::set_sink(&RECORDER);
+    RECORDER.0.lock().unwrap().clear();
+    guard
+}
+
+fn recorded() -> Vec<Access> {
+    std::mem::take(&mut *RECORDER.0.lock().unwrap())
+}
+
+rumio::define_mmio_register! {
+    Reg: u16 {
+        rw MODE: 0..1 = enum Mode [
+            A = 0b00,
+            B = 0b01,
+        ],
+
+        r FOO: 2,
+    }
+}
+
+thread_local! {
+    static REGISTER: Cell<u32> = const { Cell::new(0) };
+}
+
+struct CpuRegister;
+
+impl RegisterRead<u32> for CpuRegister {
+    fn read() -> u32 {
+        REGISTER.with(Cell::get)
+    }
+}
+
+impl RegisterWrite<u32> for CpuRegister {
+    fn write(val: u32) {
+        REGISTER.with(|reg| reg.set(val))
+    }
+
+    fn set(mask: u32) {
+        rumio::impl_cpu_set!(Self, mask);
+    }
+
+    fn clear(mask: u32) {
+        rumio::impl_cpu_clear!(Self, mask);
+    }
+}
+
+mod cpu {
+    use super::CpuRegister;
+
+    rumio::define_cpu_register! { CpuRegister as u32 =>
+        rw EN: 4,
+    }
+}
+
+#[test]
+fn trace_mmio_accesses() {
+    let _guard = recorder();
+
+    let mut mem = 0b101u16;
+    let addr = &mut mem as *mut u16 as usize;
+    let reg = Reg::new(unsafe { rumio::mmio::VolAddr::new(addr) });
+
+    assert!(reg.FOO().get());
+    reg.MODE().set(Mode::A);
+    reg.set(0xABCD);
+
+    let accesses = recorded();
+    assert_eq!(accesses.len(), 4);
+
+    assert_eq!(accesses[0].register, "Reg");
+    assert_eq!(accesses[0].field, Some("FOO"));
+    assert_eq!(accesses[0].location, Location::Mmio(addr));
+    assert_eq!(accesses[0].width, 16);
+    assert_eq!(accesses[0].value, 0b101);
+    assert_eq!(accesses[0].direction, Direction::Read);
+
+    assert_eq!(accesses[1].field, Some("MODE"));
+    assert_eq!(accesses[1].direction, Direction::Read);
+    assert_eq!(accesses[2].field, Some("MODE"));
+    assert_eq!(accesses[2].value, 0b100);
+    assert_eq!(accesses[2].direction, Direction::Write);
+
+    assert_eq!(accesses[3].field, None);
+    assert_eq!(accesses[3].value, 0xABCD);
+    assert_eq!(
+        accesses[3].to_string(),
+        format!("write Reg @ {:#x} (u16) = 0xabcd", addr)
+    );
+}
+
+#[test]
+fn trace_cpu_accesses() {
+    let _guard = recorder();
+
+    cpu::EN::set(true);
+    assert!(cpu::EN::get());
+    cpu::set(0);
+
+    let accesses = recorded();
+    assert_eq!(accesses.len(), 3);
+
+    assert_eq!(accesses[0].register, "CpuRegister");
+    assert_eq!(accesses[0].field, Some("EN"));
+    assert!(matches!(accesses[0].location, Location::Cpu(ty) if ty.ends_with("CpuRegister")));
+    assert_eq!(accesses[0].width, 32);
+    assert_eq!(accesses[0].value, 0b10000);
+    assert_eq!(accesses[0].direction, Direction::Set);
+
+    assert_eq!(accesses[1].direction, Direction::Read);
+    assert_eq!(accesses[1].value, 0b10000);
+
+    assert_eq!(accesses[2].field, None);
+    assert_eq!(accesses[2].direction, Direction::Write);
+}
+
+rumio::define_mmio_struct! {
+    pub struct Uart {
+        (0x00 => ctrl: rumio::mmio::Lit<u32>),
+        (0x04 => div: [rumio::mmio::Lit<u16>; 2]),
+        (0x08 => data: rumio::mmio::Lit<u32>),
+    }
+
+    restore [div, ctrl]
+}
+
+#[test]
+fn restore_in_declared_order() {
+    let mut mem = [0u32; 3];
+    let base = mem.as_mut_ptr() as usize;
+    let uart = unsafe { Uart::new(base) };
+    let saved = uart.save();
+
+    let _guard = recorder();
+    uart.restore(&saved);
+
+    let writes = recorded()
+        .into_iter()
+        .filter(|a| a.direction == Direction::Write)
+        .map(|a| match a.location {
+            Location::Mmio(addr) => addr - base,
+            loc => panic!("unexpected location: {:?}", loc),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(writes, [0x08, 0x04, 0x06, 0x00]);
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "x86_64")]
+
+use rumio::{
+    meta::{Describe, Perm},
+    x86_64::{cr0, cr4, efer, perfevtsel, rflags},
+};
+
+rumio::define_msr! {
+    /// The `IA32_MISC_ENABLE` MSR.
+    pub struct MiscEnable = 0x1A0;
+}
+
+rumio::define_cpu_register! { MiscEnable as u64 =>
+    rw FAST_STRINGS: 0,
+    rw MONITOR: 18,
+}
+
+#[test]
+fn decode_registers() {
+    let decoded = cr0::decode((1 << 0) | (1 << 16) | (1 << 31));
+    assert!(decoded.to_string().contains("PE: true"));
+    assert!(decoded.to_string().contains("WP: true"));
+    assert!(decoded.to_string().contains("PG: true"));
+    assert!(decoded.to_string().contains("CD: false"));
+
+    let decoded = rflags::decode((1 << 9) | (0b11 << 12));
+    assert!(decoded.to_string().contains("IF: true"));
+    assert!(decoded.to_string().contains("IOPL: Ring3"));
+
+    let decoded = perfevtsel::decode(0x3C | (1 << 16) | (1 << 22));
+    assert!(decoded.to_string().contains("USR: true"));
+    assert!(decoded.to_string().contains("OS: false"));
+    assert!(decoded.to_string().contains("EN: true"));
+}
+
+#[test]
+fn metadata() {
+    let info = <efer::Efer as Describe>::INFO;
+    assert_eq!(info.width, 64);
+    assert_eq!(info.field("LMA").unwrap().perm, Perm::Read);
+    assert_eq!(info.field("NXE").unwrap().from, 11);
+
+    assert_eq!(<cr4::Cr4 as Describe>::INFO.field("LA57").unwrap().from, 12);
+    assert_eq!(<MiscEnable as Describe>::INFO.name, "MiscEnable");
+    assert_eq!(<MiscEnable as Describe>::INFO.fields.len(), 2);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn read_rflags() {
+    // the interrupt flag is always set in user mode
+    assert!(rflags::IF::get());
+}
+
+#[test]
+fn page_table_entries() {
+    use rumio::x86_64::paging::{self, PageTableEntry};
+
+    // `__PAGE_KERNEL` of Linux, mapping the physical page at `0x1000`
+    let pte = PageTableEntry::new(0)
+        .with_present(true)
+        .with_writable(true)
+        .with_accessed(true)
+        .with_dirty(true)
+        .with_global(true)
+        .with_no_execute(true)
+        .with_address(0x1000);
+    assert_eq!(pte.bits(), 0x8000_0000_0000_1163);
+
+    // `__PAGE_KERNEL_LARGE_EXEC`, a 2 MiB page at `0x20_0000`
+    let pde = PageTableEntry::new(0x0000_0000_0020_01E3);
+    assert!(pde.present() && pde.writable() && pde.huge_page() && pde.global());
+    assert!(!pde.user() && !pde.no_execute());
+    assert_eq!(pde.address(), 0x20_0000);
+
+    // the address and protection key never spill into the other fields
+    let pte = PageTableEntry::new(!0)
+        .with_address(0)
+        .with_protection_key(0);
+    assert_eq!(pte.bits(), 0x87F0_0000_0000_0FFF);
+    assert_eq!(PageTableEntry::new(0x5 << 59).protection_key(), 5);
+    assert_eq!(PageTableEntry::FRAME.read(!0), 0x000F_FFFF_FFFF_F000);
+    assert!(format!("{:?}", pde).contains("FRAME: 0x200, PROTECTION_KEY: 0x0"));
+
+    assert_eq!(paging::index(0xFFFF_8000_0000_0000, 3), 256);
+    assert_eq!(paging::index(0xFF11_0000_0000_0000, 4), 273);
+    assert_eq!(paging::index(0x20_1000, 1), 1);
+}
+
+#[cfg(all(feature = "std", not(target_arch = "x86_64")))]
+#[test]
+fn mock_registers() {
+    use rumio::{
+        cpu::mock::{Access, IndexedMock, Mock},
+        x86_64::{perfevtsel::PerfEvtSel, Msr},
+    };
+
+    cr0::PG::set(true);
+    assert_eq!(cr0::Cr0::value(), 1 << 31);
+
+    efer::LME::set(true);
+    assert_eq!(efer::Efer::value(), 1 << 8);
+    assert_eq!(Msr::<0xC000_0080>::value(), 1 << 8);
+
+    perfevtsel::EN::set(1, true);
+    assert_eq!(PerfEvtSel::value(1), 1 << 22);
+    assert_eq!(Msr::<0x187>::value(), 1 << 22);
+
+    MONITOR::set(true);
+    assert_eq!(MiscEnable::log(), [Access::Read(0), Access::Write(1 << 18)]);
+
+    rflags::Rflags::set_value(1 << 9);
+    assert!(rflags::IF::get());
+}